@@ -1,3 +1,42 @@
 
 #define CLAY_IMPLEMENTATION
 #include "clay.h"
+
+// Used by clay-layout to report measure text cache statistics
+int32_t Clay__GetMeasureTextCacheLength(void) {
+    return Clay_GetCurrentContext()->measureTextHashMapInternal.length;
+}
+
+int32_t Clay__GetMeasureTextCacheFreeCount(void) {
+    return Clay_GetCurrentContext()->measureTextHashMapInternalFreeList.length;
+}
+
+bool Clay__CapacityExceeded(void) {
+    Clay_BooleanWarnings warnings = Clay_GetCurrentContext()->booleanWarnings;
+    return warnings.maxElementsExceeded || warnings.maxRenderCommandsExceeded || warnings.maxTextMeasureCacheExceeded;
+}
+
+// Used by clay-layout to carry scroll positions over to a grown context
+bool Clay__GetScrollContainerPosition(int32_t index, uint32_t *elementId, Clay_Vector2 *position) {
+    Clay_Context* context = Clay_GetCurrentContext();
+    if (index >= context->scrollContainerDatas.length) return false;
+    Clay__ScrollContainerDataInternal *scrollContainerData = Clay__ScrollContainerDataInternalArray_Get(&context->scrollContainerDatas, index);
+    *elementId = scrollContainerData->elementId;
+    *position = scrollContainerData->scrollPosition;
+    return true;
+}
+
+// Used by clay-layout to find the scroll container of the open element, including anonymous ones
+uint32_t Clay__GetOpenElementId(void) {
+    return Clay__GetOpenLayoutElement()->id;
+}
+
+// Used by clay-layout to return the user data of an element declared in the current frame
+void *Clay__GetElementUserData(uint32_t id) {
+    Clay_Context* context = Clay_GetCurrentContext();
+    Clay_LayoutElementHashMapItem *item = Clay__GetHashMapItem(id);
+    // Elements of earlier frames point into memory that was reused since
+    if (item == &Clay_LayoutElementHashMapItem_DEFAULT || item->generation <= context->generation || !item->layoutElement) return NULL;
+    Clay_SharedElementConfig *sharedConfig = Clay__FindElementConfigWithType(item->layoutElement, CLAY__ELEMENT_CONFIG_TYPE_SHARED).sharedElementConfig;
+    return sharedConfig ? sharedConfig->userData : NULL;
+}
@@ -3,6 +3,8 @@ fn main() {
 
     // Make sure we re-run the build script if the clay.h file changes
     println!("cargo:rerun-if-changed=clay.h");
+    println!("cargo:rerun-if-changed=build.c");
+    println!("cargo:rerun-if-changed=build.cpp");
 
     if target_os == "windows" {
         cc::Build::new()
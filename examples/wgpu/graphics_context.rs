@@ -50,9 +50,7 @@ impl<'a> GraphicsContext<'a> {
         let surface_format = surface_capabilities
             .formats
             .iter()
-            .copied()
-            .filter(|f| f.is_srgb())
-            .next()
+            .copied().find(|f| f.is_srgb())
             .unwrap_or(surface_capabilities.formats[0]);
 
         let config = wgpu::SurfaceConfiguration {
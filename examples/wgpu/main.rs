@@ -4,15 +4,11 @@ use winit::event::{MouseScrollDelta, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
 use winit::window::{Window, WindowId};
 
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use clay_layout::renderers::WgpuRenderer;
 use clay_layout::Clay;
 
-use ui_renderer::UIState;
 mod graphics_context;
 mod ui_layout;
-mod ui_renderer;
 
 #[rustfmt::skip]
 fn main() {
@@ -31,7 +27,7 @@ use graphics_context::GraphicsContext;
 pub struct App<'a> {
     ctx: Option<GraphicsContext<'a>>,
 
-    pub ui_state: Option<Rc<RefCell<UIState>>>,
+    pub ui_renderer: Option<WgpuRenderer>,
     pub clay: Option<Clay>,
     pub clay_user_data: ui_layout::ClayState,
 }
@@ -48,23 +44,23 @@ impl<'a> ApplicationHandler for App<'a> {
 
         let ctx = GraphicsContext::new(window);
 
-        let ui_state = Rc::<RefCell<UIState>>::new(RefCell::new(UIState::new(
-            &ctx.device,
-            &ctx.queue,
-            ctx.config.format,
-            size,
-            dpi_scale,
-        )));
+        let ui_renderer = WgpuRenderer::new(&ctx.device, &ctx.queue, ctx.config.format);
 
-        let mut clay = Clay::new((size.width as f32, size.height as f32).into());
+        // Lay out in logical pixels, drawn dpi_scale physical pixels large
+        self.clay_user_data.size = (
+            size.width as f32 / dpi_scale,
+            size.height as f32 / dpi_scale,
+        );
+        let mut clay = Clay::new(self.clay_user_data.size.into());
         clay.set_debug_mode(false);
+        clay.set_scale_factor(dpi_scale);
 
-        clay.set_measure_text_function_user_data(ui_state.clone(), ui_layout::measure_text);
+        clay.set_measure_text_function(ui_renderer.measure_text_function());
 
         ui_layout::initialize_user_data(&mut self.clay_user_data);
 
         self.ctx = Some(ctx);
-        self.ui_state = Some(ui_state);
+        self.ui_renderer = Some(ui_renderer);
         self.clay = Some(clay);
     }
 
@@ -75,18 +71,20 @@ impl<'a> ApplicationHandler for App<'a> {
             }
             WindowEvent::Resized(size) => {
                 self.ctx.as_mut().unwrap().resize();
-                self.ui_state
-                    .as_mut()
-                    .unwrap()
-                    .borrow_mut()
-                    .resize((size.width as i32, size.height as i32));
-                self.clay_user_data.size = (size.width as f32, size.height as f32);
+                let dpi_scale = self.clay.as_ref().unwrap().scale_factor();
+                self.clay_user_data.size = (
+                    size.width as f32 / dpi_scale,
+                    size.height as f32 / dpi_scale,
+                );
             }
             WindowEvent::ScaleFactorChanged {
                 scale_factor,
                 inner_size_writer: _,
             } => {
-                self.ui_state.as_mut().unwrap().borrow_mut().dpi_scale = scale_factor as f32;
+                self.clay
+                    .as_ref()
+                    .unwrap()
+                    .set_scale_factor(scale_factor as f32);
             }
             WindowEvent::RedrawRequested => {
                 let render_commands = ui_layout::create_layout(
@@ -94,19 +92,22 @@ impl<'a> ApplicationHandler for App<'a> {
                     &mut self.clay_user_data,
                     0.016,
                 );
-                let mut ui_renderer = self.ui_state.as_mut().unwrap().borrow_mut();
+                let ui_renderer = self.ui_renderer.as_mut().unwrap();
 
                 self.ctx
                     .as_mut()
                     .unwrap()
-                    .render(|mut render_pass, device, queue, config| {
-                        ui_renderer.render_clay(
-                            render_commands,
-                            &mut render_pass,
-                            &device,
-                            &queue,
-                            &config,
-                        );
+                    .render(|render_pass, device, queue, config| {
+                        ui_renderer
+                            .prepare(
+                                device,
+                                queue,
+                                (config.width, config.height),
+                                render_commands,
+                                |_, _, _| {},
+                            )
+                            .unwrap();
+                        ui_renderer.render(render_pass).unwrap();
                     })
                     .unwrap();
                 self.clay_user_data.mouse_down_rising_edge = false;
@@ -115,13 +116,10 @@ impl<'a> ApplicationHandler for App<'a> {
             WindowEvent::MouseInput {
                 device_id: _,
                 state,
-                button,
-            } => match button {
-                winit::event::MouseButton::Left => {
-                    self.clay_user_data.mouse_down_rising_edge = state.is_pressed();
-                }
-                _ => {}
-            },
+                button: winit::event::MouseButton::Left,
+            } => {
+                self.clay_user_data.mouse_down_rising_edge = state.is_pressed();
+            }
             WindowEvent::MouseWheel {
                 device_id: _,
                 delta,
@@ -136,7 +134,9 @@ impl<'a> ApplicationHandler for App<'a> {
                 device_id: _,
                 position,
             } => {
-                self.clay_user_data.mouse_position = position.into();
+                let dpi_scale = self.clay.as_ref().unwrap().scale_factor();
+                self.clay_user_data.mouse_position =
+                    (position.x as f32 / dpi_scale, position.y as f32 / dpi_scale);
             }
             _ => (),
         }
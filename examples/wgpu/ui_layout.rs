@@ -2,9 +2,9 @@ use clay_layout::{
     elements::FloatingAttachToElement,
     fixed, grow,
     layout::{Alignment, LayoutDirection::TopToBottom, Padding},
-    math::Dimensions,
     percent,
     render_commands::RenderCommand,
+    renderers::wgpu::WgpuImage,
     text::TextConfig,
     Clay, ClayLayoutScope, Color, Declaration,
 };
@@ -45,7 +45,7 @@ fn render_header_button<'a, ImageElementData: 'a, CustomElementData: 'a>(
     text: &str,
 ) {
     clay.with(
-        &Declaration::new()
+        Declaration::new()
             .layout()
             .padding(Padding::new(16, 16, 8, 8))
             .end()
@@ -64,7 +64,7 @@ fn render_dropdown_menu_item<'a, ImageElementData: 'a, CustomElementData: 'a>(
     text: &str,
 ) {
     clay.with(
-        &Declaration::new().layout().padding(Padding::all(16)).end(),
+        Declaration::new().layout().padding(Padding::all(16)).end(),
         |clay| {
             clay.text(text, TextConfig::new().font_size(16).color(WHITE).end());
         },
@@ -102,15 +102,15 @@ pub fn create_layout<'render>(
     clay: &'render mut Clay,
     user_data: &mut ClayState,
     time_delta: f32,
-) -> impl Iterator<Item = RenderCommand<'render, (), ()>> {
+) -> impl Iterator<Item = RenderCommand<'render, WgpuImage, ()>> {
     clay.set_layout_dimensions(user_data.size.into());
     clay.pointer_state(user_data.mouse_position.into(), false);
     clay.update_scroll_containers(false, user_data.scroll_delta.into(), time_delta);
 
-    let mut clay = clay.begin::<(), ()>();
+    let mut clay = clay.begin::<WgpuImage, ()>();
 
     clay.with(
-        &Declaration::new()
+        Declaration::new()
             .layout()
             .width(grow!())
             .height(grow!())
@@ -124,7 +124,7 @@ pub fn create_layout<'render>(
             .background_color(Color::rgb(43.0, 41.0, 51.0)),
         |clay| {
             clay.with(
-                &Declaration::new()
+                Declaration::new()
                     .content_background_config()
                     .id(clay.id("header_bar"))
                     .layout()
@@ -141,7 +141,7 @@ pub fn create_layout<'render>(
                     .end(),
                 |clay| {
                     clay.with(
-                        &Declaration::new()
+                        Declaration::new()
                             .id(clay.id("file_button"))
                             .layout()
                             .padding(Padding {
@@ -163,7 +163,7 @@ pub fn create_layout<'render>(
 
                             if file_menu_visible {
                                 clay.with(
-                                    &Declaration::new()
+                                    Declaration::new()
                                         .id(clay.id("file_menu"))
                                         .floating()
                                         .attach_to(FloatingAttachToElement::Parent)
@@ -173,7 +173,7 @@ pub fn create_layout<'render>(
                                         .end(),
                                     |clay| {
                                         clay.with(
-                                            &Declaration::new()
+                                            Declaration::new()
                                                 .layout()
                                                 .direction(TopToBottom)
                                                 .width(fixed!(200.0))
@@ -195,7 +195,7 @@ pub fn create_layout<'render>(
                     );
 
                     render_header_button(clay, "Edit");
-                    clay.with(&Declaration::new().layout().width(grow!()).end(), |_| {});
+                    clay.with(Declaration::new().layout().width(grow!()).end(), |_| {});
                     render_header_button(clay, "Upload");
                     render_header_button(clay, "Media");
                     render_header_button(clay, "Support");
@@ -203,7 +203,7 @@ pub fn create_layout<'render>(
             );
 
             clay.with(
-                &Declaration::new()
+                Declaration::new()
                     .layout_expand()
                     .id(clay.id("lower_content"))
                     .layout()
@@ -211,7 +211,7 @@ pub fn create_layout<'render>(
                     .end(),
                 |clay| {
                     clay.with(
-                        &Declaration::new()
+                        Declaration::new()
                             .content_background_config()
                             .id(clay.id("sidebar"))
                             .layout()
@@ -224,7 +224,7 @@ pub fn create_layout<'render>(
                         |clay| {
                             for i in 0..user_data.documents.len() {
                                 let document = user_data.documents.get_mut(i).unwrap();
-                                let mut side_bar_button_layout: Declaration<'_, (), ()> =
+                                let mut side_bar_button_layout: Declaration<'_, WgpuImage, ()> =
                                     Declaration::new()
                                         .layout()
                                         .width(grow!())
@@ -329,11 +329,3 @@ pub fn create_layout<'render>(
 
     clay.end()
 }
-
-use crate::UIState;
-use std::{cell::RefCell, rc::Rc};
-
-pub fn measure_text(text: &str, config: &TextConfig, ui: &mut Rc<RefCell<UIState>>) -> Dimensions {
-    ui.borrow_mut()
-        .measure_text(text, config.font_size as f32, config.line_height as f32)
-}
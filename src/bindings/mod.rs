@@ -5,4 +5,21 @@
 
 mod bindings;
 
-pub use self::bindings::*;
\ No newline at end of file
+pub use self::bindings::*;
+// Helpers defined next to the clay implementation in build.c / build.cpp
+unsafe extern "C" {
+    pub static CLAY__SPACECHAR: Clay_String;
+    pub fn Clay__GetMeasureTextCacheLength() -> i32;
+    pub fn Clay__GetMeasureTextCacheFreeCount() -> i32;
+    /// Whether clay ran out of elements, render commands or text measurement capacity this frame
+    pub fn Clay__CapacityExceeded() -> bool;
+    pub fn Clay_SetExternalScrollHandlingEnabled(enabled: bool);
+    pub fn Clay__GetScrollContainerPosition(
+        index: i32,
+        element_id: *mut u32,
+        position: *mut Clay_Vector2,
+    ) -> bool;
+    pub fn Clay__GetOpenElementId() -> u32;
+    /// The user data of an element declared in the current frame, or null
+    pub fn Clay__GetElementUserData(id: u32) -> *mut core::ffi::c_void;
+}
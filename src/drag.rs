@@ -0,0 +1,240 @@
+//! Dragging elements and dropping them onto other elements.
+//!
+//! A [`DragAndDrop`] is updated once per frame after the pointer state was set. During the layout,
+//! elements become drag sources with [`DragAndDrop::source`] and drop targets with
+//! [`DragAndDrop::target`], and [`DragAndDrop::ghost`] declares the element that follows the
+//! pointer. Like the other pointer functions, hit testing uses the last layout.
+
+use crate::{
+    elements::{FloatingAttachToElement, PointerCaptureMode},
+    id::Id,
+    layout::{LayoutDirection, Sizing},
+    math::{Dimensions, Vector2},
+    Clay, ClayLayoutScope, Declaration,
+};
+
+/// A payload dropped onto a target, passed to the callback of [`DragAndDrop::update`].
+#[derive(Debug, Clone)]
+pub struct DropEvent<Payload> {
+    /// The payload of the source the drag started from.
+    pub payload: Payload,
+    /// The element the drag started from.
+    pub source: Id,
+    /// The target the payload was dropped onto.
+    pub target: Id,
+    /// Where the payload was dropped among the items of the target, see
+    /// [`DragAndDrop::target`].
+    pub index: usize,
+}
+
+struct Drag<Payload> {
+    source: Id,
+    payload: Payload,
+    /// Pointer position when the source was pressed
+    origin: Vector2,
+    /// Pointer position relative to the source
+    grab: Vector2,
+    size: Dimensions,
+    /// Whether the pointer moved far enough to be a drag instead of a click
+    active: bool,
+}
+
+/// Tracks one drag at a time, carrying a payload of type `Payload` from a source to a target.
+pub struct DragAndDrop<Payload> {
+    /// How far the pointer has to move while pressed on a source before a drag starts.
+    pub threshold: f32,
+    drag: Option<Drag<Payload>>,
+    /// The target hovered in the last layout and the insertion index over it
+    hover: Option<(Id, usize)>,
+    pressed: bool,
+    was_down: bool,
+}
+
+impl<Payload> Default for DragAndDrop<Payload> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Payload> DragAndDrop<Payload> {
+    /// Creates a manager that starts drags after the pointer moved 4 units.
+    pub fn new() -> Self {
+        Self {
+            threshold: 4.0,
+            drag: None,
+            hover: None,
+            pressed: false,
+            was_down: false,
+        }
+    }
+
+    /// Updates the drag from the pointer state of `clay`. Call it once per frame before the
+    /// layout. When the pointer is released over a target, `on_drop` receives the payload.
+    pub fn update(&mut self, clay: &Clay, on_drop: impl FnOnce(DropEvent<Payload>)) {
+        let position = clay.pointer_position();
+        let is_down = clay.is_pointer_down();
+        self.pressed = is_down && !self.was_down;
+        self.was_down = is_down;
+        let hover = self.hover.take();
+
+        let Some(drag) = &mut self.drag else {
+            return;
+        };
+        if is_down {
+            let (dx, dy) = (position.x - drag.origin.x, position.y - drag.origin.y);
+            drag.active |= dx * dx + dy * dy >= self.threshold * self.threshold;
+            return;
+        }
+
+        let drag = self.drag.take().unwrap();
+        if let (true, Some((target, index))) = (drag.active, hover) {
+            on_drop(DropEvent {
+                payload: drag.payload,
+                source: drag.source,
+                target,
+                index,
+            });
+        }
+    }
+
+    /// Makes the element with `id` a drag source. Pressing the pointer on it prepares a drag
+    /// with the payload returned by `payload`, which starts once the pointer moves.
+    ///
+    /// Call it while declaring the element. Of nested sources, the innermost one is dragged.
+    pub fn source(&mut self, clay: &Clay, id: Id, payload: impl FnOnce() -> Payload) {
+        if !self.pressed || self.is_dragging() || !clay.pointer_over(id) {
+            return;
+        }
+        let Some(bounds) = clay.bounding_box(id) else {
+            return;
+        };
+        let origin = clay.pointer_position();
+        self.drag = Some(Drag {
+            source: id,
+            payload: payload(),
+            origin,
+            grab: Vector2::new(origin.x - bounds.x, origin.y - bounds.y),
+            size: Dimensions::new(bounds.width, bounds.height),
+            active: false,
+        });
+    }
+
+    /// Makes the element with `id` a drop target whose `items` are stacked in `direction`.
+    ///
+    /// Returns the index among `items` the payload would be inserted at while a drag hovers the
+    /// target, which can be used to highlight it or to show a placeholder. Of nested targets,
+    /// the innermost one receives the drop.
+    pub fn target(
+        &mut self,
+        clay: &Clay,
+        id: Id,
+        direction: LayoutDirection,
+        items: impl IntoIterator<Item = Id>,
+    ) -> Option<usize> {
+        if !self.is_dragging() || !clay.pointer_over(id) {
+            return None;
+        }
+        let position = clay.pointer_position();
+        let index = insertion_index(
+            match direction {
+                LayoutDirection::LeftToRight => position.x,
+                LayoutDirection::TopToBottom => position.y,
+            },
+            items
+                .into_iter()
+                .filter_map(|item| clay.bounding_box(item))
+                .map(|bounds| match direction {
+                    LayoutDirection::LeftToRight => bounds.x + bounds.width / 2.0,
+                    LayoutDirection::TopToBottom => bounds.y + bounds.height / 2.0,
+                }),
+        );
+        self.hover = Some((id, index));
+        Some(index)
+    }
+
+    /// Declares the ghost of the dragged element from `declaration`, with `f` adding its
+    /// children. The ghost floats over the root with the size of the source, keeping the point
+    /// the source was grabbed at under the pointer, and lets the pointer through to the targets
+    /// below.
+    pub fn ghost<'clay, 'render, ImageElementData, CustomElementData, UserData, F>(
+        &self,
+        scope: &mut ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>,
+        declaration: &Declaration<'render, ImageElementData, CustomElementData, UserData>,
+        f: F,
+    ) where
+        'clay: 'render,
+        F: FnOnce(
+            &mut ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>,
+            &Payload,
+        ),
+    {
+        let Some(drag) = self.drag.as_ref().filter(|drag| drag.active) else {
+            return;
+        };
+        let position = scope.pointer_position();
+        let mut declaration = *declaration;
+        declaration
+            .floating()
+            .attach_to(FloatingAttachToElement::Root)
+            .pointer_capture_mode(PointerCaptureMode::Passthrough)
+            .offset(Vector2::new(
+                position.x - drag.grab.x,
+                position.y - drag.grab.y,
+            ))
+            .end()
+            .layout()
+            .width(Sizing::Fixed(drag.size.width))
+            .height(Sizing::Fixed(drag.size.height));
+        scope.with(&declaration, |scope| f(scope, &drag.payload));
+    }
+
+    /// Returns if a drag is in progress.
+    pub fn is_dragging(&self) -> bool {
+        self.drag.as_ref().is_some_and(|drag| drag.active)
+    }
+
+    /// Returns the element being dragged.
+    pub fn dragged(&self) -> Option<Id> {
+        self.drag
+            .as_ref()
+            .filter(|drag| drag.active)
+            .map(|drag| drag.source)
+    }
+
+    /// Returns the payload being dragged.
+    pub fn payload(&self) -> Option<&Payload> {
+        self.drag
+            .as_ref()
+            .filter(|drag| drag.active)
+            .map(|drag| &drag.payload)
+    }
+
+    /// Stops the current drag without dropping it.
+    pub fn cancel(&mut self) {
+        self.drag = None;
+        self.hover = None;
+    }
+}
+
+/// Counts the items whose center lies before the pointer, which is where a payload dropped at
+/// `pointer` goes.
+fn insertion_index(pointer: f32, centers: impl Iterator<Item = f32>) -> usize {
+    centers.filter(|center| *center < pointer).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inserts_before_the_first_item_past_the_pointer() {
+        // Items of 20 starting at 0, 20 and 40
+        let centers = || [10.0, 30.0, 50.0].into_iter();
+        assert_eq!(insertion_index(-5.0, centers()), 0);
+        assert_eq!(insertion_index(9.0, centers()), 0);
+        assert_eq!(insertion_index(11.0, centers()), 1);
+        assert_eq!(insertion_index(45.0, centers()), 2);
+        assert_eq!(insertion_index(500.0, centers()), 3);
+        assert_eq!(insertion_index(5.0, core::iter::empty()), 0);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{bindings::*, color::Color, Declaration, Dimensions, Vector2};
+use crate::{bindings::*, color::Color, scroll::ScrollbarConfig, Declaration, Dimensions, Vector2};
 
 /// Builder for configuring border properties of a `Declaration`.
 pub struct BorderBuilder<
@@ -6,17 +6,28 @@ pub struct BorderBuilder<
     'render,
     ImageElementData: 'render,
     CustomElementData: 'render,
+    UserData: 'render,
 > {
-    parent: &'declaration mut Declaration<'render, ImageElementData, CustomElementData>,
+    parent: &'declaration mut Declaration<'render, ImageElementData, CustomElementData, UserData>,
 }
 
-impl<'declaration, 'render, ImageElementData: 'render, CustomElementData: 'render>
-    BorderBuilder<'declaration, 'render, ImageElementData, CustomElementData>
+impl<
+        'declaration,
+        'render,
+        ImageElementData: 'render,
+        CustomElementData: 'render,
+        UserData: 'render,
+    > BorderBuilder<'declaration, 'render, ImageElementData, CustomElementData, UserData>
 {
     /// Creates a new `BorderBuilder` with the given parent `Declaration`.
     #[inline]
     pub fn new(
-        parent: &'declaration mut Declaration<'render, ImageElementData, CustomElementData>,
+        parent: &'declaration mut Declaration<
+            'render,
+            ImageElementData,
+            CustomElementData,
+            UserData,
+        >,
     ) -> Self {
         BorderBuilder { parent }
     }
@@ -75,7 +86,9 @@ impl<'declaration, 'render, ImageElementData: 'render, CustomElementData: 'rende
 
     /// Returns the modified `Declaration`.
     #[inline]
-    pub fn end(&mut self) -> &mut Declaration<'render, ImageElementData, CustomElementData> {
+    pub fn end(
+        &mut self,
+    ) -> &mut Declaration<'render, ImageElementData, CustomElementData, UserData> {
         self.parent
     }
 }
@@ -86,17 +99,28 @@ pub struct ImageBuilder<
     'render,
     ImageElementData: 'render,
     CustomElementData: 'render,
+    UserData: 'render,
 > {
-    parent: &'declaration mut Declaration<'render, ImageElementData, CustomElementData>,
+    parent: &'declaration mut Declaration<'render, ImageElementData, CustomElementData, UserData>,
 }
 
-impl<'declaration, 'render, ImageElementData: 'render, CustomElementData: 'render>
-    ImageBuilder<'declaration, 'render, ImageElementData, CustomElementData>
+impl<
+        'declaration,
+        'render,
+        ImageElementData: 'render,
+        CustomElementData: 'render,
+        UserData: 'render,
+    > ImageBuilder<'declaration, 'render, ImageElementData, CustomElementData, UserData>
 {
     /// Creates a new `ImageBuilder` with the given parent `Declaration`.
     #[inline]
     pub fn new(
-        parent: &'declaration mut Declaration<'render, ImageElementData, CustomElementData>,
+        parent: &'declaration mut Declaration<
+            'render,
+            ImageElementData,
+            CustomElementData,
+            UserData,
+        >,
     ) -> Self {
         ImageBuilder { parent }
     }
@@ -110,7 +134,9 @@ impl<'declaration, 'render, ImageElementData: 'render, CustomElementData: 'rende
     }
     /// Returns the modified `Declaration`.
     #[inline]
-    pub fn end(&mut self) -> &mut Declaration<'render, ImageElementData, CustomElementData> {
+    pub fn end(
+        &mut self,
+    ) -> &mut Declaration<'render, ImageElementData, CustomElementData, UserData> {
         self.parent
     }
 }
@@ -163,23 +189,44 @@ pub enum FloatingAttachToElement {
     Root = Clay_FloatingAttachToElement_CLAY_ATTACH_TO_ROOT,
 }
 
+/// Defines which clipping a floating element inherits.
+#[derive(Debug, Clone)]
+#[repr(u8)]
+pub enum FloatingClipToElement {
+    /// The floating element is not clipped.
+    None = Clay_FloatingClipToElement_CLAY_CLIP_TO_NONE,
+    /// The floating element is clipped like the element it is attached to.
+    AttachedParent = Clay_FloatingClipToElement_CLAY_CLIP_TO_ATTACHED_PARENT,
+}
+
 /// Builder for configuring floating element properties in a `Declaration`.
 pub struct FloatingBuilder<
     'declaration,
     'render,
     ImageElementData: 'render,
     CustomElementData: 'render,
+    UserData: 'render,
 > {
-    parent: &'declaration mut Declaration<'render, ImageElementData, CustomElementData>,
+    parent: &'declaration mut Declaration<'render, ImageElementData, CustomElementData, UserData>,
 }
 
-impl<'declaration, 'render, ImageElementData: 'render, CustomElementData: 'render>
-    FloatingBuilder<'declaration, 'render, ImageElementData, CustomElementData>
+impl<
+        'declaration,
+        'render,
+        ImageElementData: 'render,
+        CustomElementData: 'render,
+        UserData: 'render,
+    > FloatingBuilder<'declaration, 'render, ImageElementData, CustomElementData, UserData>
 {
     /// Creates a new `FloatingBuilder` with the given parent `Declaration`.
     #[inline]
     pub fn new(
-        parent: &'declaration mut Declaration<'render, ImageElementData, CustomElementData>,
+        parent: &'declaration mut Declaration<
+            'render,
+            ImageElementData,
+            CustomElementData,
+            UserData,
+        >,
     ) -> Self {
         FloatingBuilder { parent }
     }
@@ -236,6 +283,13 @@ impl<'declaration, 'render, ImageElementData: 'render, CustomElementData: 'rende
         self
     }
 
+    /// Sets which clipping the floating element inherits.
+    #[inline]
+    pub fn clip_to(&mut self, clip: FloatingClipToElement) -> &mut Self {
+        self.parent.inner.floating.clipTo = clip as _;
+        self
+    }
+
     /// Sets the pointer capture mode.
     #[inline]
     pub fn pointer_capture_mode(&mut self, mode: PointerCaptureMode) -> &mut Self {
@@ -245,7 +299,9 @@ impl<'declaration, 'render, ImageElementData: 'render, CustomElementData: 'rende
 
     /// Returns the modified `Declaration`.
     #[inline]
-    pub fn end(&mut self) -> &mut Declaration<'render, ImageElementData, CustomElementData> {
+    pub fn end(
+        &mut self,
+    ) -> &mut Declaration<'render, ImageElementData, CustomElementData, UserData> {
         self.parent
     }
 }
@@ -256,17 +312,28 @@ pub struct CornerRadiusBuilder<
     'render,
     ImageElementData: 'render,
     CustomElementData: 'render,
+    UserData: 'render,
 > {
-    parent: &'declaration mut Declaration<'render, ImageElementData, CustomElementData>,
+    parent: &'declaration mut Declaration<'render, ImageElementData, CustomElementData, UserData>,
 }
 
-impl<'declaration, 'render, ImageElementData: 'render, CustomElementData: 'render>
-    CornerRadiusBuilder<'declaration, 'render, ImageElementData, CustomElementData>
+impl<
+        'declaration,
+        'render,
+        ImageElementData: 'render,
+        CustomElementData: 'render,
+        UserData: 'render,
+    > CornerRadiusBuilder<'declaration, 'render, ImageElementData, CustomElementData, UserData>
 {
     /// Creates a new `CornerRadiusBuilder` with the given parent `Declaration`.
     #[inline]
     pub fn new(
-        parent: &'declaration mut Declaration<'render, ImageElementData, CustomElementData>,
+        parent: &'declaration mut Declaration<
+            'render,
+            ImageElementData,
+            CustomElementData,
+            UserData,
+        >,
     ) -> Self {
         CornerRadiusBuilder { parent }
     }
@@ -311,7 +378,116 @@ impl<'declaration, 'render, ImageElementData: 'render, CustomElementData: 'rende
 
     /// Returns the modified `Declaration`.
     #[inline]
-    pub fn end(&mut self) -> &mut Declaration<'render, ImageElementData, CustomElementData> {
+    pub fn end(
+        &mut self,
+    ) -> &mut Declaration<'render, ImageElementData, CustomElementData, UserData> {
+        self.parent
+    }
+}
+
+/// Builder for the scrollbars of a clip container in a `Declaration`.
+pub struct ScrollbarBuilder<
+    'declaration,
+    'render,
+    ImageElementData: 'render,
+    CustomElementData: 'render,
+    UserData: 'render,
+> {
+    parent: &'declaration mut Declaration<'render, ImageElementData, CustomElementData, UserData>,
+}
+
+impl<
+        'declaration,
+        'render,
+        ImageElementData: 'render,
+        CustomElementData: 'render,
+        UserData: 'render,
+    > ScrollbarBuilder<'declaration, 'render, ImageElementData, CustomElementData, UserData>
+{
+    /// Creates a new `ScrollbarBuilder`, enabling scrollbars with the default styling.
+    #[inline]
+    pub fn new(
+        parent: &'declaration mut Declaration<
+            'render,
+            ImageElementData,
+            CustomElementData,
+            UserData,
+        >,
+    ) -> Self {
+        parent
+            .scrollbar
+            .get_or_insert_with(ScrollbarConfig::default);
+        ScrollbarBuilder { parent }
+    }
+
+    #[inline]
+    fn config(&mut self) -> &mut ScrollbarConfig {
+        self.parent
+            .scrollbar
+            .get_or_insert_with(ScrollbarConfig::default)
+    }
+
+    /// Sets the thickness of the scrollbars.
+    #[inline]
+    pub fn width(&mut self, width: f32) -> &mut Self {
+        self.config().width = width;
+        self
+    }
+
+    /// Sets the space between the scrollbars and the edges of the container.
+    #[inline]
+    pub fn padding(&mut self, padding: f32) -> &mut Self {
+        self.config().padding = padding;
+        self
+    }
+
+    /// Sets the length the thumb doesn't shrink below.
+    #[inline]
+    pub fn min_thumb_length(&mut self, length: f32) -> &mut Self {
+        self.config().min_thumb_length = length;
+        self
+    }
+
+    /// Sets the corner radius of the track and the thumb.
+    #[inline]
+    pub fn corner_radius(&mut self, radius: f32) -> &mut Self {
+        self.config().corner_radius = radius;
+        self
+    }
+
+    /// Sets the color of the track.
+    #[inline]
+    pub fn track_color(&mut self, color: Color) -> &mut Self {
+        self.config().track_color = color;
+        self
+    }
+
+    /// Sets the color of the thumb.
+    #[inline]
+    pub fn thumb_color(&mut self, color: Color) -> &mut Self {
+        self.config().thumb_color = color;
+        self
+    }
+
+    /// Sets the color of the thumb while it is hovered or dragged.
+    #[inline]
+    pub fn active_thumb_color(&mut self, color: Color) -> &mut Self {
+        self.config().active_thumb_color = color;
+        self
+    }
+
+    /// Sets the Z-index of the scrollbars, which should be at least the one of the container.
+    #[inline]
+    pub fn z_index(&mut self, z_index: i16) -> &mut Self {
+        self.config().z_index = z_index;
+        self
+    }
+
+    /// Returns the modified `Declaration`.
+    #[inline]
+    pub fn end(
+        &mut self,
+    ) -> &mut Declaration<'render, ImageElementData, CustomElementData, UserData> {
         self.parent
     }
 }
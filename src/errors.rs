@@ -17,7 +17,11 @@ pub enum ErrorType {
     /// found
     FloatingContainerParentNotFound =
         Clay_ErrorType_CLAY_ERROR_TYPE_FLOATING_CONTAINER_PARENT_NOT_FOUND,
+    /// Thrown if a percentage sizing is larger than 1
+    PercentageOver1 = Clay_ErrorType_CLAY_ERROR_TYPE_PERCENTAGE_OVER_1,
     InternalError = Clay_ErrorType_CLAY_ERROR_TYPE_INTERNAL_ERROR,
+    /// Thrown if elements are still open when the layout ends
+    UnbalancedOpenClose = Clay_ErrorType_CLAY_ERROR_TYPE_UNBALANCED_OPEN_CLOSE,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -34,3 +38,16 @@ impl From<Clay_ErrorData> for Error<'_> {
         }
     }
 }
+
+/// Why a frame ended incomplete, see [`Clay::set_auto_grow`](crate::Clay::set_auto_grow).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutError {
+    /// Clay ran out of capacity and grew its memory, the frame has to be declared again
+    Grown,
+    /// Clay ran out of capacity and needs `required_memory` bytes to grow, more than the
+    /// `max_memory` it may use
+    CapacityExceeded {
+        required_memory: usize,
+        max_memory: usize,
+    },
+}
@@ -0,0 +1,261 @@
+//! Flow layouts that wrap their children onto new lines, like `flex-wrap`.
+//!
+//! Items are measured in the last layout and broken into lines that fit the container of the
+//! last layout. Each item is placed in a container clipping along the lines, which keeps clay
+//! from shrinking it, so items are measured at their natural size. Lines are regular child
+//! elements, so a container that fits its content grows with the number of lines.
+
+use crate::{
+    bindings::*,
+    id::Id,
+    layout::{LayoutDirection, Sizing},
+    math::Vector2,
+    ClayLayoutScope, Declaration,
+};
+
+/// Length of the line being filled.
+#[derive(Debug, Clone, Copy, Default)]
+struct Line {
+    length: f32,
+    items: usize,
+}
+
+impl Line {
+    /// Adds an item of `length`, returns `false` if it has to start a new line instead. The
+    /// first item of a line always fits.
+    fn push(&mut self, length: f32, gap: f32, available: f32) -> bool {
+        if self.items == 0 {
+            *self = Line { length, items: 1 };
+            return true;
+        }
+        let length = self.length + gap + length;
+        if length > available {
+            return false;
+        }
+        self.length = length;
+        self.items += 1;
+        true
+    }
+}
+
+/// Adds the items of a flow created with [`ClayLayoutScope::flow`].
+pub struct FlowScope<'scope, 'clay, 'render, ImageElementData, CustomElementData, UserData> {
+    scope:
+        &'scope mut ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>,
+    container: u32,
+    horizontal: bool,
+    available: f32,
+    line_declaration: Clay_ElementDeclaration,
+    line: Line,
+    index: u32,
+}
+
+impl<
+        'render,
+        'clay: 'render,
+        ImageElementData: 'render,
+        CustomElementData: 'render,
+        UserData: 'render,
+    > FlowScope<'_, 'clay, 'render, ImageElementData, CustomElementData, UserData>
+{
+    /// Creates an item, passing its config and a function to add its children. Items without an
+    /// id get one, so they can be measured for the next layout.
+    ///
+    /// Items are laid out at their natural size along the lines and clipped if a single item
+    /// is longer than a line.
+    pub fn item<
+        F: FnOnce(&mut ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>),
+    >(
+        &mut self,
+        declaration: &Declaration<'render, ImageElementData, CustomElementData, UserData>,
+        f: F,
+    ) {
+        let mut declaration = *declaration;
+        let id = *declaration.id.get_or_insert(Id {
+            id: unsafe {
+                Clay__HashStringWithOffset("clay_rs__flow_item".into(), self.index, self.container)
+            },
+        });
+        let mut slot = Declaration::new();
+        slot.id(Id {
+            id: unsafe {
+                Clay__HashStringWithOffset("clay_rs__flow_slot".into(), self.index, self.container)
+            },
+        })
+        .layout()
+        .width(Sizing::Fit(0.0, f32::MAX))
+        .height(Sizing::Fit(0.0, f32::MAX))
+        .end()
+        // Clipping keeps clay from shrinking the item, so it can be measured for the next layout
+        .clip(self.horizontal, !self.horizontal, Vector2::default());
+        self.index += 1;
+
+        let length = self.scope.clay.bounding_box(id).map_or(0.0, |bounds| {
+            if self.horizontal {
+                bounds.width
+            } else {
+                bounds.height
+            }
+        });
+        let gap = self.line_declaration.layout.childGap as f32;
+        if !self.line.push(length, gap, self.available) {
+            unsafe { Clay__CloseElement() };
+            self.line = Line::default();
+            self.line.push(length, gap, self.available);
+        }
+        if self.line.items == 1 {
+            unsafe {
+                Clay__OpenElement();
+                Clay__ConfigureOpenElement(self.line_declaration);
+            }
+        }
+
+        self.scope.with(&slot, |scope| scope.with(&declaration, f));
+    }
+}
+
+impl<
+        'render,
+        'clay: 'render,
+        ImageElementData: 'render,
+        CustomElementData: 'render,
+        UserData: 'render,
+    > ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>
+{
+    /// Creates a container from `declaration` whose items, added with [`FlowScope::item`], wrap
+    /// onto new lines when the current line is full.
+    ///
+    /// Lines run in the layout direction of `declaration` and are separated by `line_gap`, the
+    /// items of a line by the `child_gap` of `declaration`.
+    pub fn flow<F>(
+        &mut self,
+        declaration: &Declaration<'render, ImageElementData, CustomElementData, UserData>,
+        line_gap: u16,
+        f: F,
+    ) where
+        F: FnOnce(
+            &mut FlowScope<'_, 'clay, 'render, ImageElementData, CustomElementData, UserData>,
+        ),
+    {
+        let layout = declaration.inner.layout;
+        let horizontal = layout.layoutDirection == LayoutDirection::LeftToRight as _;
+        let root = self.clay.layout_dimensions.get();
+
+        // Lines stack across the direction of the items
+        let mut container = *declaration;
+        container
+            .layout()
+            .direction(if horizontal {
+                LayoutDirection::TopToBottom
+            } else {
+                LayoutDirection::LeftToRight
+            })
+            .child_gap(line_gap);
+
+        let mut line = Declaration::<(), ()>::new();
+        line.layout()
+            .direction(if horizontal {
+                LayoutDirection::LeftToRight
+            } else {
+                LayoutDirection::TopToBottom
+            })
+            .child_gap(layout.childGap);
+        line.inner.layout.childAlignment = layout.childAlignment;
+        let fit = Sizing::Fit(0.0, f32::MAX);
+        line.layout().width(fit).height(fit);
+
+        self.with(&container, |scope| {
+            let id = unsafe { Clay__GetOpenElementId() };
+            // Before the container was laid out once, the root is the best guess for its size
+            let available = match scope.clay.bounding_box(Id::from_raw(id)) {
+                Some(bounds) if horizontal => {
+                    bounds.width - (layout.padding.left + layout.padding.right) as f32
+                }
+                Some(bounds) => bounds.height - (layout.padding.top + layout.padding.bottom) as f32,
+                None if horizontal => root.width,
+                None => root.height,
+            };
+
+            let mut flow = FlowScope {
+                scope,
+                container: id,
+                horizontal,
+                available,
+                line_declaration: line.inner,
+                line: Line::default(),
+                index: 0,
+            };
+            f(&mut flow);
+
+            if flow.line.items > 0 {
+                unsafe { Clay__CloseElement() };
+            }
+        });
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::{
+        layout::{LayoutDirection, Sizing},
+        math::Dimensions,
+        render_commands::RenderCommandConfig,
+        text::TextConfig,
+        Clay, Declaration,
+    };
+
+    fn measure_ten_per_char(text: &str, _: &TextConfig) -> Dimensions {
+        Dimensions::new(text.chars().count() as f32 * 10.0, 16.0)
+    }
+
+    /// Lays out `items` fit items holding "hello world" in a 300 wide flow, returning the
+    /// position of every text
+    fn flow_frame(clay: &mut Clay, items: usize) -> Vec<(String, f32, f32)> {
+        let mut scope = clay.begin::<(), ()>();
+        let mut container = Declaration::new();
+        container
+            .id(scope.id("flow"))
+            .layout()
+            .width(Sizing::Fixed(300.0))
+            .direction(LayoutDirection::LeftToRight)
+            .child_gap(10);
+        scope.flow(&container, 5, |flow| {
+            for _ in 0..items {
+                flow.item(&Declaration::new(), |scope| {
+                    scope.text("hello world", TextConfig::new().end())
+                });
+            }
+        });
+        scope
+            .end()
+            .filter_map(|command| match command.config {
+                RenderCommandConfig::Text(text) => Some((
+                    text.text.to_owned(),
+                    command.bounding_box.x,
+                    command.bounding_box.y,
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn items_wrap_at_their_natural_size() {
+        let _context = crate::tests::lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        clay.set_measure_text_function(measure_ten_per_char);
+
+        // Once the flow has a size, new items aren't measured yet, so they all start on one line
+        // and are squeezed by clay
+        flow_frame(&mut clay, 0);
+        flow_frame(&mut clay, 6);
+        let expected: Vec<_> = [(0.0, 0.0), (120.0, 0.0), (0.0, 21.0)]
+            .iter()
+            .chain(&[(120.0, 21.0), (0.0, 42.0), (120.0, 42.0)])
+            .map(|&(x, y)| ("hello world".to_owned(), x, y))
+            .collect();
+        for _ in 0..3 {
+            assert_eq!(flow_frame(&mut clay, 6), expected);
+        }
+    }
+}
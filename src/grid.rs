@@ -0,0 +1,376 @@
+//! Grid layouts with explicit column and row tracks.
+//!
+//! Clay only lays out rows and columns, so the grid computes the position and size of every cell
+//! itself and declares cells as fixed size floating elements attached to the grid container.
+//! Track sizes are computed from the size of the container and the content of
+//! [`GridTrack::Fit`] tracks in the last layout.
+
+use crate::{
+    bindings::*,
+    elements::{FloatingAttachToElement, FloatingClipToElement, PointerCaptureMode},
+    id::Id,
+    layout::Sizing,
+    math::Vector2,
+    ClayLayoutScope, Declaration,
+};
+
+/// Maximum number of columns and of rows of a grid, further tracks are ignored.
+pub const MAX_GRID_TRACKS: usize = 32;
+
+/// Size of a column or row of a [`Grid`], mirroring [`Sizing`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GridTrack {
+    /// Fits the largest cell that only spans this track, within a min and max size.
+    Fit(f32, f32),
+    /// Shares the space left by the other tracks by this weight.
+    Fraction(f32),
+    /// Sets a fixed size.
+    Fixed(f32),
+    /// Sets the size as a percentage of the grid, without gaps. Value should be between `0.0`
+    /// and `1.0`.
+    Percent(f32),
+}
+
+/// Columns, rows and gaps of a grid created with [`ClayLayoutScope::grid`].
+#[derive(Debug, Clone, Copy)]
+pub struct Grid<'a> {
+    pub columns: &'a [GridTrack],
+    pub rows: &'a [GridTrack],
+    pub column_gap: f32,
+    pub row_gap: f32,
+    /// Z-index of the cells, which should be at least the one of the grid container.
+    pub z_index: i16,
+}
+
+impl<'a> Grid<'a> {
+    /// Creates a grid with the given tracks and no gaps
+    pub fn new(columns: &'a [GridTrack], rows: &'a [GridTrack]) -> Self {
+        Self {
+            columns,
+            rows,
+            column_gap: 0.0,
+            row_gap: 0.0,
+            z_index: 0,
+        }
+    }
+
+    /// Sets the space between columns and between rows
+    pub fn gap(&mut self, column_gap: f32, row_gap: f32) -> &mut Self {
+        self.column_gap = column_gap;
+        self.row_gap = row_gap;
+        self
+    }
+
+    /// Sets the Z-index of the cells
+    pub fn z_index(&mut self, z_index: i16) -> &mut Self {
+        self.z_index = z_index;
+        self
+    }
+}
+
+/// Where a cell is placed in a [`Grid`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridCell {
+    pub column: usize,
+    pub row: usize,
+    pub column_span: usize,
+    pub row_span: usize,
+}
+
+impl GridCell {
+    /// Places a cell spanning a single track at `column` and `row`
+    pub fn new(column: usize, row: usize) -> Self {
+        Self {
+            column,
+            row,
+            column_span: 1,
+            row_span: 1,
+        }
+    }
+
+    /// Sets how many columns and rows the cell spans
+    pub fn span(mut self, columns: usize, rows: usize) -> Self {
+        self.column_span = columns.max(1);
+        self.row_span = rows.max(1);
+        self
+    }
+}
+
+/// Computed start and size of the tracks along one axis.
+#[derive(Debug, Clone, Copy)]
+struct Tracks {
+    starts: [f32; MAX_GRID_TRACKS],
+    sizes: [f32; MAX_GRID_TRACKS],
+    fit: [bool; MAX_GRID_TRACKS],
+    count: usize,
+}
+
+impl Tracks {
+    /// Sizes `tracks` to fill `available`, where `content` is the size of the largest cell of
+    /// each track.
+    fn new(tracks: &[GridTrack], available: f32, gap: f32, content: &[f32]) -> Self {
+        let count = tracks.len().min(MAX_GRID_TRACKS);
+        let tracks = &tracks[..count];
+        let gaps = gap * count.saturating_sub(1) as f32;
+
+        let mut sizes = [0.0; MAX_GRID_TRACKS];
+        let mut fit = [false; MAX_GRID_TRACKS];
+        let mut weights = 0.0;
+        for (index, track) in tracks.iter().enumerate() {
+            sizes[index] = match *track {
+                GridTrack::Fixed(size) => size,
+                GridTrack::Percent(percent) => percent * (available - gaps).max(0.0),
+                GridTrack::Fit(min, max) => {
+                    fit[index] = true;
+                    content.get(index).copied().unwrap_or(0.0).min(max).max(min)
+                }
+                GridTrack::Fraction(weight) => {
+                    weights += weight;
+                    0.0
+                }
+            };
+        }
+
+        let remaining = (available - gaps - sizes.iter().sum::<f32>()).max(0.0);
+        let mut starts = [0.0; MAX_GRID_TRACKS];
+        let mut start = 0.0;
+        for (index, track) in tracks.iter().enumerate() {
+            if let GridTrack::Fraction(weight) = *track {
+                if weights > 0.0 {
+                    sizes[index] = remaining * weight / weights;
+                }
+            }
+            starts[index] = start;
+            start += sizes[index] + gap;
+        }
+
+        Self {
+            starts,
+            sizes,
+            fit,
+            count,
+        }
+    }
+
+    /// Returns the start and size of `span` tracks beginning at `first`, or `None` if `first`
+    /// is not a track. Spans are cut at the last track.
+    fn span(&self, first: usize, span: usize) -> Option<(f32, f32)> {
+        if first >= self.count {
+            return None;
+        }
+        let last = (first + span.max(1)).min(self.count) - 1;
+        let start = self.starts[first];
+        Some((start, self.starts[last] + self.sizes[last] - start))
+    }
+
+    fn total(&self) -> f32 {
+        self.span(0, self.count).map_or(0.0, |(_, size)| size)
+    }
+}
+
+/// Id of the element measuring the content of a cell, `wide` and `tall` tell if it spans more
+/// than one column or row. Only cells spanning a single track size [`GridTrack::Fit`] tracks.
+fn content_id(grid: u32, column: usize, row: usize, wide: bool, tall: bool) -> Id {
+    let index = ((row * MAX_GRID_TRACKS + column) * 4) as u32 + wide as u32 + 2 * tall as u32;
+    Id {
+        id: unsafe { Clay__HashStringWithOffset("clay_rs__grid_cell".into(), index, grid) },
+    }
+}
+
+/// Places the cells of a grid created with [`ClayLayoutScope::grid`].
+pub struct GridScope<'scope, 'clay, 'render, ImageElementData, CustomElementData, UserData> {
+    scope:
+        &'scope mut ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>,
+    grid: u32,
+    columns: Tracks,
+    rows: Tracks,
+    origin: Vector2,
+    z_index: i16,
+}
+
+impl<
+        'render,
+        'clay: 'render,
+        ImageElementData: 'render,
+        CustomElementData: 'render,
+        UserData: 'render,
+    > GridScope<'_, 'clay, 'render, ImageElementData, CustomElementData, UserData>
+{
+    /// Creates a cell at `cell`, passing its config and a function to add its children. The
+    /// sizing and floating settings of `declaration` are replaced to place the cell. Cells
+    /// starting outside of the grid's tracks are not created.
+    pub fn cell<
+        F: FnOnce(&mut ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>),
+    >(
+        &mut self,
+        cell: GridCell,
+        declaration: &Declaration<'render, ImageElementData, CustomElementData, UserData>,
+        f: F,
+    ) {
+        let (Some((x, width)), Some((y, height))) = (
+            self.columns.span(cell.column, cell.column_span),
+            self.rows.span(cell.row, cell.row_span),
+        ) else {
+            return;
+        };
+        let fit_column = cell.column_span == 1 && self.columns.fit[cell.column];
+        let fit_row = cell.row_span == 1 && self.rows.fit[cell.row];
+
+        let mut declaration = *declaration;
+        declaration
+            .floating()
+            .attach_to(FloatingAttachToElement::Parent)
+            .clip_to(FloatingClipToElement::AttachedParent)
+            .pointer_capture_mode(PointerCaptureMode::Passthrough)
+            .offset(Vector2::new(self.origin.x + x, self.origin.y + y))
+            .z_index(self.z_index)
+            .end()
+            .layout()
+            .width(Sizing::Fixed(width))
+            .height(Sizing::Fixed(height));
+
+        if !(fit_column || fit_row) {
+            self.scope.with(&declaration, f);
+            return;
+        }
+
+        // Clipping keeps clay from shrinking the content, so it can be measured for the next
+        // layout
+        declaration.clip(true, true, Vector2::default());
+        let mut content = Declaration::new();
+        content
+            .id(content_id(
+                self.grid,
+                cell.column,
+                cell.row,
+                cell.column_span > 1,
+                cell.row_span > 1,
+            ))
+            .layout()
+            .width(Sizing::Fit(0.0, f32::MAX))
+            .height(Sizing::Fit(0.0, f32::MAX));
+        self.scope
+            .with(&declaration, |scope| scope.with(&content, f));
+    }
+}
+
+impl<
+        'render,
+        'clay: 'render,
+        ImageElementData: 'render,
+        CustomElementData: 'render,
+        UserData: 'render,
+    > ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>
+{
+    /// Creates a grid container from `declaration`, whose cells are placed with
+    /// [`GridScope::cell`].
+    ///
+    /// The grid fills the container of the last layout, or the root before that, and sizes the
+    /// container if it fits its content. [`GridTrack::Fraction`] and [`GridTrack::Percent`]
+    /// tracks need a container sized by its parent.
+    pub fn grid<F>(
+        &mut self,
+        declaration: &Declaration<'render, ImageElementData, CustomElementData, UserData>,
+        grid: &Grid,
+        f: F,
+    ) where
+        F: FnOnce(
+            &mut GridScope<'_, 'clay, 'render, ImageElementData, CustomElementData, UserData>,
+        ),
+    {
+        let padding = declaration.inner.layout.padding;
+        let root = self.clay.layout_dimensions.get();
+
+        self.with(declaration, |scope| {
+            let container = unsafe { Clay__GetOpenElementId() };
+            let (width, height) = match scope.clay.bounding_box(Id::from_raw(container)) {
+                Some(bounds) => (
+                    bounds.width - (padding.left + padding.right) as f32,
+                    bounds.height - (padding.top + padding.bottom) as f32,
+                ),
+                None => (root.width, root.height),
+            };
+
+            // The largest content of the cells spanning a single fit track
+            let mut column_content = [0.0f32; MAX_GRID_TRACKS];
+            let mut row_content = [0.0f32; MAX_GRID_TRACKS];
+            let fits = |tracks: &[GridTrack]| {
+                let mut fits = [false; MAX_GRID_TRACKS];
+                for (fit, track) in fits.iter_mut().zip(tracks) {
+                    *fit = matches!(track, GridTrack::Fit(..));
+                }
+                fits
+            };
+            let (fit_columns, fit_rows) = (fits(grid.columns), fits(grid.rows));
+            if fit_columns.contains(&true) || fit_rows.contains(&true) {
+                let columns = grid.columns.len().min(MAX_GRID_TRACKS);
+                let rows = grid.rows.len().min(MAX_GRID_TRACKS);
+                for row in 0..rows {
+                    for column in 0..columns {
+                        if !fit_columns[column] && !fit_rows[row] {
+                            continue;
+                        }
+                        for (wide, tall) in [(false, false), (true, false), (false, true)] {
+                            let id = content_id(container, column, row, wide, tall);
+                            let Some(bounds) = scope.clay.bounding_box(id) else {
+                                continue;
+                            };
+                            if !wide {
+                                column_content[column] = column_content[column].max(bounds.width);
+                            }
+                            if !tall {
+                                row_content[row] = row_content[row].max(bounds.height);
+                            }
+                        }
+                    }
+                }
+            }
+
+            let columns = Tracks::new(grid.columns, width, grid.column_gap, &column_content);
+            let rows = Tracks::new(grid.rows, height, grid.row_gap, &row_content);
+
+            // Floating cells take no space, this spacer lets the container fit the grid
+            let mut spacer = Declaration::new();
+            spacer
+                .layout()
+                .width(Sizing::Fixed(columns.total()))
+                .height(Sizing::Fixed(rows.total()));
+            scope.with(&spacer, |_| {});
+
+            f(&mut GridScope {
+                scope,
+                grid: container,
+                columns,
+                rows,
+                origin: Vector2::new(padding.left as f32, padding.top as f32),
+                z_index: grid.z_index,
+            });
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_share_remaining_space() {
+        let tracks = [
+            GridTrack::Fixed(100.0),
+            GridTrack::Fit(0.0, 50.0),
+            GridTrack::Fraction(1.0),
+            GridTrack::Percent(0.25),
+            GridTrack::Fraction(3.0),
+        ];
+        // 420 without gaps: 100 fixed, 50 fit (clamped), 105 percent, 165 shared 1:3
+        let tracks = Tracks::new(&tracks, 440.0, 5.0, &[0.0, 80.0]);
+        assert_eq!(&tracks.sizes[..5], &[100.0, 50.0, 41.25, 105.0, 123.75]);
+        assert_eq!(&tracks.starts[..5], &[0.0, 105.0, 160.0, 206.25, 316.25]);
+        assert_eq!(tracks.total(), 440.0);
+
+        // Spans include the gaps between their tracks and stop at the last track
+        assert_eq!(tracks.span(1, 2), Some((105.0, 96.25)));
+        assert_eq!(tracks.span(4, 3), Some((316.25, 123.75)));
+        assert_eq!(tracks.span(5, 1), None);
+    }
+}
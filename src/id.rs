@@ -18,6 +18,17 @@ impl Id {
         Self::new_index_internal(label, index)
     }
 
+    /// Creates an id from the hash clay uses in render commands
+    #[inline]
+    pub(crate) fn from_raw(id: u32) -> Id {
+        Id {
+            id: Clay_ElementId {
+                id,
+                ..crate::mem::zeroed_init()
+            },
+        }
+    }
+
     #[inline]
     pub(crate) fn new_index_internal(label: &'static str, index: u32) -> Id {
         let id = unsafe { Clay__HashStringWithOffset(label.into(), index, 0) };
@@ -156,17 +156,28 @@ pub struct LayoutBuilder<
     'render,
     ImageElementData: 'render,
     CustomElementData: 'render,
+    UserData: 'render,
 > {
-    parent: &'declaration mut Declaration<'render, ImageElementData, CustomElementData>,
+    parent: &'declaration mut Declaration<'render, ImageElementData, CustomElementData, UserData>,
 }
 
-impl<'declaration, 'render, ImageElementData: 'render, CustomElementData: 'render>
-    LayoutBuilder<'declaration, 'render, ImageElementData, CustomElementData>
+impl<
+        'declaration,
+        'render,
+        ImageElementData: 'render,
+        CustomElementData: 'render,
+        UserData: 'render,
+    > LayoutBuilder<'declaration, 'render, ImageElementData, CustomElementData, UserData>
 {
     /// Creates a new `LayoutBuilder` with the given parent `Declaration`.
     #[inline]
     pub fn new(
-        parent: &'declaration mut Declaration<'render, ImageElementData, CustomElementData>,
+        parent: &'declaration mut Declaration<
+            'render,
+            ImageElementData,
+            CustomElementData,
+            UserData,
+        >,
     ) -> Self {
         LayoutBuilder { parent }
     }
@@ -219,7 +230,9 @@ impl<'declaration, 'render, ImageElementData: 'render, CustomElementData: 'rende
 
     /// Returns the modified `Declaration`.
     #[inline]
-    pub fn end(&mut self) -> &mut Declaration<'render, ImageElementData, CustomElementData> {
+    pub fn end(
+        &mut self,
+    ) -> &mut Declaration<'render, ImageElementData, CustomElementData, UserData> {
         self.parent
     }
 }
@@ -276,7 +289,6 @@ macro_rules! percent {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{fit, fixed, grow, percent};
 
     #[test]
     fn fit_macro() {
@@ -2,45 +2,86 @@
 
 pub mod bindings;
 pub mod color;
+pub mod drag;
 pub mod elements;
 pub mod errors;
+pub mod flow;
+pub mod grid;
 pub mod id;
 pub mod layout;
 pub mod math;
+pub mod overlay;
 pub mod render_commands;
+pub mod responsive;
+pub mod scroll;
+#[cfg(feature = "alloc")]
+pub mod selection;
 pub mod text;
+pub mod virtualized;
 
 mod mem;
 pub mod renderers;
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, string::String, vec::Vec};
+
 use core::marker::PhantomData;
 
 pub use crate::bindings::*;
 use errors::Error;
+#[cfg(feature = "alloc")]
+use errors::LayoutError;
 use id::Id;
 use math::{BoundingBox, Dimensions, Vector2};
 use render_commands::RenderCommand;
 
 pub use color::Color;
 
-#[cfg(feature = "std")]
 use text::TextConfig;
+#[cfg(feature = "std")]
+use text::MeasureTextStats;
 
 use text::TextElementConfig;
-#[derive(Copy, Clone)]
-pub struct Declaration<'render, ImageElementData: 'render, CustomElementData: 'render> {
+pub struct Declaration<
+    'render,
+    ImageElementData: 'render,
+    CustomElementData: 'render,
+    UserData: 'render = (),
+> {
     id: Option<Id>,
     inner: Clay_ElementDeclaration,
-    _phantom: PhantomData<(&'render CustomElementData, &'render ImageElementData)>,
+    scrollbar: Option<scroll::ScrollbarConfig>,
+    _phantom: PhantomData<(
+        &'render CustomElementData,
+        &'render ImageElementData,
+        &'render UserData,
+    )>,
 }
 
-impl<'render, ImageElementData: 'render, CustomElementData: 'render>
-    Declaration<'render, ImageElementData, CustomElementData>
+// Only references to the element data are stored, so it doesn't have to be `Copy` itself
+impl<ImageElementData, CustomElementData, UserData> Clone
+    for Declaration<'_, ImageElementData, CustomElementData, UserData>
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<ImageElementData, CustomElementData, UserData> Copy
+    for Declaration<'_, ImageElementData, CustomElementData, UserData>
+{
+}
+
+impl<'render, ImageElementData: 'render, CustomElementData: 'render, UserData: 'render>
+    Declaration<'render, ImageElementData, CustomElementData, UserData>
 {
     #[inline]
     pub fn new() -> Self {
         let mut zeroed: Self = crate::mem::zeroed_init();
         zeroed.id = None; // Zeroed doesn't mean None for Option
+        zeroed.scrollbar = None;
         zeroed
     }
 
@@ -65,12 +106,30 @@ impl<'render, ImageElementData: 'render, CustomElementData: 'render>
         self
     }
 
+    /// Shows scrollbars on the axes [`Declaration::clip`] scrolls, see
+    /// [`ScrollbarBuilder`](elements::ScrollbarBuilder)
+    #[inline]
+    pub fn scrollbar(
+        &mut self,
+    ) -> elements::ScrollbarBuilder<'_, 'render, ImageElementData, CustomElementData, UserData> {
+        elements::ScrollbarBuilder::new(self)
+    }
+
     #[inline]
     pub fn id(&mut self, id: Id) -> &mut Self {
         self.id = Some(id);
         self
     }
 
+    /// Attaches `data` to the element, it is returned in [`RenderCommand::user_data`] of the
+    /// render commands of the element and by [`ClayLayoutScope::user_data`]. Text declared inside
+    /// the element doesn't get it, text commands have no user data.
+    #[inline]
+    pub fn user_data(&mut self, data: &'render UserData) -> &mut Self {
+        self.inner.userData = data as *const UserData as _;
+        self
+    }
+
     #[inline]
     pub fn custom_element(&mut self, data: &'render CustomElementData) -> &mut Self {
         self.inner.custom.customData = data as *const CustomElementData as _;
@@ -80,48 +139,48 @@ impl<'render, ImageElementData: 'render, CustomElementData: 'render>
     #[inline]
     pub fn layout(
         &mut self,
-    ) -> layout::LayoutBuilder<'_, 'render, ImageElementData, CustomElementData> {
+    ) -> layout::LayoutBuilder<'_, 'render, ImageElementData, CustomElementData, UserData> {
         layout::LayoutBuilder::new(self)
     }
 
     #[inline]
     pub fn image(
         &mut self,
-    ) -> elements::ImageBuilder<'_, 'render, ImageElementData, CustomElementData> {
+    ) -> elements::ImageBuilder<'_, 'render, ImageElementData, CustomElementData, UserData> {
         elements::ImageBuilder::new(self)
     }
 
     #[inline]
     pub fn floating(
         &mut self,
-    ) -> elements::FloatingBuilder<'_, 'render, ImageElementData, CustomElementData> {
+    ) -> elements::FloatingBuilder<'_, 'render, ImageElementData, CustomElementData, UserData> {
         elements::FloatingBuilder::new(self)
     }
 
     #[inline]
     pub fn border(
         &mut self,
-    ) -> elements::BorderBuilder<'_, 'render, ImageElementData, CustomElementData> {
+    ) -> elements::BorderBuilder<'_, 'render, ImageElementData, CustomElementData, UserData> {
         elements::BorderBuilder::new(self)
     }
 
     #[inline]
     pub fn corner_radius(
         &mut self,
-    ) -> elements::CornerRadiusBuilder<'_, 'render, ImageElementData, CustomElementData> {
+    ) -> elements::CornerRadiusBuilder<'_, 'render, ImageElementData, CustomElementData, UserData> {
         elements::CornerRadiusBuilder::new(self)
     }
 }
 
-impl<ImageElementData, CustomElementData> Default
-    for Declaration<'_, ImageElementData, CustomElementData>
+impl<ImageElementData, CustomElementData, UserData> Default
+    for Declaration<'_, ImageElementData, CustomElementData, UserData>
 {
     fn default() -> Self {
         Self::new()
     }
 }
 
-#[cfg(feature = "std")]
+#[cfg(feature = "alloc")]
 unsafe extern "C" fn measure_text_trampoline_user_data<'a, F, T>(
     text_slice: Clay_StringSlice,
     config: *mut Clay_TextElementConfig,
@@ -136,13 +195,15 @@ where
         text_slice.length as _,
     ));
 
+    #[cfg(feature = "std")]
+    count_measure_call(&text_slice);
     let closure_and_data: &mut (F, T) = &mut *(user_data as *mut (F, T));
     let text_config = TextConfig::from(*config);
     let (callback, data) = closure_and_data;
     callback(text, &text_config, data).into()
 }
 
-#[cfg(feature = "std")]
+#[cfg(feature = "alloc")]
 unsafe extern "C" fn measure_text_trampoline<'a, F>(
     text_slice: Clay_StringSlice,
     config: *mut Clay_TextElementConfig,
@@ -156,47 +217,310 @@ where
         text_slice.length as _,
     ));
 
+    #[cfg(feature = "std")]
+    count_measure_call(&text_slice);
     let tuple = &*(user_data as *const (F, usize));
     let text_config = TextConfig::from(*config);
     (tuple.0)(text, &text_config).into()
 }
 
+unsafe extern "C" fn measure_text_trampoline_static<F>(
+    text_slice: Clay_StringSlice,
+    config: *mut Clay_TextElementConfig,
+    user_data: *mut core::ffi::c_void,
+) -> Clay_Dimensions
+where
+    F: Fn(&str, &TextConfig) -> Dimensions + 'static,
+{
+    let text = core::str::from_utf8_unchecked(core::slice::from_raw_parts(
+        text_slice.chars as *const u8,
+        text_slice.length as _,
+    ));
+
+    #[cfg(feature = "std")]
+    count_measure_call(&text_slice);
+    let callback = &*(user_data as *const F);
+    let text_config = TextConfig::from(*config);
+    callback(text, &text_config).into()
+}
+
+/// Frees a callback boxed by one of the `set_measure_text_function` methods.
+#[cfg(feature = "alloc")]
+unsafe fn drop_boxed<T>(ptr: *const core::ffi::c_void) {
+    drop(Box::from_raw(ptr as *mut T));
+}
+
+/// Calls to the measure text function in the frame of `context`, and how many of them measured
+/// the space character, which clay does once for every cache miss while declaring the layout.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy)]
+struct MeasureCalls {
+    context: *mut Clay_Context,
+    total: u32,
+    spaces: u32,
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static MEASURE_CALLS: core::cell::Cell<MeasureCalls> = const {
+        core::cell::Cell::new(MeasureCalls { context: core::ptr::null_mut(), total: 0, spaces: 0 })
+    };
+}
+
+/// Counts a call of the measure text function, if it was made for the frame being counted.
+#[cfg(feature = "std")]
+fn count_measure_call(text_slice: &Clay_StringSlice) {
+    let is_space = text_slice.chars == unsafe { CLAY__SPACECHAR.chars };
+    let context = unsafe { Clay_GetCurrentContext() };
+    MEASURE_CALLS.with(|calls| {
+        let mut counted = calls.get();
+        if counted.context == context {
+            counted.total += 1;
+            counted.spaces += is_space as u32;
+            calls.set(counted);
+        }
+    });
+}
+
+/// Returns the number of used slots and free slots of clay's measure text cache.
+#[cfg(feature = "std")]
+fn measure_text_cache_state() -> (i32, i32) {
+    unsafe {
+        (
+            Clay__GetMeasureTextCacheLength(),
+            Clay__GetMeasureTextCacheFreeCount(),
+        )
+    }
+}
+
 unsafe extern "C" fn error_handler(error_data: Clay_ErrorData) {
     let error: Error = error_data.into();
+    #[cfg(feature = "alloc")]
+    if let Some(auto_grow) = (error_data.userData as *const AutoGrow).as_ref() {
+        if auto_grow.handles(error.type_) {
+            auto_grow.capacity_exceeded.set(true);
+            return;
+        }
+    }
     panic!("Clay Error: (type: {:?}) {}", error.type_, error.text);
 }
 
+/// Signature of the C callback clay uses to measure text.
+type MeasureTextFunction = unsafe extern "C" fn(
+    Clay_StringSlice,
+    *mut Clay_TextElementConfig,
+    *mut core::ffi::c_void,
+) -> Clay_Dimensions;
+
+/// Frees callback data boxed by the setter that registered it.
+type FreeCallback = unsafe fn(*const core::ffi::c_void);
+
+/// Signature of the C callback clay uses to query scroll offsets.
+type QueryScrollOffsetFunction =
+    unsafe extern "C" fn(u32, *mut core::ffi::c_void) -> Clay_Vector2;
+
+#[cfg(feature = "alloc")]
+unsafe extern "C" fn query_scroll_offset_trampoline<F>(
+    element_id: u32,
+    user_data: *mut core::ffi::c_void,
+) -> Clay_Vector2
+where
+    F: Fn(u32) -> Vector2 + 'static,
+{
+    let tuple = &*(user_data as *const (F, usize));
+    (tuple.0)(element_id).into()
+}
+
+/// State of the automatic memory growth, see [`Clay::set_auto_grow`].
+#[cfg(feature = "alloc")]
+#[derive(Default)]
+struct AutoGrow {
+    /// Upper bound of the memory clay may use, `None` if growing is disabled
+    max_memory: core::cell::Cell<Option<usize>>,
+    /// Set by the error handler if clay ran out of capacity in the current frame
+    capacity_exceeded: core::cell::Cell<bool>,
+}
+
+#[cfg(feature = "alloc")]
+impl AutoGrow {
+    fn handles(&self, error: errors::ErrorType) -> bool {
+        use errors::ErrorType;
+
+        self.max_memory.get().is_some()
+            && match error {
+                ErrorType::ArenaCapacityExceeded
+                | ErrorType::ElementsCapacityExceeded
+                | ErrorType::TextMeasurementCapacityExceeded => true,
+                // Clay stops closing elements once it ran out of them
+                ErrorType::UnbalancedOpenClose => unsafe { Clay__CapacityExceeded() },
+                _ => false,
+            }
+    }
+}
+
+/// Memory clay's context lives in.
+enum Memory {
+    #[cfg(feature = "alloc")]
+    Owned(Vec<u8>),
+    /// Memory managed by the caller
+    Borrowed(*const core::ffi::c_void, usize),
+}
+
+impl Memory {
+    fn as_ptr(&self) -> *const core::ffi::c_void {
+        match self {
+            #[cfg(feature = "alloc")]
+            Self::Owned(memory) => memory.as_ptr() as _,
+            Self::Borrowed(memory, _) => *memory,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            #[cfg(feature = "alloc")]
+            Self::Owned(memory) => memory.len(),
+            Self::Borrowed(_, len) => *len,
+        }
+    }
+}
+
+/// Copies text into the string storage, cutting off what doesn't fit.
+#[cfg(not(feature = "alloc"))]
+struct StorageWriter {
+    buffer: *mut u8,
+    capacity: usize,
+    len: usize,
+}
+
+#[cfg(not(feature = "alloc"))]
+impl core::fmt::Write for StorageWriter {
+    fn write_str(&mut self, text: &str) -> core::fmt::Result {
+        let mut end = text.len().min(self.capacity - self.len);
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        if end > 0 {
+            unsafe {
+                core::ptr::copy_nonoverlapping(text.as_ptr(), self.buffer.add(self.len), end);
+            }
+            self.len += end;
+        }
+
+        if end < text.len() {
+            Err(core::fmt::Error)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Last pointer state passed to [`Clay::pointer_state`].
+#[derive(Debug, Clone, Copy, Default)]
+struct PointerState {
+    position: Vector2,
+    is_down: bool,
+}
+
 #[allow(dead_code)]
 pub struct Clay {
     /// Memory used internally by clay
-    #[cfg(feature = "std")]
-    _memory: Vec<u8>,
+    memory: Memory,
     context: *mut Clay_Context,
-    /// Memory used internally by clay. The caller is responsible for managing this memory in
-    /// no_std case.
-    #[cfg(not(feature = "std"))]
-    _memory: *const core::ffi::c_void,
     /// Stores the raw pointer to the callback data for later cleanup
     text_measure_callback: Option<*const core::ffi::c_void>,
+    /// Frees the callback data if it was boxed
+    free_text_measure_callback: Option<FreeCallback>,
+    /// The query scroll offset function with its boxed callback and how to free it
+    query_scroll_offset:
+        Option<(QueryScrollOffsetFunction, *mut core::ffi::c_void, Option<FreeCallback>)>,
+    external_scroll_handling: core::cell::Cell<bool>,
+    scroll_animations:
+        core::cell::Cell<[Option<scroll::ScrollAnimation>; scroll::MAX_SCROLL_ANIMATIONS]>,
+    /// Scrollbars declared in the last layout, and the one whose thumb is dragged
+    scrollbars: core::cell::Cell<[Option<scroll::Scrollbar>; scroll::MAX_SCROLLBARS]>,
+    scrollbar_drag: core::cell::Cell<Option<scroll::ScrollbarDrag>>,
+    /// Scrollbars of the last layout that didn't fit into `scrollbars`
+    dropped_scrollbars: core::cell::Cell<usize>,
+    /// The trampoline registered with clay, so text can also be measured from Rust
+    measure_text_function: Option<MeasureTextFunction>,
+    pointer: core::cell::Cell<PointerState>,
+    #[cfg(feature = "std")]
+    measure_text_stats: core::cell::Cell<MeasureTextStats>,
+    layout_dimensions: core::cell::Cell<Dimensions>,
+    scale_factor: core::cell::Cell<f32>,
+    culling: core::cell::Cell<bool>,
+    /// Boxed so the error handler can keep a pointer to it
+    #[cfg(feature = "alloc")]
+    auto_grow: Box<AutoGrow>,
+    /// Memory of the context replaced by the last growth, kept alive until the next frame begins
+    /// because render commands of the frame that ran out of capacity still point into it
+    #[cfg(feature = "alloc")]
+    retired_memory: Option<Memory>,
+    /// Scroll positions to carry over to the containers of the grown context
+    #[cfg(feature = "alloc")]
+    restored_scroll_positions: core::cell::RefCell<Vec<(u32, Vector2)>>,
+    /// Text copied for the current frame, kept until the next frame begins because its render
+    /// commands point into it
+    #[cfg(feature = "alloc")]
+    frame_strings: core::cell::RefCell<Vec<String>>,
+    /// Buffer [`ClayLayoutScope::text_fmt`] copies text into, and how much of it is used
+    #[cfg(not(feature = "alloc"))]
+    string_storage: (*mut u8, usize),
+    #[cfg(not(feature = "alloc"))]
+    string_storage_used: core::cell::Cell<usize>,
+}
+
+/// A text element that gets truncated once its wrapper element has been laid out.
+#[cfg(feature = "alloc")]
+struct TruncatedText {
+    wrapper: Id,
+    text: *const str,
+    config: TextConfig,
 }
 
-pub struct ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData> {
+/// What happens to a text render command after truncation.
+#[cfg(feature = "alloc")]
+enum TextLineEdit<'render> {
+    Keep,
+    Remove,
+    Replace(&'render str, BoundingBox),
+}
+
+pub struct ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData = ()> {
     clay: &'clay mut Clay,
-    _phantom: core::marker::PhantomData<(&'render ImageElementData, &'render CustomElementData)>,
+    _phantom: core::marker::PhantomData<(
+        &'render ImageElementData,
+        &'render CustomElementData,
+        &'render UserData,
+    )>,
     dropped: bool,
+    #[cfg(feature = "alloc")]
+    truncated_texts: core::cell::RefCell<Vec<TruncatedText>>,
+    #[cfg(feature = "std")]
+    text_lookups: core::cell::Cell<u32>,
     #[cfg(feature = "std")]
-    owned_strings: core::cell::RefCell<std::vec::Vec<std::string::String>>,
+    measure_text_cache: (i32, i32),
+    /// Set by [`ClayLayoutScope::end`] if clay ran out of capacity
+    #[cfg(feature = "alloc")]
+    capacity: Result<(), LayoutError>,
 }
 
-impl<'render, 'clay: 'render, ImageElementData: 'render, CustomElementData: 'render>
-    ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData>
+impl<
+        'render,
+        'clay: 'render,
+        ImageElementData: 'render,
+        CustomElementData: 'render,
+        UserData: 'render,
+    > ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>
 {
     /// Create an element, passing its config and a function to add childrens
     pub fn with<
-        F: FnOnce(&mut ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData>),
+        F: FnOnce(
+            &mut ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>,
+        ),
     >(
         &mut self,
-        declaration: &Declaration<'render, ImageElementData, CustomElementData>,
+        declaration: &Declaration<'render, ImageElementData, CustomElementData, UserData>,
         f: F,
     ) {
         unsafe {
@@ -208,9 +532,14 @@ impl<'render, 'clay: 'render, ImageElementData: 'render, CustomElementData: 'ren
             }
             Clay__ConfigureOpenElement(declaration.inner);
         }
+        #[cfg(feature = "alloc")]
+        self.clay.restore_scroll_positions();
 
         f(self);
 
+        if let Some(scrollbar) = declaration.scrollbar {
+            self.declare_scrollbars(declaration.inner.clip, scrollbar);
+        }
         unsafe {
             Clay__CloseElement();
         }
@@ -218,9 +547,9 @@ impl<'render, 'clay: 'render, ImageElementData: 'render, CustomElementData: 'ren
 
     pub fn with_styling<
         G: FnOnce(
-            &ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData>,
-        ) -> Declaration<'render, ImageElementData, CustomElementData>,
-        F: FnOnce(&ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData>),
+            &ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>,
+        ) -> Declaration<'render, ImageElementData, CustomElementData, UserData>,
+        F: FnOnce(&ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>),
     >(
         &self,
         g: G,
@@ -240,40 +569,297 @@ impl<'render, 'clay: 'render, ImageElementData: 'render, CustomElementData: 'ren
             }
             Clay__ConfigureOpenElement(declaration.inner);
         }
+        #[cfg(feature = "alloc")]
+        self.clay.restore_scroll_positions();
 
         f(self);
 
+        if let Some(scrollbar) = declaration.scrollbar {
+            self.declare_scrollbars(declaration.inner.clip, scrollbar);
+        }
         unsafe {
             Clay__CloseElement();
         }
     }
 
+    /// Ends the frame and returns its render commands.
+    ///
+    /// If clay ran out of capacity while growing is enabled with [`Clay::set_auto_grow`], the
+    /// commands are incomplete. [`ClayLayoutScope::try_end`] and [`Clay::layout`] report it.
     pub fn end(
         &mut self,
-    ) -> impl Iterator<Item = RenderCommand<'render, ImageElementData, CustomElementData>> {
+    ) -> impl Iterator<
+        Item = RenderCommand<'render, ImageElementData, CustomElementData, UserData>,
+    > {
+        // Clay measures the space character again while wrapping text, so misses are counted first
+        #[cfg(feature = "std")]
+        let cache_misses = MEASURE_CALLS.with(|calls| calls.get().spaces);
+
         let array = unsafe { Clay_EndLayout() };
         self.dropped = true;
+
+        #[cfg(feature = "std")]
+        self.record_measure_text_stats(cache_misses);
+        #[cfg(feature = "alloc")]
+        self.clay.restored_scroll_positions.borrow_mut().clear();
         let slice = unsafe { core::slice::from_raw_parts(array.internalArray, array.length as _) };
-        slice
-            .iter()
-            .map(|command| unsafe { RenderCommand::from_clay_render_command(*command) })
+
+        #[cfg(feature = "alloc")]
+        let edits = self.truncate_text_commands(slice);
+
+        #[cfg(feature = "alloc")]
+        if self.clay.auto_grow.max_memory.get().is_some()
+            && (self.clay.auto_grow.capacity_exceeded.get() || unsafe { Clay__CapacityExceeded() })
+        {
+            self.capacity = match self.clay.grow() {
+                Ok(()) => Err(LayoutError::Grown),
+                Err(error) => Err(error),
+            };
+        }
+
+        let scale_factor = self.clay.scale_factor.get();
+        // Without `alloc` there are no edits and every command is kept
+        #[cfg_attr(
+            not(feature = "alloc"),
+            allow(clippy::unused_enumerate_index, clippy::unnecessary_filter_map)
+        )]
+        slice.iter().enumerate().filter_map(move |(_index, command)| {
+            #[allow(unused_mut)]
+            let mut command =
+                unsafe { RenderCommand::from_clay_render_command(*command, scale_factor) };
+
+            #[cfg(feature = "alloc")]
+            match edits.get(_index) {
+                Some(TextLineEdit::Remove) => return None,
+                Some(TextLineEdit::Replace(text, bounding_box)) => {
+                    if let render_commands::RenderCommandConfig::Text(config) = &mut command.config
+                    {
+                        config.text = text;
+                        command.bounding_box = *bounding_box;
+                    }
+                }
+                Some(TextLineEdit::Keep) | None => {}
+            }
+
+            Some(command)
+        })
+    }
+
+    /// Computes the truncated lines of every text element declared with
+    /// [`TextConfig::max_lines`] or an ellipsis [`TextConfig::overflow`].
+    #[cfg(feature = "alloc")]
+    fn truncate_text_commands(&self, commands: &[Clay_RenderCommand]) -> Vec<TextLineEdit<'render>> {
+        let truncated_texts = self.truncated_texts.borrow();
+        if truncated_texts.is_empty() {
+            return Vec::new();
+        }
+
+        // Text render commands carry the index of their truncated text in `userData`.
+        let mut lines = alloc::vec![Vec::new(); truncated_texts.len()];
+        for (index, command) in commands.iter().enumerate() {
+            if command.commandType == Clay_RenderCommandType_CLAY_RENDER_COMMAND_TYPE_TEXT
+                && !command.userData.is_null()
+            {
+                if let Some(lines) = lines.get_mut(command.userData as usize - 1) {
+                    lines.push(index);
+                }
+            }
+        }
+
+        let mut edits: Vec<TextLineEdit<'render>> =
+            commands.iter().map(|_| TextLineEdit::Keep).collect();
+
+        for (truncated, lines) in truncated_texts.iter().zip(lines) {
+            let Some(wrapper) = self.clay.bounding_box(truncated.wrapper) else {
+                continue;
+            };
+            let Some(&first) = lines.first() else {
+                continue;
+            };
+
+            let config = &truncated.config;
+            let full_text = unsafe { &*truncated.text };
+            let line_text = |index: usize| -> &str {
+                unsafe { commands[index].renderData.text.stringContents }.into()
+            };
+            let measure = |text: &str| self.clay.measure_text(text, config).map_or(0.0, |d| d.width);
+            let overflows = |index: usize| commands[index].boundingBox.width > wrapper.width;
+
+            let (line, text) = match config.overflow {
+                text::TextOverflow::Clip => {
+                    let max_lines = config.max_lines as usize;
+                    for &index in lines.iter().skip(max_lines.max(1)) {
+                        edits[index] = TextLineEdit::Remove;
+                    }
+                    continue;
+                }
+                text::TextOverflow::EllipsisEnd => {
+                    let kept = match config.max_lines {
+                        0 => lines.len(),
+                        max_lines => lines.len().min(max_lines as usize),
+                    };
+                    for &index in &lines[kept..] {
+                        edits[index] = TextLineEdit::Remove;
+                    }
+                    let last = lines[kept - 1];
+                    if kept == lines.len() && !overflows(last) {
+                        continue;
+                    }
+                    // Everything from the start of the last visible line is shown on that line.
+                    let start = line_text(last).as_ptr() as usize - full_text.as_ptr() as usize;
+                    (last, full_text[start..].replace('\n', " "))
+                }
+                text::TextOverflow::EllipsisStart | text::TextOverflow::EllipsisMiddle => {
+                    for &index in &lines[1..] {
+                        edits[index] = TextLineEdit::Remove;
+                    }
+                    if lines.len() == 1 && !overflows(first) {
+                        continue;
+                    }
+                    (first, full_text.replace('\n', " "))
+                }
+            };
+            // The ellipsis can't be measured, so the lines that are kept stay as they are
+            if self.clay.measure_text_function.is_none() {
+                continue;
+            }
+
+            let text = text::ellipsize(&text, wrapper.width, config.overflow, measure);
+            let width = measure(&text);
+            let x = match config.alignment {
+                text::TextAlignment::Left => wrapper.x,
+                text::TextAlignment::Center => wrapper.x + (wrapper.width - width) / 2.0,
+                text::TextAlignment::Right => wrapper.x + wrapper.width - width,
+            };
+            let mut bounding_box: BoundingBox = commands[line].boundingBox.into();
+            bounding_box.x = x;
+            bounding_box.width = width;
+
+            let mut frame_strings = self.clay.frame_strings.borrow_mut();
+            frame_strings.push(text);
+            let text_ref = frame_strings.last().unwrap();
+            // The string is kept alive by `frame_strings` until the next frame begins.
+            let text_ref: &'render str = unsafe { &*(text_ref.as_str() as *const str) };
+            edits[line] = TextLineEdit::Replace(text_ref, bounding_box);
+        }
+
+        edits
+    }
+
+    /// Opens a text element, wrapping it in a clipping element if it has to be truncated.
+    fn open_text_element(&self, text: Clay_String, config: TextElementConfig) {
+        #[cfg(feature = "std")]
+        self.text_lookups.set(self.text_lookups.get() + 1);
+
+        #[cfg(feature = "alloc")]
+        if config.config.truncates() {
+            self.open_truncated_text_element(text, config);
+            return;
+        }
+
+        unsafe { Clay__OpenTextElement(text, config.into()) };
+    }
+
+    /// Like [`ClayLayoutScope::end`], but fails if clay ran out of capacity, see
+    /// [`Clay::set_auto_grow`]. After [`LayoutError::Grown`] the frame should be declared again.
+    #[cfg(feature = "alloc")]
+    pub fn try_end(
+        &mut self,
+    ) -> Result<
+        impl Iterator<Item = RenderCommand<'render, ImageElementData, CustomElementData, UserData>>,
+        LayoutError,
+    > {
+        let commands = self.end();
+        self.capacity.map(|()| commands)
+    }
+
+    #[cfg(feature = "std")]
+    fn record_measure_text_stats(&self, cache_misses: u32) {
+        let calls = MEASURE_CALLS.with(|calls| calls.get());
+        // Another clay began a frame since this one did, so the calls are not this frame's
+        if calls.context != self.clay.context {
+            self.clay.measure_text_stats.set(MeasureTextStats::default());
+            return;
+        }
+        let (length, free) = measure_text_cache_state();
+        let (before_length, before_free) = self.measure_text_cache;
+        // Misses reuse free slots or append new ones, evictions free slots
+        let used_growth = (length - free) - (before_length - before_free);
+        self.clay.measure_text_stats.set(MeasureTextStats {
+            measure_calls: calls.total,
+            cache_hits: self.text_lookups.get().saturating_sub(cache_misses),
+            cache_misses,
+            evictions: (cache_misses as i32 - used_growth).max(0) as u32,
+        });
+    }
+
+    #[cfg(feature = "alloc")]
+    fn open_truncated_text_element(&self, text: Clay_String, config: TextElementConfig) {
+        let mut truncated_texts = self.truncated_texts.borrow_mut();
+        let index = truncated_texts.len();
+        let wrapper = Id::new_index("clay_rs__truncated_text", index as u32);
+        let text_config = config.config;
+        let text_str: &str = text.into();
+
+        let single_line = text_config.max_lines == 1
+            || matches!(text_config.wrap_mode, text::TextElementConfigWrapMode::None)
+            || matches!(
+                text_config.overflow,
+                text::TextOverflow::EllipsisStart | text::TextOverflow::EllipsisMiddle
+            );
+        let max_height = if text_config.max_lines == 0 {
+            f32::MAX
+        } else {
+            let line_height = if text_config.line_height > 0 {
+                text_config.line_height as f32
+            } else {
+                self.clay
+                    .measure_text(text_str, &text_config)
+                    .map_or(0.0, |d| d.height)
+            };
+            line_height * text_config.max_lines as f32
+        };
+
+        // Clipping a single line lets the wrapper shrink below the width of the text, while
+        // wrapped text keeps wrapping to the width of the wrapper.
+        let mut declaration = Declaration::<(), ()>::new();
+        declaration
+            .layout()
+            .direction(layout::LayoutDirection::TopToBottom)
+            .height(layout::Sizing::Fit(0.0, max_height))
+            .end()
+            .clip(single_line, text_config.max_lines > 0, Vector2::default());
+
+        unsafe {
+            Clay__OpenElementWithId(wrapper.id);
+            Clay__ConfigureOpenElement(declaration.inner);
+            (*config.inner).userData = (index + 1) as _;
+            Clay__OpenTextElement(text, config.inner);
+            Clay__CloseElement();
+        }
+
+        truncated_texts.push(TruncatedText {
+            wrapper,
+            text: text_str as *const str,
+            config: text_config,
+        });
     }
 
     /// Adds a text element to the current open element or to the root layout.
     /// The string data is copied and stored.
     /// For string literals, use `text_literal()` for better performance (avoids copying).
     /// For dynamic strings, use `text_string()`.
-    #[cfg(feature = "std")]
+    #[cfg(feature = "alloc")]
     pub fn text(&self, text: &str, config: TextElementConfig) {
-        let owned = std::string::String::from(text);
+        let owned = String::from(text);
         self.text_string(owned, config);
     }
 
     /// Adds a text element from a string that must live until fully used.
     /// Only available in no_std - you must ensure the string lives long enough.
-    #[cfg(not(feature = "std"))]
+    #[cfg(not(feature = "alloc"))]
     pub fn text(&self, text: &'render str, config: TextElementConfig) {
-        unsafe { Clay__OpenTextElement(text.into(), config.into()) };
+        self.open_text_element(text.into(), config);
     }
 
     /// Adds a text element from a static string literal without copying.
@@ -283,28 +869,64 @@ impl<'render, 'clay: 'render, ImageElementData: 'render, CustomElementData: 'ren
             length: text.len() as _,
             chars: text.as_ptr() as _,
         };
-        unsafe { Clay__OpenTextElement(clay_string, config.into()) };
+        self.open_text_element(clay_string, config);
     }
 
     /// Adds a text element from an owned string that will be stored.
-    #[cfg(feature = "std")]
-    pub fn text_string(&self, text: std::string::String, config: TextElementConfig) {
-        let mut owned_strings = self.owned_strings.borrow_mut();
-        owned_strings.push(text);
-        let text_ref = owned_strings.last().unwrap();
+    #[cfg(feature = "alloc")]
+    pub fn text_string(&self, text: String, config: TextElementConfig) {
+        let mut frame_strings = self.clay.frame_strings.borrow_mut();
+        frame_strings.push(text);
+        let text_ref = frame_strings.last().unwrap();
 
         let clay_string = Clay_String {
             isStaticallyAllocated: false,
             length: text_ref.len() as _,
             chars: text_ref.as_ptr() as _,
         };
-        unsafe { Clay__OpenTextElement(clay_string, config.into()) };
+        drop(frame_strings);
+        self.open_text_element(clay_string, config);
+    }
+
+    /// Adds a text element from format arguments, e.g. `format_args!("{fps} fps")`.
+    /// Without `alloc` the text is copied into the buffer set with [`Clay::set_string_storage`]
+    /// and cut off once the buffer is full.
+    ///
+    /// Returns `false` if the text was cut off, or is empty because no buffer was set.
+    pub fn text_fmt(&self, args: core::fmt::Arguments, config: TextElementConfig) -> bool {
+        #[cfg(feature = "alloc")]
+        {
+            self.text_string(alloc::fmt::format(args), config);
+            true
+        }
+
+        #[cfg(not(feature = "alloc"))]
+        {
+            let (buffer, capacity) = self.clay.string_storage;
+            let start = self.clay.string_storage_used.get();
+            let mut writer = StorageWriter {
+                buffer,
+                capacity,
+                len: start,
+            };
+            let complete = core::fmt::write(&mut writer, args).is_ok();
+            self.clay.string_storage_used.set(writer.len);
+
+            let clay_string = Clay_String {
+                isStaticallyAllocated: false,
+                length: (writer.len - start) as _,
+                chars: buffer.wrapping_add(start) as _,
+            };
+            self.open_text_element(clay_string, config);
+            complete
+        }
     }
 
     pub fn hovered(&self) -> bool {
         unsafe { Clay_Hovered() }
     }
 
+    #[cfg(feature = "alloc")]
     pub fn on_hover<F, T>(&self, callback: F, user_data: T)
     where
         F: Fn(Id, Clay_PointerData, &mut T) + 'static
@@ -333,13 +955,21 @@ impl<'render, 'clay: 'render, ImageElementData: 'render, CustomElementData: 'ren
         }
     }
 
+    /// Returns the data set with [`Declaration::user_data`] on the element with `id`, if it was
+    /// declared in this frame
+    pub fn user_data(&self, id: Id) -> Option<&'render UserData> {
+        unsafe { Clay__GetElementUserData(id.id.id).cast::<UserData>().as_ref() }
+    }
+
+    /// Returns the scroll offset of the element that is currently open, pass it as the child
+    /// offset of [`Declaration::clip`] to scroll its content
     pub fn scroll_offset(&self) -> Vector2 {
         unsafe { Clay_GetScrollOffset().into() }
     }
 }
 
-impl<'clay, 'render, ImageElementData, CustomElementData> core::ops::Deref
-    for ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData>
+impl<'clay, 'render, ImageElementData, CustomElementData, UserData> core::ops::Deref
+    for ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>
 {
     type Target = Clay;
 
@@ -348,16 +978,16 @@ impl<'clay, 'render, ImageElementData, CustomElementData> core::ops::Deref
     }
 }
 
-impl<'clay, 'render, ImageElementData, CustomElementData> core::ops::DerefMut
-    for ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData>
+impl<'clay, 'render, ImageElementData, CustomElementData, UserData> core::ops::DerefMut
+    for ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>
 {
     fn deref_mut(&mut self) -> &mut Self::Target {
         self.clay
     }
 }
 
-impl<ImageElementData, CustomElementData> Drop
-    for ClayLayoutScope<'_, '_, ImageElementData, CustomElementData>
+impl<ImageElementData, CustomElementData, UserData> Drop
+    for ClayLayoutScope<'_, '_, ImageElementData, CustomElementData, UserData>
 {
     fn drop(&mut self) {
         if !self.dropped {
@@ -372,43 +1002,264 @@ impl Clay {
     pub fn begin<'render, ImageElementData: 'render, CustomElementData: 'render>(
         &mut self,
     ) -> ClayLayoutScope<'_, 'render, ImageElementData, CustomElementData> {
+        self.begin_with_user_data()
+    }
+
+    /// Like [`Clay::begin`], but elements can carry typed user data set with
+    /// [`Declaration::user_data`]
+    pub fn begin_with_user_data<
+        'render,
+        ImageElementData: 'render,
+        CustomElementData: 'render,
+        UserData: 'render,
+    >(
+        &mut self,
+    ) -> ClayLayoutScope<'_, 'render, ImageElementData, CustomElementData, UserData> {
+        #[cfg(feature = "alloc")]
+        {
+            self.retired_memory = None;
+            self.auto_grow.capacity_exceeded.set(false);
+            self.frame_strings.get_mut().clear();
+        }
+        #[cfg(not(feature = "alloc"))]
+        self.string_storage_used.set(0);
+        self.scrollbars.set([None; scroll::MAX_SCROLLBARS]);
+        self.dropped_scrollbars.set(0);
         unsafe { Clay_BeginLayout() };
+        #[cfg(feature = "std")]
+        MEASURE_CALLS.with(|calls| {
+            calls.set(MeasureCalls {
+                context: self.context,
+                total: 0,
+                spaces: 0,
+            })
+        });
         ClayLayoutScope {
             clay: self,
             _phantom: core::marker::PhantomData,
             dropped: false,
+            #[cfg(feature = "alloc")]
+            truncated_texts: core::cell::RefCell::new(Vec::new()),
             #[cfg(feature = "std")]
-            owned_strings: core::cell::RefCell::new(std::vec::Vec::new()),
+            text_lookups: core::cell::Cell::new(0),
+            #[cfg(feature = "std")]
+            measure_text_cache: measure_text_cache_state(),
+            #[cfg(feature = "alloc")]
+            capacity: Ok(()),
         }
     }
 
-    #[cfg(feature = "std")]
+    #[cfg(feature = "alloc")]
     pub fn new(dimensions: Dimensions) -> Self {
-        let memory_size = Self::required_memory_size();
-        let memory = vec![0; memory_size];
-        let context;
+        let memory = Memory::Owned(alloc::vec![0; Self::required_memory_size()]);
+        Self::with_memory(dimensions, memory)
+    }
 
-        unsafe {
-            let arena =
-                Clay_CreateArenaWithCapacityAndMemory(memory_size as _, memory.as_ptr() as _);
-
-            context = Clay_Initialize(
-                arena,
-                dimensions.into(),
-                Clay_ErrorHandler {
-                    errorHandlerFunction: Some(error_handler),
-                    userData: std::ptr::null_mut(),
-                },
-            );
+    /// Creates clay in caller-provided memory without allocating.
+    ///
+    /// Returns `None` if `memory` is smaller than [`Clay::required_memory_size`].
+    pub fn new_with_buffer(
+        dimensions: Dimensions,
+        memory: &'static mut [core::mem::MaybeUninit<u8>],
+    ) -> Option<Self> {
+        if memory.len() < Self::required_memory_size() {
+            return None;
         }
+        let memory = Memory::Borrowed(memory.as_mut_ptr() as _, memory.len());
+        Some(Self::with_memory(dimensions, memory))
+    }
+
+    fn with_memory(dimensions: Dimensions, memory: Memory) -> Self {
+        #[cfg(feature = "alloc")]
+        let auto_grow = Box::<AutoGrow>::default();
+        #[cfg(feature = "alloc")]
+        let error_user_data = &*auto_grow as *const AutoGrow as _;
+        #[cfg(not(feature = "alloc"))]
+        let error_user_data = core::ptr::null_mut();
+
+        let context = unsafe { Self::initialize(dimensions, &memory, error_user_data) };
 
         Self {
-            _memory: memory,
+            memory,
             context,
             text_measure_callback: None,
+            free_text_measure_callback: None,
+            query_scroll_offset: None,
+            external_scroll_handling: core::cell::Cell::new(false),
+            scroll_animations: core::cell::Cell::new([None; scroll::MAX_SCROLL_ANIMATIONS]),
+            scrollbars: core::cell::Cell::new([None; scroll::MAX_SCROLLBARS]),
+            scrollbar_drag: core::cell::Cell::new(None),
+            dropped_scrollbars: core::cell::Cell::new(0),
+            measure_text_function: None,
+            pointer: core::cell::Cell::new(PointerState::default()),
+            #[cfg(feature = "std")]
+            measure_text_stats: core::cell::Cell::new(MeasureTextStats::default()),
+            layout_dimensions: core::cell::Cell::new(dimensions),
+            scale_factor: core::cell::Cell::new(1.0),
+            culling: core::cell::Cell::new(true),
+            #[cfg(feature = "alloc")]
+            auto_grow,
+            #[cfg(feature = "alloc")]
+            retired_memory: None,
+            #[cfg(feature = "alloc")]
+            restored_scroll_positions: core::cell::RefCell::new(Vec::new()),
+            #[cfg(feature = "alloc")]
+            frame_strings: core::cell::RefCell::new(Vec::new()),
+            #[cfg(not(feature = "alloc"))]
+            string_storage: (core::ptr::null_mut(), 0),
+            #[cfg(not(feature = "alloc"))]
+            string_storage_used: core::cell::Cell::new(0),
         }
     }
 
+    /// Initializes a context in `memory`
+    unsafe fn initialize(
+        dimensions: Dimensions,
+        memory: &Memory,
+        error_user_data: *mut core::ffi::c_void,
+    ) -> *mut Clay_Context {
+        let arena = Clay_CreateArenaWithCapacityAndMemory(memory.len() as _, memory.as_ptr() as _);
+
+        Clay_Initialize(
+            arena,
+            dimensions.into(),
+            Clay_ErrorHandler {
+                errorHandlerFunction: Some(error_handler),
+                userData: error_user_data,
+            },
+        )
+    }
+
+    /// Lets clay grow its memory when a frame runs out of element, arena or text measurement
+    /// capacity, instead of panicking. Limits are doubled until the frame fits or the required
+    /// memory would exceed `max_memory` bytes. `None` disables growing.
+    ///
+    /// The frame that ran out of capacity is incomplete. [`Clay::layout`] declares it again
+    /// right away, [`ClayLayoutScope::try_end`] tells when to.
+    #[cfg(feature = "alloc")]
+    pub fn set_auto_grow(&mut self, max_memory: Option<usize>) {
+        self.auto_grow.max_memory.set(max_memory);
+    }
+
+    /// Declares a frame with `declare` and returns its render commands.
+    ///
+    /// If clay runs out of capacity while growing is enabled with [`Clay::set_auto_grow`], it
+    /// grows and calls `declare` again, until the frame fits or growing would exceed the limit.
+    #[cfg(feature = "alloc")]
+    pub fn layout<
+        'render,
+        ImageElementData: 'render,
+        CustomElementData: 'render,
+        UserData: 'render,
+    >(
+        &'render mut self,
+        mut declare: impl FnMut(
+            &mut ClayLayoutScope<'render, 'render, ImageElementData, CustomElementData, UserData>,
+        ),
+    ) -> Result<
+        Vec<RenderCommand<'render, ImageElementData, CustomElementData, UserData>>,
+        LayoutError,
+    > {
+        let clay: *mut Clay = self;
+        loop {
+            // Every scope ends before the next one begins, so only one of them borrows clay
+            let mut scope = unsafe { &mut *clay }.begin_with_user_data();
+            declare(&mut scope);
+            match scope.try_end() {
+                Ok(commands) => return Ok(commands.collect()),
+                Err(LayoutError::Grown) => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Returns the size of the memory clay currently uses in bytes
+    pub fn memory_size(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// Doubles the limits and moves clay into a new context large enough for them, keeping the
+    /// measure function, settings and scroll positions. Fails if that needs more than the
+    /// `max_memory` of [`Clay::set_auto_grow`], keeping the current context.
+    #[cfg(feature = "alloc")]
+    fn grow(&mut self) -> Result<(), LayoutError> {
+        let max_memory = self.auto_grow.max_memory.get().unwrap_or(0);
+        let max_element_count = self.get_max_element_count() as i32;
+        let max_word_count = self.get_max_measure_text_cache_word_count() as i32;
+
+        unsafe {
+            Clay_SetCurrentContext(self.context);
+            Clay_SetMaxElementCount(max_element_count * 2);
+            Clay_SetMaxMeasureTextCacheWordCount(max_word_count * 2);
+        }
+        let required_memory = Self::required_memory_size();
+        if required_memory > max_memory {
+            unsafe {
+                Clay_SetMaxElementCount(max_element_count);
+                Clay_SetMaxMeasureTextCacheWordCount(max_word_count);
+            }
+            return Err(LayoutError::CapacityExceeded {
+                required_memory,
+                max_memory,
+            });
+        }
+
+        let mut scroll_positions = Vec::new();
+        let (mut id, mut position) = (0, Clay_Vector2 { x: 0.0, y: 0.0 });
+        while unsafe {
+            Clay__GetScrollContainerPosition(scroll_positions.len() as _, &mut id, &mut position)
+        } {
+            scroll_positions.push((id, position.into()));
+        }
+        let debug_mode = self.is_debug_mode();
+
+        // The old context is still current, so the new one inherits its limits
+        let memory = Memory::Owned(alloc::vec![0; Self::required_memory_size()]);
+        let error_user_data = &*self.auto_grow as *const AutoGrow as _;
+        self.context =
+            unsafe { Self::initialize(self.layout_dimensions.get(), &memory, error_user_data) };
+        self.retired_memory = Some(core::mem::replace(&mut self.memory, memory));
+
+        unsafe {
+            if let (Some(function), Some(user_data)) =
+                (self.measure_text_function, self.text_measure_callback)
+            {
+                Self::set_measure_text_function_unsafe(function, user_data as _);
+            }
+        }
+        self.set_debug_mode(debug_mode);
+        self.set_culling(self.culling.get());
+        self.set_external_scroll_handling(self.external_scroll_handling.get());
+        if let Some((function, user_data, _)) = self.query_scroll_offset {
+            unsafe { Clay_SetQueryScrollOffsetFunction(Some(function), user_data) };
+        }
+        let pointer = self.pointer.get();
+        self.pointer_state(pointer.position, pointer.is_down);
+        *self.restored_scroll_positions.borrow_mut() = scroll_positions;
+        Ok(())
+    }
+
+    /// Applies scroll positions saved by [`Clay::grow`] to containers that exist by now
+    #[cfg(feature = "alloc")]
+    fn restore_scroll_positions(&self) {
+        let mut positions = self.restored_scroll_positions.borrow_mut();
+        if positions.is_empty() {
+            return;
+        }
+        positions.retain(|&(id, position)| unsafe {
+            let data = Clay_GetScrollContainerData(Clay_ElementId {
+                id,
+                ..core::mem::zeroed()
+            });
+            if data.found && !data.scrollPosition.is_null() {
+                *data.scrollPosition = position.into();
+                false
+            } else {
+                true
+            }
+        });
+    }
+
     /// Generates a unique ID based on the given `label`.
     ///
     /// This ID is global and must be unique across the entire scope.
@@ -445,35 +1296,36 @@ impl Clay {
         unsafe { Clay_PointerOver(cfg.id) }
     }
 
-    #[cfg(feature = "std")]
+    #[cfg(feature = "alloc")]
     /// Z-sorted list of element IDs that the cursor is currently over
     pub fn pointer_over_ids(&self) -> Vec<Id> {
-        unsafe {
+        self.pointer_over_ids_iter().collect()
+    }
+
+    /// Z-sorted element IDs that the cursor is currently over, without allocating
+    pub fn pointer_over_ids_iter(&self) -> impl Iterator<Item = Id> + '_ {
+        let slice = unsafe {
             let array = Clay_GetPointerOverIds();
-            let slice = core::slice::from_raw_parts(array.internalArray, array.length as _);
-            slice.iter().map(|&id| Id { id }).collect()
-        }
+            core::slice::from_raw_parts(array.internalArray, array.length as _)
+        };
+        slice.iter().map(|&id| Id { id })
     }
 
-    #[cfg(not(feature = "std"))]
+    /// Creates clay in caller-provided memory, see [`Clay::new_with_buffer`] for a safe
+    /// alternative.
+    /// # Safety
+    /// `memory` must point to at least [`Clay::required_memory_size`] bytes that outlive clay
     pub unsafe fn new_with_memory(dimensions: Dimensions, memory: *mut core::ffi::c_void) -> Self {
-        let memory_size = Self::required_memory_size();
-        let arena = Clay_CreateArenaWithCapacityAndMemory(memory_size as _, memory);
-
-        let context = Clay_Initialize(
-            arena,
-            dimensions.into(),
-            Clay_ErrorHandler {
-                errorHandlerFunction: Some(error_handler),
-                userData: core::ptr::null_mut(),
-            },
-        );
+        let memory = Memory::Borrowed(memory, Self::required_memory_size());
+        Self::with_memory(dimensions, memory)
+    }
 
-        Self {
-            _memory: memory,
-            context,
-            text_measure_callback: None,
-        }
+    /// Sets the buffer [`ClayLayoutScope::text_fmt`] copies text into, it's reused every frame.
+    /// Text that doesn't fit in the rest of the buffer is cut off, `text_fmt` returns `false` then.
+    #[cfg(not(feature = "alloc"))]
+    pub fn set_string_storage(&mut self, storage: &'static mut [core::mem::MaybeUninit<u8>]) {
+        self.string_storage = (storage.as_mut_ptr() as _, storage.len());
+        self.string_storage_used.set(0);
     }
 
     /// Wrapper for `Clay_MinMemorySize`, returns the minimum required memory by clay
@@ -482,7 +1334,7 @@ impl Clay {
     }
 
     /// Set the callback for text measurement with user data
-    #[cfg(feature = "std")]
+    #[cfg(feature = "alloc")]
     pub fn set_measure_text_function_user_data<'clay, F, T>(
         &'clay mut self,
         userdata: T,
@@ -497,20 +1349,15 @@ impl Clay {
         // Get a raw pointer to the boxed data
         let user_data_ptr = Box::into_raw(boxed) as _;
 
-        // Register the callback with the external C function
-        unsafe {
-            Self::set_measure_text_function_unsafe(
-                measure_text_trampoline_user_data::<F, T>,
-                user_data_ptr,
-            );
-        }
-
-        // Store the raw pointer for later cleanup
-        self.text_measure_callback = Some(user_data_ptr as *const core::ffi::c_void);
+        self.store_measure_text_function(
+            measure_text_trampoline_user_data::<F, T>,
+            user_data_ptr,
+            Some(drop_boxed::<(F, T)>),
+        );
     }
 
     /// Set the callback for text measurement
-    #[cfg(feature = "std")]
+    #[cfg(feature = "alloc")]
     pub fn set_measure_text_function<F>(&mut self, callback: F)
     where
         F: Fn(&str, &TextConfig) -> Dimensions + 'static,
@@ -522,13 +1369,52 @@ impl Clay {
         // Get a raw pointer to the boxed data
         let user_data_ptr = Box::into_raw(boxed) as *mut core::ffi::c_void;
 
-        // Register the callback with the external C function
+        self.store_measure_text_function(
+            measure_text_trampoline::<F>,
+            user_data_ptr,
+            Some(drop_boxed::<(F, usize)>),
+        );
+    }
+
+    /// Set the callback for text measurement without allocating, the callback lives in a
+    /// caller-provided slot, for example a `static`
+    pub fn set_measure_text_function_static<F>(&mut self, callback: &'static F)
+    where
+        F: Fn(&str, &TextConfig) -> Dimensions + 'static,
+    {
+        self.store_measure_text_function(
+            measure_text_trampoline_static::<F>,
+            callback as *const F as _,
+            None,
+        );
+    }
+
+    /// Registers `function` with clay and keeps it to measure text from Rust, freeing the
+    /// previous callback data
+    fn store_measure_text_function(
+        &mut self,
+        function: MeasureTextFunction,
+        user_data: *mut core::ffi::c_void,
+        free: Option<FreeCallback>,
+    ) {
         unsafe {
-            Self::set_measure_text_function_unsafe(measure_text_trampoline::<F>, user_data_ptr);
+            Self::set_measure_text_function_unsafe(function, user_data);
+            self.free_measure_text_callback();
         }
 
         // Store the raw pointer for later cleanup
-        self.text_measure_callback = Some(user_data_ptr as *const core::ffi::c_void);
+        self.text_measure_callback = Some(user_data as *const core::ffi::c_void);
+        self.free_text_measure_callback = free;
+        self.measure_text_function = Some(function);
+    }
+
+    unsafe fn free_measure_text_callback(&mut self) {
+        if let (Some(ptr), Some(free)) = (
+            self.text_measure_callback.take(),
+            self.free_text_measure_callback.take(),
+        ) {
+            free(ptr);
+        }
     }
 
     /// Set the callback for text measurement with user data.
@@ -545,21 +1431,77 @@ impl Clay {
         Clay_SetMeasureTextFunction(Some(callback), user_data);
     }
 
+    /// Measures `text` with the function set through [`Clay::set_measure_text_function`] or
+    /// [`Clay::set_measure_text_function_user_data`].
+    ///
+    /// Returns `None` if no measure function was set from Rust.
+    pub fn measure_text(&self, text: &str, config: &TextConfig) -> Option<Dimensions> {
+        let function = self.measure_text_function?;
+        let user_data = self.text_measure_callback? as *mut core::ffi::c_void;
+        let mut config: Clay_TextElementConfig = (*config).into();
+        let slice = Clay_StringSlice {
+            length: text.len() as _,
+            chars: text.as_ptr() as _,
+            baseChars: text.as_ptr() as _,
+        };
+        #[cfg(feature = "std")]
+        let calls = MEASURE_CALLS.with(|calls| calls.get());
+        let dimensions = unsafe { function(slice, &mut config, user_data) }.into();
+        // Measuring from Rust doesn't count towards clay's statistics
+        #[cfg(feature = "std")]
+        MEASURE_CALLS.with(|c| c.set(calls));
+        Some(dimensions)
+    }
+
     /// Sets the maximum number of element that clay supports
     /// **Use only if you know what you are doing or your getting errors from clay**
     pub fn max_element_count(&mut self, max_element_count: u32) {
         unsafe {
+            Clay_SetCurrentContext(self.context);
             Clay_SetMaxElementCount(max_element_count as _);
         }
     }
+
+    /// Returns the maximum number of elements that clay supports
+    pub fn get_max_element_count(&self) -> u32 {
+        unsafe {
+            Clay_SetCurrentContext(self.context);
+            Clay_GetMaxElementCount() as _
+        }
+    }
+
     /// Sets the capacity of the cache used for text in the measure text function
     /// **Use only if you know what you are doing or your getting errors from clay**
-    pub fn max_measure_text_cache_word_count(&self, count: u32) {
+    pub fn max_measure_text_cache_word_count(&mut self, count: u32) {
         unsafe {
-            Clay_SetMaxElementCount(count as _);
+            Clay_SetCurrentContext(self.context);
+            Clay_SetMaxMeasureTextCacheWordCount(count as _);
         }
     }
 
+    /// Returns the capacity of the cache used for text in the measure text function
+    pub fn get_max_measure_text_cache_word_count(&self) -> u32 {
+        unsafe {
+            Clay_SetCurrentContext(self.context);
+            Clay_GetMaxMeasureTextCacheWordCount() as _
+        }
+    }
+
+    /// Clears all cached text measurements, use after fonts were reloaded or changed
+    pub fn reset_measure_text_cache(&self) {
+        unsafe {
+            Clay_SetCurrentContext(self.context);
+            Clay_ResetMeasureTextCache();
+        }
+    }
+
+    /// Returns the text measurement statistics of the last frame finished with
+    /// [`ClayLayoutScope::end`]. They are empty if another clay began a frame in the meantime.
+    #[cfg(feature = "std")]
+    pub fn measure_text_stats(&self) -> MeasureTextStats {
+        self.measure_text_stats.get()
+    }
+
     /// Enables or disables the debug mode of clay
     pub fn set_debug_mode(&self, enable: bool) {
         unsafe {
@@ -574,6 +1516,7 @@ impl Clay {
 
     /// Enables or disables culling
     pub fn set_culling(&self, enable: bool) {
+        self.culling.set(enable);
         unsafe {
             Clay_SetCullingEnabled(enable);
         }
@@ -582,16 +1525,40 @@ impl Clay {
     /// Sets the dimensions of the global layout, use if, for example the window size you render to
     /// changed
     pub fn set_layout_dimensions(&self, dimensions: Dimensions) {
+        self.layout_dimensions.set(dimensions);
         unsafe {
             Clay_SetLayoutDimensions(dimensions.into());
         }
     }
+
+    /// Sets how many pixels renderers draw per layout unit, for example `2.0` on a high DPI
+    /// display. Layout dimensions, pointer positions and text measurement stay in layout units.
+    pub fn set_scale_factor(&self, scale_factor: f32) {
+        self.scale_factor.set(scale_factor);
+    }
+
+    /// Returns the scale factor set with [`Clay::set_scale_factor`]
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor.get()
+    }
     /// Updates the state of the pointer for clay. Used to update scroll containers and for
     /// interactions functions
     pub fn pointer_state(&self, position: Vector2, is_down: bool) {
+        let was_down = self.pointer.replace(PointerState { position, is_down }).is_down;
         unsafe {
             Clay_SetPointerState(position.into(), is_down);
         }
+        self.update_scrollbars(was_down);
+    }
+
+    /// Returns the pointer position last passed to [`Clay::pointer_state`]
+    pub fn pointer_position(&self) -> Vector2 {
+        self.pointer.get().position
+    }
+
+    /// Returns if the pointer was down in the last call to [`Clay::pointer_state`]
+    pub fn is_pointer_down(&self) -> bool {
+        self.pointer.get().is_down
     }
     pub fn update_scroll_containers(
         &self,
@@ -602,6 +1569,40 @@ impl Clay {
         unsafe {
             Clay_UpdateScrollContainers(drag_scrolling_enabled, scroll_delta.into(), delta_time);
         }
+        self.advance_scroll_animations(scroll_delta, delta_time);
+    }
+
+    /// Lets the renderer apply scroll offsets instead of clay, which then queries them with the
+    /// function set through [`Clay::set_query_scroll_offset_function`]
+    pub fn set_external_scroll_handling(&self, enable: bool) {
+        self.external_scroll_handling.set(enable);
+        unsafe {
+            Clay_SetExternalScrollHandlingEnabled(enable);
+        }
+    }
+
+    /// Set the callback clay uses to query the scroll offset of a container by its element id
+    /// when external scroll handling is enabled, so the scroll state can live outside of clay
+    #[cfg(feature = "alloc")]
+    pub fn set_query_scroll_offset_function<F>(&mut self, callback: F)
+    where
+        F: Fn(u32) -> Vector2 + 'static,
+    {
+        // Tuple here is to prevent Rust ZST optimization from breaking getting a raw pointer
+        let user_data = Box::into_raw(Box::new((callback, 0usize))) as *mut core::ffi::c_void;
+        let function = query_scroll_offset_trampoline::<F> as QueryScrollOffsetFunction;
+
+        unsafe {
+            Clay_SetQueryScrollOffsetFunction(Some(function), user_data);
+            self.free_query_scroll_offset_callback();
+        }
+        self.query_scroll_offset = Some((function, user_data, Some(drop_boxed::<(F, usize)>)));
+    }
+
+    unsafe fn free_query_scroll_offset_callback(&mut self) {
+        if let Some((_, user_data, Some(free))) = self.query_scroll_offset.take() {
+            free(user_data);
+        }
     }
 
     /// Returns if the current element you are creating is hovered
@@ -622,6 +1623,12 @@ impl Clay {
             None
         }
     }
+    /// Returns a handle to the scroll container with `id` of the last layout
+    pub fn scroll_container(&self, id: Id) -> Option<scroll::ScrollContainer<'_>> {
+        unsafe { Clay_SetCurrentContext(self.context) };
+        scroll::ScrollContainer::new(self, id)
+    }
+
     pub fn scroll_container_data(&self, id: Id) -> Option<Clay_ScrollContainerData> {
         unsafe {
             Clay_SetCurrentContext(self.context);
@@ -636,13 +1643,11 @@ impl Clay {
     }
 }
 
-#[cfg(feature = "std")]
 impl Drop for Clay {
     fn drop(&mut self) {
         unsafe {
-            if let Some(ptr) = self.text_measure_callback {
-                let _ = Box::from_raw(ptr as *mut (usize, usize));
-            }
+            self.free_measure_text_callback();
+            self.free_query_scroll_offset_callback();
 
             Clay_SetCurrentContext(core::ptr::null_mut() as _);
         }
@@ -681,15 +1686,22 @@ impl From<Clay_StringSlice> for &str {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use color::Color;
     use layout::{Padding, Sizing};
 
+    /// Serializes the tests that create a [`Clay`], as the current context of clay is global
+    pub(crate) fn lock_context() -> std::sync::MutexGuard<'static, ()> {
+        static CONTEXT: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        CONTEXT.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
     #[rustfmt::skip]
     #[test]
     fn test_begin() {
+        let _context = lock_context();
         let mut callback_data = 0u32;
 
         let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
@@ -705,7 +1717,7 @@ mod tests {
 
         let mut clay = clay.begin::<(), ()>();
 
-        clay.with(&Declaration::new()
+        clay.with(Declaration::new()
             .id(clay.id("parent_rect"))
             .layout()
                 .width(Sizing::Fixed(100.0))
@@ -714,7 +1726,7 @@ mod tests {
                 .end()
             .background_color(Color::rgb(255., 255., 255.)), |clay|
         {
-            clay.with(&Declaration::new()
+            clay.with(Declaration::new()
                 .layout()
                     .width(Sizing::Fixed(100.0))
                     .height(Sizing::Fixed(100.0))
@@ -722,7 +1734,7 @@ mod tests {
                     .end()
                 .background_color(Color::rgb(255., 255., 255.)), |clay|
             {
-                clay.with(&Declaration::new()
+                clay.with(Declaration::new()
                     .id(clay.id("rect_under_rect"))
                     .layout()
                         .width(Sizing::Fixed(100.0))
@@ -740,7 +1752,7 @@ mod tests {
             });
         });
 
-        clay.with(&Declaration::new()
+        clay.with(Declaration::new()
             .id(clay.id_index("border_container", 1))
             .layout()
                 .padding(Padding::all(16))
@@ -751,7 +1763,7 @@ mod tests {
                 .end()
             .corner_radius().all(10.0).end(), |clay|
         {
-            clay.with(&Declaration::new()
+            clay.with(Declaration::new()
                 .id(clay.id("rect_under_border"))
                 .layout()
                     .width(Sizing::Fixed(50.0))
@@ -774,6 +1786,7 @@ mod tests {
     #[rustfmt::skip]
     #[test]
     fn test_simple_text_measure() {
+        let _context = lock_context();
         let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
 
         clay.set_measure_text_function(|_text, _config| {
@@ -782,7 +1795,7 @@ mod tests {
 
         let mut clay = clay.begin::<(), ()>();
 
-        clay.with(&Declaration::new()
+        clay.with(Declaration::new()
             .id(clay.id("parent_rect"))
             .layout()
                 .width(Sizing::Fixed(100.0))
@@ -809,4 +1822,495 @@ mod tests {
 
         let _items = clay.end();
     }
+
+    /// Declares `count` rectangles inside a vertically scrolling container with the id "list"
+    fn declare_list<'render>(
+        clay: &mut ClayLayoutScope<'render, 'render, (), (), ()>,
+        count: usize,
+    ) {
+        let mut list = Declaration::new();
+        list.id(clay.id("list"))
+            .layout()
+            .width(Sizing::Fixed(100.0))
+            .height(Sizing::Fixed(100.0))
+            .end()
+            .clip(false, true, Vector2::default());
+        clay.with(&list, |clay| {
+            for _ in 0..count {
+                let mut item = Declaration::new();
+                item.layout()
+                    .width(Sizing::Fixed(100.0))
+                    .height(Sizing::Fixed(50.0))
+                    .end()
+                    .background_color(Color::rgb(255., 0., 0.));
+                clay.with(&item, |_| {});
+            }
+        });
+    }
+
+    #[test]
+    fn auto_grow_declares_the_frame_again() {
+        let _context = lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        clay.max_element_count(16);
+        clay.set_auto_grow(Some(usize::MAX));
+
+        let mut declarations = 0;
+        let commands = clay
+            .layout(|clay| {
+                declarations += 1;
+                declare_list(clay, 40);
+            })
+            .unwrap();
+        // The scissor start and end of the list around the rectangles
+        assert_eq!(commands.len(), 42);
+        assert_eq!(declarations, 3);
+        assert_eq!(clay.get_max_element_count(), 64);
+        assert_eq!(clay.memory_size(), Clay::required_memory_size());
+    }
+
+    #[test]
+    fn auto_grow_fails_past_the_memory_limit() {
+        let _context = lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        clay.max_element_count(16);
+        clay.set_auto_grow(Some(1024));
+
+        let result = clay.layout(|clay| declare_list(clay, 40));
+        assert!(matches!(
+            result,
+            Err(LayoutError::CapacityExceeded {
+                max_memory: 1024,
+                ..
+            })
+        ));
+        assert_eq!(clay.get_max_element_count(), 16);
+
+        // `end` can't tell, `try_end` can
+        clay.set_auto_grow(Some(usize::MAX));
+        let mut scope = clay.begin::<(), ()>();
+        declare_list(&mut scope, 40);
+        assert!(matches!(scope.try_end(), Err(LayoutError::Grown)));
+    }
+
+    #[test]
+    fn auto_grow_keeps_scroll_positions() {
+        let _context = lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        clay.max_element_count(16);
+        clay.set_auto_grow(Some(usize::MAX));
+        let list = clay.id("list");
+
+        clay.layout(|clay| declare_list(clay, 4)).unwrap();
+        let data = clay.scroll_container_data(list).unwrap();
+        unsafe { *data.scrollPosition = Vector2::new(0.0, -30.0).into() };
+
+        clay.layout(|clay| declare_list(clay, 40)).unwrap();
+        assert_eq!(clay.get_max_element_count(), 64);
+        let data = clay.scroll_container_data(list).unwrap();
+        assert_eq!(unsafe { (*data.scrollPosition).y }, -30.0);
+    }
+
+    /// Declares "hello world" and "foo" and returns the statistics of the frame
+    fn measure_frame(clay: &mut Clay) -> MeasureTextStats {
+        {
+            let mut scope = clay.begin::<(), ()>();
+            scope.text("hello world", TextConfig::new().font_size(16).end());
+            scope.text("foo", TextConfig::new().font_size(16).end());
+            let _commands = scope.end();
+        }
+        clay.measure_text_stats()
+    }
+
+    #[test]
+    fn measure_text_stats_count_cache_hits_and_misses() {
+        let _context = lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        clay.set_measure_text_function(|text, _| Dimensions::new(text.len() as f32 * 10.0, 16.0));
+
+        // The space, "hello" and "world", then the space and "foo"
+        let stats = measure_frame(&mut clay);
+        assert_eq!(stats.measure_calls, 5);
+        assert_eq!((stats.cache_hits, stats.cache_misses), (0, 2));
+
+        let stats = measure_frame(&mut clay);
+        assert_eq!(stats.measure_calls, 0);
+        assert_eq!((stats.cache_hits, stats.cache_misses), (2, 0));
+
+        clay.reset_measure_text_cache();
+        let stats = measure_frame(&mut clay);
+        assert_eq!(stats.measure_calls, 5);
+        assert_eq!((stats.cache_hits, stats.cache_misses), (0, 2));
+    }
+
+    #[test]
+    fn measure_text_cache_word_count_keeps_the_element_count() {
+        let _context = lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        let max_element_count = clay.get_max_element_count();
+        clay.max_measure_text_cache_word_count(1024);
+        assert_eq!(clay.get_max_measure_text_cache_word_count(), 1024);
+        assert_eq!(clay.get_max_element_count(), max_element_count);
+    }
+
+    /// Lays out text clamped to two lines in a 100 wide element and returns the text lines
+    fn clamped_text_lines(clay: &mut Clay) -> Vec<(String, f32)> {
+        let mut scope = clay.begin::<(), ()>();
+        let mut parent = Declaration::new();
+        parent.layout().width(Sizing::Fixed(100.0)).end();
+        scope.with(&parent, |scope| {
+            scope.text(
+                "aaa bbb ccc ddd eee",
+                TextConfig::new()
+                    .font_size(16)
+                    .max_lines(2)
+                    .overflow(text::TextOverflow::EllipsisEnd)
+                    .end(),
+            );
+        });
+        scope
+            .end()
+            .filter_map(|command| match command.config {
+                render_commands::RenderCommandConfig::Text(text) => {
+                    Some((text.text.to_owned(), command.bounding_box.width))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    unsafe extern "C" fn measure_ten_per_byte(
+        text: Clay_StringSlice,
+        _config: *mut Clay_TextElementConfig,
+        _user_data: *mut core::ffi::c_void,
+    ) -> Clay_Dimensions {
+        Clay_Dimensions {
+            width: text.length as f32 * 10.0,
+            height: 16.0,
+        }
+    }
+
+    #[test]
+    fn clamped_text_is_ellipsized() {
+        let _context = lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        clay.set_measure_text_function(|text, _| {
+            Dimensions::new(text.chars().count() as f32 * 10.0, 16.0)
+        });
+
+        let lines = clamped_text_lines(&mut clay);
+        assert_eq!(
+            lines,
+            [
+                ("aaa bbb".to_owned(), 70.0),
+                ("ccc ddd e…".to_owned(), 100.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn clamped_text_keeps_its_lines_without_a_rust_measure_function() {
+        let _context = lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        unsafe {
+            Clay::set_measure_text_function_unsafe(measure_ten_per_byte, core::ptr::null_mut())
+        };
+
+        let lines = clamped_text_lines(&mut clay);
+        assert_eq!(
+            lines,
+            [("aaa bbb".to_owned(), 70.0), ("ccc ddd".to_owned(), 70.0)]
+        );
+    }
+
+    #[test]
+    fn user_data_is_returned_after_the_frame_ends() {
+        let _context = lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        clay.set_measure_text_function(|text, _| Dimensions::new(text.len() as f32 * 10.0, 16.0));
+        let card = clay.id("card");
+        let data = 42u32;
+
+        let mut scope = clay.begin_with_user_data::<(), (), u32>();
+        let mut declaration = Declaration::new();
+        declaration
+            .id(card)
+            .user_data(&data)
+            .background_color(Color::rgb(255., 0., 0.));
+        scope.with(&declaration, |scope| {
+            scope.text("title", TextConfig::new().font_size(16).end());
+        });
+        let commands: Vec<_> = scope
+            .end()
+            .map(|command| match command.config {
+                render_commands::RenderCommandConfig::Text(_) => ("text", command.user_data),
+                _ => ("rectangle", command.user_data),
+            })
+            .collect();
+
+        assert_eq!(commands, [("rectangle", Some(&42)), ("text", None)]);
+        assert_eq!(scope.user_data(card), Some(&42));
+        assert_eq!(scope.user_data(scope.id("missing")), None);
+    }
+
+    fn measure_ten_per_char(text: &str, _: &TextConfig) -> Dimensions {
+        Dimensions::new(text.chars().count() as f32 * 10.0, 16.0)
+    }
+
+    #[test]
+    fn clay_lives_in_a_caller_provided_buffer() {
+        let _context = lock_context();
+        let dimensions = Dimensions::new(800.0, 600.0);
+        let too_small = Vec::leak(vec![core::mem::MaybeUninit::uninit(); 16]);
+        assert!(Clay::new_with_buffer(dimensions, too_small).is_none());
+
+        let memory = vec![core::mem::MaybeUninit::uninit(); Clay::required_memory_size()];
+        let mut clay = Clay::new_with_buffer(dimensions, Vec::leak(memory)).unwrap();
+        static MEASURE: fn(&str, &TextConfig) -> Dimensions = measure_ten_per_char;
+        clay.set_measure_text_function_static(&MEASURE);
+        assert_eq!(
+            clay.measure_text("abc", &TextConfig::new()),
+            Some(Dimensions::new(30.0, 16.0))
+        );
+
+        let mut scope = clay.begin::<(), ()>();
+        scope.text("hello", TextConfig::new().font_size(16).end());
+        let widths: Vec<f32> = scope
+            .end()
+            .map(|command| command.bounding_box.width)
+            .collect();
+        assert_eq!(widths, [50.0]);
+    }
+}
+
+#[cfg(all(test, not(feature = "alloc")))]
+mod no_alloc_tests {
+    extern crate std;
+
+    use super::*;
+    use core::mem::MaybeUninit;
+    use std::vec::Vec;
+
+    fn measure_ten_per_char(text: &str, _: &TextConfig) -> Dimensions {
+        Dimensions::new(text.chars().count() as f32 * 10.0, 16.0)
+    }
+    static MEASURE: fn(&str, &TextConfig) -> Dimensions = measure_ten_per_char;
+
+    fn leak(len: usize) -> &'static mut [MaybeUninit<u8>] {
+        Vec::leak(std::vec![MaybeUninit::uninit(); len])
+    }
+
+    fn texts<'render>(
+        clay: &mut ClayLayoutScope<'render, 'render, (), ()>,
+    ) -> Vec<(std::string::String, f32)> {
+        clay.end()
+            .filter_map(|command| match command.config {
+                render_commands::RenderCommandConfig::Text(text) => {
+                    Some((text.text.into(), command.bounding_box.width))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn text_fmt_copies_text_into_the_string_storage() {
+        let dimensions = Dimensions::new(800.0, 600.0);
+        let mut clay =
+            Clay::new_with_buffer(dimensions, leak(Clay::required_memory_size())).unwrap();
+        clay.set_measure_text_function_static(&MEASURE);
+
+        {
+            let mut scope = clay.begin::<(), ()>();
+            // Without a buffer the text is empty
+            assert!(!scope.text_fmt(format_args!("{} fps", 60), TextConfig::new().end()));
+            assert_eq!(texts(&mut scope), []);
+        }
+
+        clay.set_string_storage(leak(8));
+        for _ in 0..2 {
+            let mut scope = clay.begin::<(), ()>();
+            assert!(scope.text_fmt(format_args!("{} fps", 60), TextConfig::new().end()));
+            // Cut off at the end of the buffer
+            assert!(!scope.text_fmt(format_args!("{} ms", 16.5), TextConfig::new().end()));
+            // The buffer is reused every frame
+            assert_eq!(
+                texts(&mut scope),
+                [("60 fps".into(), 60.0), ("16".into(), 20.0)]
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod declaration_tests {
+    use super::*;
+    use elements::{
+        FloatingAttachPointType, FloatingAttachToElement, FloatingClipToElement,
+        PointerCaptureMode,
+    };
+    use layout::{Alignment, LayoutAlignmentX, LayoutAlignmentY, LayoutDirection, Padding, Sizing};
+
+    #[test]
+    fn new_is_empty() {
+        let declaration = Declaration::<(), ()>::new();
+        assert!(declaration.id.is_none());
+        assert!(declaration.scrollbar.is_none());
+        assert!(declaration.inner.userData.is_null());
+        assert!(declaration.inner.image.imageData.is_null());
+        assert!(declaration.inner.custom.customData.is_null());
+    }
+
+    #[test]
+    fn id() {
+        let id = Id::new("declaration");
+        let mut declaration = Declaration::<(), ()>::new();
+        declaration.id(id);
+        assert_eq!(declaration.id.unwrap().id.id, id.id.id);
+    }
+
+    #[test]
+    fn background_color() {
+        let mut declaration = Declaration::<(), ()>::new();
+        declaration.background_color(Color::rgba(1., 2., 3., 4.));
+        let color = declaration.inner.backgroundColor;
+        assert_eq!((color.r, color.g, color.b, color.a), (1., 2., 3., 4.));
+    }
+
+    #[test]
+    fn aspect_ratio() {
+        let mut declaration = Declaration::<(), ()>::new();
+        declaration.aspect_ratio(1.5);
+        assert_eq!(declaration.inner.aspectRatio.aspectRatio, 1.5);
+    }
+
+    #[test]
+    fn clip() {
+        let mut declaration = Declaration::<(), ()>::new();
+        declaration.clip(true, false, Vector2::new(-1., -2.));
+        let clip = declaration.inner.clip;
+        assert!(clip.horizontal);
+        assert!(!clip.vertical);
+        assert_eq!((clip.childOffset.x, clip.childOffset.y), (-1., -2.));
+    }
+
+    #[test]
+    fn user_data_and_element_data() {
+        let (image, custom, user) = (1u8, 2u16, 3u32);
+        let mut declaration = Declaration::<u8, u16, u32>::new();
+        declaration
+            .image()
+            .data(&image)
+            .end()
+            .custom_element(&custom)
+            .user_data(&user);
+        assert_eq!(declaration.inner.image.imageData as *const u8, &image as *const u8);
+        assert_eq!(declaration.inner.custom.customData as *const u16, &custom as *const u16);
+        assert_eq!(declaration.inner.userData as *const u32, &user as *const u32);
+    }
+
+    #[test]
+    fn layout() {
+        let mut declaration = Declaration::<(), ()>::new();
+        declaration
+            .layout()
+            .width(Sizing::Fixed(10.))
+            .height(Sizing::Grow(1., 2.))
+            .padding(Padding::new(1, 2, 3, 4))
+            .child_gap(5)
+            .child_alignment(Alignment::new(LayoutAlignmentX::Right, LayoutAlignmentY::Bottom))
+            .direction(LayoutDirection::TopToBottom);
+        let layout = declaration.inner.layout;
+
+        let width = layout.sizing.width;
+        assert_eq!(width.type_, layout::SizingType::Fixed as u8);
+        let min_max = unsafe { width.size.minMax };
+        assert_eq!((min_max.min, min_max.max), (10., 10.));
+        let height = layout.sizing.height;
+        assert_eq!(height.type_, layout::SizingType::Grow as u8);
+        let min_max = unsafe { height.size.minMax };
+        assert_eq!((min_max.min, min_max.max), (1., 2.));
+
+        let padding = layout.padding;
+        assert_eq!((padding.left, padding.right, padding.top, padding.bottom), (1, 2, 3, 4));
+        assert_eq!(layout.childGap, 5);
+        assert_eq!(layout.childAlignment.x, LayoutAlignmentX::Right as u8);
+        assert_eq!(layout.childAlignment.y, LayoutAlignmentY::Bottom as u8);
+        assert_eq!(layout.layoutDirection, LayoutDirection::TopToBottom as u8);
+    }
+
+    #[test]
+    fn floating() {
+        let mut declaration = Declaration::<(), ()>::new();
+        declaration
+            .floating()
+            .offset(Vector2::new(1., 2.))
+            .dimensions(Dimensions::new(3., 4.))
+            .z_index(5)
+            .parent_id(6)
+            .attach_points(FloatingAttachPointType::RightBottom, FloatingAttachPointType::CenterTop)
+            .attach_to(FloatingAttachToElement::ElementWithId)
+            .clip_to(FloatingClipToElement::AttachedParent)
+            .pointer_capture_mode(PointerCaptureMode::Passthrough);
+        let floating = declaration.inner.floating;
+        assert_eq!((floating.offset.x, floating.offset.y), (1., 2.));
+        assert_eq!((floating.expand.width, floating.expand.height), (3., 4.));
+        assert_eq!(floating.zIndex, 5);
+        assert_eq!(floating.parentId, 6);
+        assert_eq!(floating.attachPoints.element, FloatingAttachPointType::RightBottom as u8);
+        assert_eq!(floating.attachPoints.parent, FloatingAttachPointType::CenterTop as u8);
+        assert_eq!(floating.attachTo, FloatingAttachToElement::ElementWithId as u8);
+        assert_eq!(floating.clipTo, FloatingClipToElement::AttachedParent as u8);
+        assert_eq!(floating.pointerCaptureMode, PointerCaptureMode::Passthrough as u8);
+    }
+
+    #[test]
+    fn border() {
+        let mut declaration = Declaration::<(), ()>::new();
+        declaration
+            .border()
+            .left(1)
+            .right(2)
+            .top(3)
+            .bottom(4)
+            .between_children(5)
+            .color(Color::rgb(6., 7., 8.));
+        let border = declaration.inner.border;
+        let width = border.width;
+        assert_eq!(
+            (width.left, width.right, width.top, width.bottom, width.betweenChildren),
+            (1, 2, 3, 4, 5)
+        );
+        assert_eq!((border.color.r, border.color.g, border.color.b), (6., 7., 8.));
+
+        declaration.border().all_directions(9);
+        let width = declaration.inner.border.width;
+        assert_eq!((width.left, width.right, width.top, width.bottom), (9, 9, 9, 9));
+    }
+
+    #[test]
+    fn corner_radius() {
+        let mut declaration = Declaration::<(), ()>::new();
+        declaration
+            .corner_radius()
+            .top_left(1.)
+            .top_right(2.)
+            .bottom_left(3.)
+            .bottom_right(4.);
+        let radius = declaration.inner.cornerRadius;
+        assert_eq!(
+            (radius.topLeft, radius.topRight, radius.bottomLeft, radius.bottomRight),
+            (1., 2., 3., 4.)
+        );
+    }
+
+    #[test]
+    fn scrollbar() {
+        let mut declaration = Declaration::<(), ()>::new();
+        declaration.scrollbar().width(12.).z_index(3);
+        let scrollbar = declaration.scrollbar.unwrap();
+        assert_eq!((scrollbar.width, scrollbar.z_index), (12., 3));
+        // The scrollbar is declared by the wrapper, clay never sees it
+        assert!(!declaration.inner.clip.horizontal && !declaration.inner.clip.vertical);
+    }
 }
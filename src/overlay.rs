@@ -0,0 +1,368 @@
+//! Modal dialogs, popovers and context menus that float above the layout.
+//!
+//! [`Overlays`] keeps a stack of open overlays keyed by [`Id`]. It is updated once per frame
+//! after the pointer state was set, dismissing overlays clicked outside of, and declares the
+//! overlays with [`Overlays::declare`]. Overlays opened later are stacked above earlier ones.
+
+use crate::{
+    bindings::*,
+    color::Color,
+    elements::{FloatingAttachPointType, FloatingAttachToElement, PointerCaptureMode},
+    id::Id,
+    layout::Sizing,
+    math::{BoundingBox, Dimensions, Vector2},
+    Clay, ClayLayoutScope, Declaration,
+};
+
+/// The maximum number of overlays open at once.
+pub const MAX_OVERLAYS: usize = 16;
+
+/// The side of its anchor a popover is placed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// Where an overlay is placed.
+#[derive(Debug, Clone, Copy)]
+pub enum Placement {
+    /// Centered on the root, like a modal dialog.
+    Center,
+    /// Next to the element with the id, on the given side, like a popover.
+    Anchor(Id, Side),
+    /// At a position in the root, like a context menu.
+    Point(Vector2),
+}
+
+/// How an overlay is placed, shown and dismissed.
+#[derive(Debug, Clone, Copy)]
+pub struct OverlayConfig {
+    pub placement: Placement,
+    /// Distance between a popover and its anchor.
+    pub gap: f32,
+    /// Color of the backdrop covering everything below the overlay. Overlays with a backdrop
+    /// are modal, pointer input doesn't reach the overlays and elements below them.
+    pub backdrop: Option<Color>,
+    /// Whether pressing the pointer outside of the overlay closes it.
+    pub dismiss_on_outside_click: bool,
+}
+
+impl OverlayConfig {
+    /// A dialog centered over a backdrop, closed only explicitly.
+    pub fn modal() -> Self {
+        Self {
+            placement: Placement::Center,
+            gap: 0.0,
+            backdrop: Some(Color::rgba(0.0, 0.0, 0.0, 128.0)),
+            dismiss_on_outside_click: false,
+        }
+    }
+
+    /// A popup next to `anchor`, flipped to the opposite side if `side` has no room.
+    pub fn popover(anchor: Id, side: Side) -> Self {
+        Self {
+            placement: Placement::Anchor(anchor, side),
+            gap: 4.0,
+            backdrop: None,
+            dismiss_on_outside_click: true,
+        }
+    }
+
+    /// A menu at `position`, usually the pointer position, kept inside the root.
+    pub fn context_menu(position: Vector2) -> Self {
+        Self {
+            placement: Placement::Point(position),
+            gap: 0.0,
+            backdrop: None,
+            dismiss_on_outside_click: true,
+        }
+    }
+}
+
+/// The stack of open overlays.
+pub struct Overlays {
+    /// The z-index of the lowest overlay. Every overlay above it takes two more, one for its
+    /// backdrop and one for itself.
+    pub z_index: i16,
+    stack: [Option<(Id, OverlayConfig)>; MAX_OVERLAYS],
+    len: usize,
+    was_down: bool,
+}
+
+impl Default for Overlays {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Overlays {
+    /// Creates an empty stack whose overlays start at z-index 1000.
+    pub fn new() -> Self {
+        Self {
+            z_index: 1000,
+            stack: [None; MAX_OVERLAYS],
+            len: 0,
+            was_down: false,
+        }
+    }
+
+    /// Opens the overlay `id` on top of the others, or moves it to the top with the new
+    /// `config` if it is already open. Returns `false` if [`MAX_OVERLAYS`] are already open.
+    pub fn open(&mut self, id: Id, config: OverlayConfig) -> bool {
+        self.close(id);
+        if self.len == MAX_OVERLAYS {
+            return false;
+        }
+        self.stack[self.len] = Some((id, config));
+        self.len += 1;
+        true
+    }
+
+    /// Closes the overlay `id` and the overlays above it, which usually belong to it.
+    pub fn close(&mut self, id: Id) {
+        if let Some(index) = self.index(id) {
+            self.truncate(index);
+        }
+    }
+
+    /// Closes the topmost overlay.
+    pub fn close_top(&mut self) {
+        self.truncate(self.len.saturating_sub(1));
+    }
+
+    /// Closes every overlay.
+    pub fn close_all(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Returns if the overlay `id` is open.
+    pub fn is_open(&self, id: Id) -> bool {
+        self.index(id).is_some()
+    }
+
+    /// Returns the topmost overlay.
+    pub fn top(&self) -> Option<Id> {
+        self.len
+            .checked_sub(1)
+            .and_then(|index| self.stack[index])
+            .map(|(id, _)| id)
+    }
+
+    /// Closes the overlays the pointer was pressed outside of, from the top down to the first
+    /// overlay that was hit, can't be dismissed or is modal. Call it once per frame before the
+    /// layout.
+    pub fn update(&mut self, clay: &Clay) {
+        let is_down = clay.is_pointer_down();
+        let pressed = is_down && !self.was_down;
+        self.was_down = is_down;
+        if !pressed {
+            return;
+        }
+
+        while let Some((id, config)) = self.len.checked_sub(1).and_then(|index| self.stack[index]) {
+            let anchor_hit = match config.placement {
+                Placement::Anchor(anchor, _) => clay.pointer_over(anchor),
+                _ => false,
+            };
+            if clay.pointer_over(id) || anchor_hit || !config.dismiss_on_outside_click {
+                return;
+            }
+            self.close_top();
+            if config.backdrop.is_some() {
+                return;
+            }
+        }
+    }
+
+    /// Declares the overlay `id` from `declaration` if it is open, with `f` adding its children.
+    ///
+    /// The overlay floats over the root, so it can be declared anywhere in the layout. Its size
+    /// is taken from the last layout to keep it inside the root.
+    pub fn declare<'clay, 'render, ImageElementData, CustomElementData, UserData, F>(
+        &self,
+        scope: &mut ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>,
+        id: Id,
+        declaration: &Declaration<'render, ImageElementData, CustomElementData, UserData>,
+        f: F,
+    ) where
+        'clay: 'render,
+        F: FnOnce(
+            &mut ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>,
+        ),
+    {
+        let Some(index) = self.index(id) else {
+            return;
+        };
+        let Some((_, config)) = self.stack[index] else {
+            return;
+        };
+        let z_index = self.z_index.saturating_add(2 * index as i16);
+        let root = scope.layout_dimensions();
+
+        if let Some(color) = config.backdrop {
+            let mut backdrop = Declaration::new();
+            backdrop
+                .id(Id {
+                    id: unsafe {
+                        Clay__HashStringWithOffset("clay_rs__overlay_backdrop".into(), 0, id.id.id)
+                    },
+                })
+                .floating()
+                .attach_to(FloatingAttachToElement::Root)
+                .pointer_capture_mode(PointerCaptureMode::Capture)
+                .z_index(z_index)
+                .end()
+                .layout()
+                .width(Sizing::Fixed(root.width))
+                .height(Sizing::Fixed(root.height))
+                .end()
+                .background_color(color);
+            scope.with(&backdrop, |_| {});
+        }
+
+        let mut declaration = *declaration;
+        declaration
+            .id(id)
+            .floating()
+            .attach_to(FloatingAttachToElement::Root)
+            .pointer_capture_mode(PointerCaptureMode::Capture)
+            .z_index(z_index.saturating_add(1));
+
+        let size = scope
+            .bounding_box(id)
+            .map_or(Dimensions::default(), |bounds| {
+                Dimensions::new(bounds.width, bounds.height)
+            });
+        let anchor = match config.placement {
+            Placement::Center => None,
+            Placement::Anchor(anchor, side) => scope
+                .bounding_box(anchor)
+                .map(|anchor| (anchor, side, config.gap)),
+            Placement::Point(position) => Some((
+                BoundingBox::new(position.x, position.y, 0.0, 0.0),
+                Side::Bottom,
+                0.0,
+            )),
+        };
+        match anchor {
+            Some((anchor, side, gap)) => {
+                declaration
+                    .floating()
+                    .offset(place(anchor, size, side, gap, root));
+            }
+            None if matches!(config.placement, Placement::Center) => {
+                declaration.floating().attach_points(
+                    FloatingAttachPointType::CenterCenter,
+                    FloatingAttachPointType::CenterCenter,
+                );
+            }
+            // The anchor wasn't laid out
+            None => return,
+        }
+
+        scope.with(&declaration, f);
+    }
+
+    fn index(&self, id: Id) -> Option<usize> {
+        self.stack[..self.len]
+            .iter()
+            .position(|overlay| overlay.is_some_and(|(open, _)| open.id.id == id.id.id))
+    }
+
+    fn truncate(&mut self, len: usize) {
+        for overlay in &mut self.stack[len..self.len] {
+            *overlay = None;
+        }
+        self.len = self.len.min(len);
+    }
+}
+
+/// Places an overlay of `size` next to `anchor` on `side`, separated by `gap`. It flips to the
+/// opposite side if it would leave `root` and fits there, and is shifted into `root` along the
+/// side.
+fn place(anchor: BoundingBox, size: Dimensions, side: Side, gap: f32, root: Dimensions) -> Vector2 {
+    // Along the side the overlay is aligned with the start of the anchor, or its end if that
+    // overflows, and then kept inside the root
+    let align = |start: f32, length: f32, content: f32, available: f32| {
+        let position = if start + content > available {
+            start + length - content
+        } else {
+            start
+        };
+        position.min(available - content).max(0.0)
+    };
+    // Across the side the overlay goes on the preferred side, unless only the other one fits
+    let flip = |start: f32, length: f32, content: f32, available: f32, before: bool| {
+        let after = start + length + gap;
+        let prior = start - gap - content;
+        let fits_after = after + content <= available;
+        let fits_before = prior >= 0.0;
+        let flipped = if before {
+            !fits_before && fits_after
+        } else {
+            !fits_after && fits_before
+        };
+        if before != flipped {
+            prior
+        } else {
+            after
+        }
+    };
+
+    match side {
+        Side::Top | Side::Bottom => Vector2::new(
+            align(anchor.x, anchor.width, size.width, root.width),
+            flip(
+                anchor.y,
+                anchor.height,
+                size.height,
+                root.height,
+                side == Side::Top,
+            ),
+        ),
+        Side::Left | Side::Right => Vector2::new(
+            flip(
+                anchor.x,
+                anchor.width,
+                size.width,
+                root.width,
+                side == Side::Left,
+            ),
+            align(anchor.y, anchor.height, size.height, root.height),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn popovers_flip_and_stay_inside_the_root() {
+        let root = Dimensions::new(200.0, 200.0);
+        let size = Dimensions::new(50.0, 40.0);
+
+        let anchor = BoundingBox::new(10.0, 10.0, 30.0, 20.0);
+        let below = place(anchor, size, Side::Bottom, 5.0, root);
+        assert_eq!((below.x, below.y), (10.0, 35.0));
+        // No room above, so it stays below
+        let above = place(anchor, size, Side::Top, 5.0, root);
+        assert_eq!((above.x, above.y), (10.0, 35.0));
+
+        // At the bottom right corner it flips up and aligns with the right edge of the anchor
+        let anchor = BoundingBox::new(160.0, 170.0, 30.0, 20.0);
+        let flipped = place(anchor, size, Side::Bottom, 5.0, root);
+        assert_eq!((flipped.x, flipped.y), (140.0, 125.0));
+
+        // A context menu at a point opens to the left when there is no room to the right
+        let point = BoundingBox::new(180.0, 20.0, 0.0, 0.0);
+        let menu = place(point, size, Side::Bottom, 0.0, root);
+        assert_eq!((menu.x, menu.y), (130.0, 20.0));
+
+        let right = place(anchor, size, Side::Right, 0.0, root);
+        assert_eq!((right.x, right.y), (110.0, 150.0));
+    }
+}
@@ -27,7 +27,7 @@ pub struct Text<'a> {
 }
 
 /// Defines individual corner radii for an element.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct CornerRadii {
     /// The radius for the top-left corner.
     pub top_left: f32,
@@ -50,7 +50,8 @@ pub struct BorderWidth {
     pub top: u16,
     /// Border width on the bottom side.
     pub bottom: u16,
-    /// Border width between child elements.
+    /// Border width between child elements. Clay draws these dividers as separate rectangle
+    /// render commands.
     pub between_children: u16,
 }
 
@@ -65,6 +66,24 @@ pub struct Border {
     pub width: BorderWidth,
 }
 
+impl Border {
+    /// Returns the horizontal and vertical radii of the inner edge of the top left, top right,
+    /// bottom left and bottom right corner. They are the outer radius minus the widths of the
+    /// sides meeting at the corner, so the border keeps the width of each side around it.
+    pub fn inner_corner_radii(&self) -> [(f32, f32); 4] {
+        let (radii, width) = (&self.corner_radii, &self.width);
+        let inner = |radius: f32, x: u16, y: u16| {
+            ((radius - x as f32).max(0.0), (radius - y as f32).max(0.0))
+        };
+        [
+            inner(radii.top_left, width.left, width.top),
+            inner(radii.top_right, width.right, width.top),
+            inner(radii.bottom_left, width.left, width.bottom),
+            inner(radii.bottom_right, width.right, width.bottom),
+        ]
+    }
+}
+
 /// Represents an image with defined dimensions and data.
 #[derive(Debug, Clone)]
 pub struct Image<'a, ImageElementData> {
@@ -207,7 +226,7 @@ impl<ImageElementData, CustomElementData>
 
 /// Represents a render command for drawing an element on the screen.
 #[derive(Debug, Clone)]
-pub struct RenderCommand<'a, ImageElementData, CustomElementData> {
+pub struct RenderCommand<'a, ImageElementData, CustomElementData, UserData = ()> {
     /// The bounding box defining the area occupied by the element.
     pub bounding_box: BoundingBox,
     /// The specific configuration for rendering this command.
@@ -217,15 +236,64 @@ pub struct RenderCommand<'a, ImageElementData, CustomElementData> {
     /// The z-index determines the stacking order of elements.
     /// Higher values are drawn above lower values.
     pub z_index: i16,
+    /// The data set with [`Declaration::user_data`](crate::Declaration::user_data) on the
+    /// element of this command. Text and scissor end commands have no user data.
+    pub user_data: Option<&'a UserData>,
+    /// How many pixels to draw per layout unit, see
+    /// [`Clay::set_scale_factor`](crate::Clay::set_scale_factor).
+    pub scale_factor: f32,
 }
 
-impl<ImageElementData, CustomElementData> RenderCommand<'_, ImageElementData, CustomElementData> {
-    pub(crate) unsafe fn from_clay_render_command(value: Clay_RenderCommand) -> Self {
+impl<ImageElementData, CustomElementData, UserData>
+    RenderCommand<'_, ImageElementData, CustomElementData, UserData>
+{
+    pub(crate) unsafe fn from_clay_render_command(
+        value: Clay_RenderCommand,
+        scale_factor: f32,
+    ) -> Self {
+        // Text commands carry the user data of their text config, which the truncation uses
+        let user_data = if value.commandType == Clay_RenderCommandType_CLAY_RENDER_COMMAND_TYPE_TEXT
+        {
+            None
+        } else {
+            unsafe { value.userData.cast::<UserData>().as_ref() }
+        };
         Self {
             id: value.id,
             z_index: value.zIndex,
             bounding_box: value.boundingBox.into(),
             config: unsafe { RenderCommandConfig::from_clay_render_command(&value) },
+            user_data,
+            scale_factor,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inner_radii_shrink_by_the_adjacent_sides() {
+        let border = Border {
+            color: Color::rgb(0., 0., 0.),
+            corner_radii: CornerRadii {
+                top_left: 10.0,
+                top_right: 4.0,
+                bottom_left: 0.0,
+                bottom_right: 10.0,
+            },
+            width: BorderWidth {
+                left: 2,
+                right: 6,
+                top: 1,
+                bottom: 12,
+                between_children: 0,
+            },
+        };
+        assert_eq!(
+            border.inner_corner_radii(),
+            [(8.0, 9.0), (0.0, 3.0), (0.0, 0.0), (4.0, 0.0)]
+        );
+    }
+}
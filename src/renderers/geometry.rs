@@ -0,0 +1,512 @@
+//! Tessellation of render commands into vertex and index buffers for any graphics API.
+//!
+//! [`Geometry`] turns every rectangle, border and image into a quad whose vertices carry the
+//! rounded rectangle it is clipped to, so a fragment shader can compute the signed distance to
+//! its edges for antialiased corners and borders. Text becomes one quad per glyph, rasterized
+//! by a [`GlyphAtlas`]. Consecutive quads sharing a texture and scissor rectangle are batched
+//! into one [`DrawCall`].
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use super::{render, Renderer};
+use crate::{
+    color::Color,
+    math::{BoundingBox, Dimensions, Vector2},
+    render_commands::{Border, CornerRadii, Custom, Image, Rectangle, RenderCommand, Text},
+    text::TextConfig,
+};
+
+/// A vertex of a quad or triangle.
+///
+/// Fragments are covered by `clamp(0.5 - d, 0, 1)`, with `d` the signed distance in pixels to
+/// `rect` with `corner_radii`. With a border, fragments inside `rect` shrunk by `border_widths` are
+/// cut out again. The inner corners are elliptical, their horizontal and vertical radii are the
+/// corner radius minus the width of the vertical and horizontal side meeting there, like
+/// [`Border::inner_corner_radii`]. The color is `color` times the texture sampled at `uv`, times
+/// the coverage.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Vertex {
+    /// Position in pixels.
+    pub position: [f32; 2],
+    /// Depth of the command the vertex belongs to, see [`depth`].
+    pub depth: f32,
+    /// Texture coordinates, from 0 to 1 across the texture.
+    pub uv: [f32; 2],
+    /// Red, green, blue and alpha in sRGB from 0 to 1, not premultiplied.
+    pub color: [f32; 4],
+    /// The rectangle fragments are clipped to: x, y, width and height in pixels.
+    pub rect: [f32; 4],
+    /// Radii of the top left, top right, bottom right and bottom left corner of `rect`.
+    pub corner_radii: [f32; 4],
+    /// Widths of the left, top, right and bottom border, all zero for a filled rectangle.
+    pub border_widths: [f32; 4],
+}
+
+/// Indices drawn with the same texture and scissor rectangle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawCall<Texture> {
+    /// The texture to sample, or `None` for solid colors.
+    pub texture: Option<Texture>,
+    /// The rectangle to clip to in pixels, or `None` to draw everywhere. It isn't clamped to the
+    /// render target.
+    pub scissor: Option<BoundingBox>,
+    /// The range of [`Geometry::indices`] to draw, as a triangle list.
+    pub indices: Range<u32>,
+}
+
+/// A glyph laid out by a [`GlyphAtlas`].
+#[derive(Debug, Clone)]
+pub struct Glyph<Texture> {
+    /// Where the glyph is drawn in pixels, relative to the top left corner of the text.
+    pub bounds: BoundingBox,
+    /// Where the glyph is in `texture`, from 0 to 1 across it.
+    pub uv: BoundingBox,
+    /// The texture holding the glyph as white with its coverage in alpha.
+    pub texture: Texture,
+}
+
+/// Rasterizes glyphs into textures for [`Geometry`] to draw text with.
+pub trait GlyphAtlas {
+    type Texture;
+
+    /// Lays out `text` in pixels at `scale_factor` and returns its glyphs, rasterizing the
+    /// missing ones.
+    fn glyphs(
+        &mut self,
+        text: &Text,
+        scale_factor: f32,
+    ) -> impl Iterator<Item = Glyph<Self::Texture>>;
+
+    /// Returns a function that measures text the way [`GlyphAtlas::glyphs`] lays it out.
+    fn measure_text_function(&self) -> impl Fn(&str, &TextConfig) -> Dimensions + 'static;
+}
+
+/// Depth of the vertices of the command at `index`, from 1 for the first command down to 0, so
+/// later commands are in front when compared with less. Renderers drawing some commands
+/// separately, like text, can use it to keep the order of the commands.
+///
+/// Depths are distinct in a 32 bit float depth buffer for the first 2^24 commands, the commands
+/// after them share the depth of the last one.
+pub fn depth(index: usize) -> f32 {
+    const STEPS: usize = 1 << 24;
+    1.0 - index.min(STEPS - 1) as f32 / STEPS as f32
+}
+
+/// The vertices, indices and draw calls of a frame.
+#[derive(Debug, Clone)]
+pub struct Geometry<Texture> {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    pub draw_calls: Vec<DrawCall<Texture>>,
+    /// Scissor rectangles in pixels, each clipped to the one below it
+    scissors: Vec<BoundingBox>,
+    command: usize,
+}
+
+impl<Texture> Default for Geometry<Texture> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Texture> Geometry<Texture> {
+    pub fn new() -> Self {
+        Self {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            draw_calls: Vec::new(),
+            scissors: Vec::new(),
+            command: 0,
+        }
+    }
+
+    /// Removes everything, keeping the allocations.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+        self.draw_calls.clear();
+        self.scissors.clear();
+        self.command = 0;
+    }
+
+    /// Moves on to the next command, drawn in front of the previous ones, and returns its index.
+    pub fn begin_command(&mut self) -> usize {
+        self.command += 1;
+        self.command
+    }
+
+    /// The index of the current command, see [`Geometry::begin_command`].
+    pub fn command(&self) -> usize {
+        self.command
+    }
+
+    /// Pops the scissor rectangle of [`Geometry::push_scissor`].
+    pub fn pop_scissor(&mut self) {
+        self.scissors.pop();
+    }
+
+    /// Clips the following shapes to `bounds`, intersected with the current scissor rectangle.
+    pub fn push_scissor(&mut self, bounds: BoundingBox, scale_factor: f32) {
+        let bounds = scale_bounding_box(bounds, scale_factor);
+        let (mut x1, mut y1) = (bounds.x, bounds.y);
+        let (mut x2, mut y2) = (bounds.x + bounds.width, bounds.y + bounds.height);
+        if let Some(outer) = self.scissors.last() {
+            (x1, y1) = (x1.max(outer.x), y1.max(outer.y));
+            x2 = x2.min(outer.x + outer.width).max(x1);
+            y2 = y2.min(outer.y + outer.height).max(y1);
+        }
+        self.scissors
+            .push(BoundingBox::new(x1, y1, x2 - x1, y2 - y1));
+    }
+
+    /// The current scissor rectangle in pixels.
+    pub fn scissor(&self) -> Option<BoundingBox> {
+        self.scissors.last().copied()
+    }
+}
+
+impl<Texture: Clone + PartialEq> Geometry<Texture> {
+    /// Replaces the geometry with the render commands of a frame, drawing text with `atlas`.
+    /// Custom elements are drawn by `render_custom_element`, which receives their bounds and
+    /// scale factor and can add shapes with [`Geometry::quad`] and [`Geometry::triangle`].
+    pub fn tessellate<'a, A, CustomElementData: 'a, UserData: 'a>(
+        &mut self,
+        render_commands: impl Iterator<Item = RenderCommand<'a, Texture, CustomElementData, UserData>>,
+        atlas: &mut A,
+        render_custom_element: impl FnMut(&mut Self, BoundingBox, f32, &Custom<CustomElementData>),
+    ) where
+        A: GlyphAtlas<Texture = Texture>,
+        Texture: 'a,
+    {
+        self.clear();
+        render(
+            &mut Tessellator {
+                geometry: self,
+                atlas,
+                render_custom_element,
+            },
+            render_commands,
+        );
+    }
+
+    /// Adds a rectangle command.
+    pub fn rectangle(&mut self, bounds: BoundingBox, scale_factor: f32, rectangle: &Rectangle) {
+        let bounds = scale_bounding_box(bounds, scale_factor);
+        let radii = scale_corner_radii(&rectangle.corner_radii, scale_factor);
+        self.quad(bounds, radii, [0.0; 4], rectangle.color, None, UNIT);
+    }
+
+    /// Adds a border command.
+    pub fn border(&mut self, bounds: BoundingBox, scale_factor: f32, border: &Border) {
+        let width = |width: u16| width as f32 * scale_factor;
+        let widths = [
+            width(border.width.left),
+            width(border.width.top),
+            width(border.width.right),
+            width(border.width.bottom),
+        ];
+        if widths == [0.0; 4] {
+            return;
+        }
+        let bounds = scale_bounding_box(bounds, scale_factor);
+        let radii = scale_corner_radii(&border.corner_radii, scale_factor);
+        self.quad(bounds, radii, widths, border.color, None, UNIT);
+    }
+
+    /// Adds an image command, tinted by its background color unless that is transparent.
+    pub fn image(&mut self, bounds: BoundingBox, scale_factor: f32, image: &Image<'_, Texture>) {
+        let bounds = scale_bounding_box(bounds, scale_factor);
+        let radii = scale_corner_radii(&image.corner_radii, scale_factor);
+        let tint = match image.background_color {
+            Color { a: 0.0, .. } => Color::rgb(255.0, 255.0, 255.0),
+            color => color,
+        };
+        self.quad(bounds, radii, [0.0; 4], tint, Some(image.data), UNIT);
+    }
+
+    /// Adds a text command with the glyphs of `atlas`.
+    pub fn text<A>(&mut self, bounds: BoundingBox, scale_factor: f32, text: &Text, atlas: &mut A)
+    where
+        A: GlyphAtlas<Texture = Texture>,
+    {
+        let (x, y) = (bounds.x * scale_factor, bounds.y * scale_factor);
+        for glyph in atlas.glyphs(text, scale_factor) {
+            let bounds = BoundingBox::new(
+                x + glyph.bounds.x,
+                y + glyph.bounds.y,
+                glyph.bounds.width,
+                glyph.bounds.height,
+            );
+            self.quad(
+                bounds,
+                [0.0; 4],
+                [0.0; 4],
+                text.color,
+                Some(&glyph.texture),
+                glyph.uv,
+            );
+        }
+    }
+
+    /// Adds a quad covering `bounds` in pixels, clipped to its `corner_radii` and cut out inside
+    /// of `border_widths`, see [`Vertex`]. `uv` is the part of `texture` it shows.
+    pub fn quad(
+        &mut self,
+        bounds: BoundingBox,
+        corner_radii: [f32; 4],
+        border_widths: [f32; 4],
+        color: Color,
+        texture: Option<&Texture>,
+        uv: BoundingBox,
+    ) {
+        if bounds.width <= 0.0 || bounds.height <= 0.0 {
+            return;
+        }
+        let fit = bounds.width.min(bounds.height) / 2.0;
+        let vertex = |x: f32, y: f32, u: f32, v: f32| Vertex {
+            position: [x, y],
+            depth: depth(self.command),
+            uv: [u, v],
+            color: normalize(color),
+            rect: [bounds.x, bounds.y, bounds.width, bounds.height],
+            corner_radii: corner_radii.map(|radius| radius.clamp(0.0, fit)),
+            border_widths,
+        };
+        let (x2, y2) = (bounds.x + bounds.width, bounds.y + bounds.height);
+        let (u2, v2) = (uv.x + uv.width, uv.y + uv.height);
+        let vertices = [
+            vertex(bounds.x, bounds.y, uv.x, uv.y),
+            vertex(x2, bounds.y, u2, uv.y),
+            vertex(x2, y2, u2, v2),
+            vertex(bounds.x, y2, uv.x, v2),
+        ];
+        self.push(&vertices, &[0, 1, 2, 0, 2, 3], texture);
+    }
+
+    /// Adds a solid triangle between `points` in pixels.
+    pub fn triangle(&mut self, points: [Vector2; 3], color: Color) {
+        let (x1, y1) = points
+            .iter()
+            .fold((f32::MAX, f32::MAX), |(x, y), p| (x.min(p.x), y.min(p.y)));
+        let (x2, y2) = points
+            .iter()
+            .fold((f32::MIN, f32::MIN), |(x, y), p| (x.max(p.x), y.max(p.y)));
+        let vertices = points.map(|point| Vertex {
+            position: [point.x, point.y],
+            depth: depth(self.command),
+            uv: [0.0; 2],
+            color: normalize(color),
+            rect: [x1, y1, x2 - x1, y2 - y1],
+            corner_radii: [0.0; 4],
+            border_widths: [0.0; 4],
+        });
+        self.push(&vertices, &[0, 1, 2], None);
+    }
+
+    /// Appends vertices with indices relative to the first one, batched with the previous draw
+    /// call if it has the same texture and scissor rectangle.
+    fn push(&mut self, vertices: &[Vertex], indices: &[u32], texture: Option<&Texture>) {
+        let base = self.vertices.len() as u32;
+        let start = self.indices.len() as u32;
+        self.vertices.extend_from_slice(vertices);
+        self.indices
+            .extend(indices.iter().map(|index| base + index));
+        let end = self.indices.len() as u32;
+
+        let scissor = self.scissor();
+        match self.draw_calls.last_mut() {
+            Some(draw_call)
+                if draw_call.scissor == scissor && draw_call.texture.as_ref() == texture =>
+            {
+                draw_call.indices.end = end;
+            }
+            _ => self.draw_calls.push(DrawCall {
+                texture: texture.cloned(),
+                scissor,
+                indices: start..end,
+            }),
+        }
+    }
+}
+
+/// The whole texture.
+const UNIT: BoundingBox = BoundingBox {
+    x: 0.0,
+    y: 0.0,
+    width: 1.0,
+    height: 1.0,
+};
+
+/// Converts a color from 0-255 to 0-1.
+fn normalize(color: Color) -> [f32; 4] {
+    [color.r, color.g, color.b, color.a].map(|channel| channel / 255.0)
+}
+
+/// Converts layout units to pixels.
+fn scale_bounding_box(bb: BoundingBox, scale: f32) -> BoundingBox {
+    BoundingBox::new(
+        bb.x * scale,
+        bb.y * scale,
+        bb.width * scale,
+        bb.height * scale,
+    )
+}
+
+/// Returns the top left, top right, bottom right and bottom left radius in pixels.
+fn scale_corner_radii(radii: &CornerRadii, scale: f32) -> [f32; 4] {
+    [
+        radii.top_left,
+        radii.top_right,
+        radii.bottom_right,
+        radii.bottom_left,
+    ]
+    .map(|radius| radius * scale)
+}
+
+/// Geometry while it tessellates a frame, with the atlas and the hook for custom elements.
+struct Tessellator<'g, Texture, A, F> {
+    geometry: &'g mut Geometry<Texture>,
+    atlas: &'g mut A,
+    render_custom_element: F,
+}
+
+impl<Texture, A, F, CustomElementData> Renderer<CustomElementData>
+    for Tessellator<'_, Texture, A, F>
+where
+    Texture: Clone + PartialEq,
+    A: GlyphAtlas<Texture = Texture>,
+    F: FnMut(&mut Geometry<Texture>, BoundingBox, f32, &Custom<CustomElementData>),
+{
+    type ImageElementData = Texture;
+
+    fn rectangle(&mut self, bounds: BoundingBox, scale_factor: f32, rectangle: &Rectangle) {
+        self.geometry.begin_command();
+        self.geometry.rectangle(bounds, scale_factor, rectangle);
+    }
+
+    fn border(&mut self, bounds: BoundingBox, scale_factor: f32, border: &Border) {
+        self.geometry.begin_command();
+        self.geometry.border(bounds, scale_factor, border);
+    }
+
+    fn text(&mut self, bounds: BoundingBox, scale_factor: f32, text: &Text) {
+        self.geometry.begin_command();
+        self.geometry.text(bounds, scale_factor, text, self.atlas);
+    }
+
+    fn image(&mut self, bounds: BoundingBox, scale_factor: f32, image: &Image<'_, Texture>) {
+        self.geometry.begin_command();
+        self.geometry.image(bounds, scale_factor, image);
+    }
+
+    fn push_scissor(&mut self, bounds: BoundingBox, scale_factor: f32) {
+        self.geometry.push_scissor(bounds, scale_factor);
+    }
+
+    fn pop_scissor(&mut self) {
+        self.geometry.pop_scissor();
+    }
+
+    fn custom(
+        &mut self,
+        bounds: BoundingBox,
+        scale_factor: f32,
+        custom: &Custom<'_, CustomElementData>,
+    ) {
+        self.geometry.begin_command();
+        (self.render_custom_element)(self.geometry, bounds, scale_factor, custom);
+    }
+
+    fn measure_text_function(&self) -> impl Fn(&str, &TextConfig) -> Dimensions + 'static {
+        self.atlas.measure_text_function()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lays out every character as a 10 pixel square in texture 7.
+    struct MonospaceAtlas;
+
+    impl GlyphAtlas for MonospaceAtlas {
+        type Texture = u32;
+
+        fn glyphs(&mut self, text: &Text, scale_factor: f32) -> impl Iterator<Item = Glyph<u32>> {
+            let size = 10.0 * scale_factor;
+            (0..text.text.len()).map(move |i| Glyph {
+                bounds: BoundingBox::new(i as f32 * size, 0.0, size, size),
+                uv: UNIT,
+                texture: 7,
+            })
+        }
+
+        fn measure_text_function(&self) -> impl Fn(&str, &TextConfig) -> Dimensions + 'static {
+            |text, _| Dimensions::new(text.len() as f32 * 10.0, 10.0)
+        }
+    }
+
+    #[test]
+    fn batches_quads_by_texture_and_scissor() {
+        let mut geometry = Geometry::<u32>::new();
+        let rectangle = Rectangle {
+            color: Color::rgb(255.0, 0.0, 0.0),
+            corner_radii: CornerRadii {
+                top_left: 50.0,
+                ..Default::default()
+            },
+        };
+        let bounds = BoundingBox::new(10.0, 10.0, 20.0, 40.0);
+        geometry.rectangle(bounds, 2.0, &rectangle);
+        geometry.rectangle(bounds, 2.0, &rectangle);
+        geometry.push_scissor(BoundingBox::new(0.0, 0.0, 15.0, 100.0), 2.0);
+        geometry.push_scissor(BoundingBox::new(5.0, 5.0, 100.0, 100.0), 2.0);
+        let text = Text {
+            text: "hey",
+            color: Color::rgb(255.0, 255.0, 255.0),
+            font_id: 0,
+            font_size: 10,
+            letter_spacing: 0,
+            line_height: 0,
+        };
+        geometry.text(bounds, 2.0, &text, &mut MonospaceAtlas);
+        geometry.pop_scissor();
+        geometry.pop_scissor();
+
+        // Both rectangles, then the glyphs clipped to both scissor rectangles
+        assert_eq!(geometry.vertices.len(), 4 * 5);
+        assert_eq!(
+            geometry.draw_calls,
+            [
+                DrawCall {
+                    texture: None,
+                    scissor: None,
+                    indices: 0..12,
+                },
+                DrawCall {
+                    texture: Some(7),
+                    scissor: Some(BoundingBox::new(10.0, 10.0, 20.0, 190.0)),
+                    indices: 12..30,
+                },
+            ]
+        );
+
+        // The radius is limited to half of the shorter side, 40 pixels wide
+        let vertex = geometry.vertices[2];
+        assert_eq!(vertex.position, [60.0, 100.0]);
+        assert_eq!(vertex.rect, [20.0, 20.0, 40.0, 80.0]);
+        assert_eq!(vertex.corner_radii, [20.0, 0.0, 0.0, 0.0]);
+        assert_eq!(vertex.color, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(geometry.vertices[8].position, [20.0, 20.0]);
+    }
+
+    #[test]
+    fn depths_of_later_commands_are_in_front() {
+        assert_eq!(depth(0), 1.0);
+        assert!(depth(70_000) < depth(65_535));
+        assert!(depth(1 << 24) > 0.0);
+        assert_eq!(depth(usize::MAX), depth((1 << 24) - 1));
+    }
+}
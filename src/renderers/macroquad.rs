@@ -1,12 +1,22 @@
 use macroquad::prelude::*;
-use crate::{math::BoundingBox, render_commands::{CornerRadii, RenderCommand, RenderCommandConfig}};
+use crate::{
+    math::{BoundingBox, Dimensions},
+    render_commands::{
+        Border, CornerRadii, Custom, Image as ImageCommand, Rectangle, RenderCommand, RenderCommandConfig, Text,
+    },
+    renderers::{render_command, Renderer},
+    text::TextConfig,
+};
 
+#[cfg(feature = "macroquad-texture-manager")]
+use crate::renderers::macroquad_assets::{AssetError, AssetSource, FileSource, ImageSource};
 #[cfg(feature = "macroquad-text-styling")]
 use crate::renderers::macroquad_text_styling::{parse_text_lines, render_styled_text, StyledSegment};
 #[cfg(feature = "macroquad-text-styling")]
 use std::collections::HashMap;
 
-const PIXELS_PER_POINT: f32 = 2.0;
+/// Length in pixels of the segments curves are approximated with
+const PIXELS_PER_SEGMENT: f32 = 2.0;
 
 #[cfg(feature = "macroquad-text-styling")]
 static ANIMATION_TRACKER: std::sync::LazyLock<std::sync::Mutex<HashMap<String, (usize, f64)>>> = std::sync::LazyLock::new(|| std::sync::Mutex::new(HashMap::new()));
@@ -18,10 +28,22 @@ pub static TEXTURE_MANAGER: std::sync::LazyLock<std::sync::Mutex<TextureManager>
 /// Manages textures, loading and unloading them as needed. No manual management needed.
 /// 
 /// You can adjust `max_frames_not_used` to control how many frames a texture can go unused before being unloaded.
+///
+/// Image paths are loaded from an [`AssetSource`], files by default. Images that fail to load are drawn as the
+/// placeholder texture and their errors are kept until [`TextureManager::take_errors`].
+///
+/// Sources that load right away, like files on desktop or a [`MemorySource`](crate::renderers::macroquad_assets::MemorySource),
+/// are also loaded by [`TextureManager::get_image`] without awaiting. Paths that are still loading then, like files
+/// over HTTP on the web, are drawn once they were loaded with [`TextureManager::get_or_load`].
 #[cfg(feature = "macroquad-texture-manager")]
 pub struct TextureManager {
     textures: std::collections::HashMap<String, TextureData>,
     pub max_frames_not_used: usize,
+    source: Box<dyn AssetSource>,
+    placeholder: Option<Texture2D>,
+    errors: Vec<AssetError>,
+    /// Paths the asset source didn't load right away, left to [`TextureManager::get_or_load`]
+    deferred: std::collections::HashSet<String>,
 }
 #[cfg(feature = "macroquad-texture-manager")]
 struct TextureData {
@@ -29,14 +51,39 @@ struct TextureData {
     pub texture: Texture2D,
 }
 #[cfg(feature = "macroquad-texture-manager")]
+impl Default for TextureManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+#[cfg(feature = "macroquad-texture-manager")]
 impl TextureManager {
     pub fn new() -> Self {
         Self {
             textures: std::collections::HashMap::new(),
             max_frames_not_used: 1,
+            source: Box::new(FileSource),
+            placeholder: None,
+            errors: Vec::new(),
+            deferred: std::collections::HashSet::new(),
         }
     }
 
+    /// Loads the image paths from `source` from now on. Textures that were already loaded are kept.
+    pub fn set_source(&mut self, source: impl AssetSource + 'static) {
+        self.source = Box::new(source);
+    }
+
+    /// Draws `texture` for images that failed to load, instead of nothing.
+    pub fn set_placeholder(&mut self, texture: Texture2D) {
+        self.placeholder = Some(texture);
+    }
+
+    /// Returns the errors of the loads that failed since the last call.
+    pub fn take_errors(&mut self) -> Vec<AssetError> {
+        std::mem::take(&mut self.errors)
+    }
+
     /// Get a cached texture by its key.
     pub fn get(&mut self, path: &str) -> Option<&Texture2D> {
         if let Some(data) = self.textures.get_mut(path) {
@@ -47,11 +94,18 @@ impl TextureManager {
         }
     }
 
-    /// Get the cached texture by its key, or load from a file path and cache it.
-    pub async fn get_or_load(&mut self, path: &'static str) -> &Texture2D {
+    /// Get the cached texture by its key, or load it from the asset source and cache it.
+    /// If loading fails, the placeholder is cached instead.
+    pub async fn get_or_load(&mut self, path: &str) -> &Texture2D {
         if !self.textures.contains_key(path) {
-            let texture = load_texture(path).await.unwrap();
-            self.textures.insert(path.to_owned(), TextureData { frames_not_used: 0, texture });
+            self.deferred.remove(path);
+            let texture = self.source.load(path).await.and_then(|bytes| decode_texture(path, &bytes));
+            match texture {
+                Ok(texture) => {
+                    self.cache(path.to_owned(), texture);
+                }
+                Err(error) => self.report(path.to_owned(), error),
+            }
         }
 
         let entry = self.textures.get_mut(path).unwrap();
@@ -59,6 +113,89 @@ impl TextureManager {
         &entry.texture
     }
 
+    /// Get the texture of `image`, decoding and caching bytes and paths the asset source loads right away.
+    /// Returns `None` for paths that are still loading.
+    pub fn get_image(&mut self, image: &ImageSource) -> Option<Texture2D> {
+        match image {
+            ImageSource::Path(path) => {
+                if !self.textures.contains_key(path.as_ref()) {
+                    match self.load_now(path)?.and_then(|bytes| decode_texture(path, &bytes)) {
+                        Ok(texture) => {
+                            self.cache(path.to_string(), texture);
+                        }
+                        Err(error) => self.report(path.to_string(), error),
+                    }
+                }
+                self.get(path).cloned()
+            }
+            ImageSource::Bytes { key, bytes } => {
+                if !self.textures.contains_key(key.as_ref()) {
+                    match decode_texture(key, bytes) {
+                        Ok(texture) => {
+                            self.cache(key.to_string(), texture);
+                        }
+                        Err(error) => self.report(key.to_string(), error),
+                    }
+                }
+                self.get(key).cloned()
+            }
+            ImageSource::Texture(texture) => Some(texture.clone()),
+        }
+    }
+
+    /// Get the texture of `image`, loading paths from the asset source.
+    pub async fn get_or_load_image(&mut self, image: &ImageSource) -> Texture2D {
+        match image {
+            ImageSource::Path(path) => self.get_or_load(path).await.clone(),
+            _ => self.get_image(image).unwrap(),
+        }
+    }
+
+    /// Get the texture of the TinyVG image at `path` rasterized under `key`, loading it like [`TextureManager::get_image`].
+    #[cfg(feature = "macroquad-tinyvg")]
+    fn get_tinyvg(&mut self, path: &str, key: String, bounds: BoundingBox, state: &RenderState) -> Option<Texture2D> {
+        if !self.textures.contains_key(&key) {
+            let bytes = self.load_now(path)?;
+            self.cache_tinyvg(key.clone(), path, bytes, bounds, state);
+        }
+        self.get(&key).cloned()
+    }
+
+    /// Rasterizes the TinyVG image `bytes` loaded from `path` to the size of `bounds` and caches it under `key`.
+    #[cfg(feature = "macroquad-tinyvg")]
+    fn cache_tinyvg(&mut self, key: String, path: &str, bytes: Result<Vec<u8>, AssetError>, bounds: BoundingBox, state: &RenderState) {
+        let texture = bytes.and_then(|tvg_bytes| {
+            render_tinyvg_texture(&tvg_bytes, bounds.width, bounds.height, state).ok_or_else(|| AssetError::Decode {
+                path: path.to_owned(),
+                message: "invalid TinyVG image".to_owned(),
+            })
+        });
+        match texture {
+            Ok(texture) => {
+                self.cache(key, texture);
+            }
+            Err(error) => self.report(key, error),
+        }
+    }
+
+    /// Loads `path` from the asset source if it is ready right away. Otherwise returns `None` and defers the path,
+    /// so it isn't requested again every frame.
+    fn load_now(&mut self, path: &str) -> Option<Result<Vec<u8>, AssetError>> {
+        if self.deferred.contains(path) {
+            return None;
+        }
+        let mut future = self.source.load(path);
+        let poll = future.as_mut().poll(&mut std::task::Context::from_waker(std::task::Waker::noop()));
+        drop(future);
+        match poll {
+            std::task::Poll::Ready(bytes) => Some(bytes),
+            std::task::Poll::Pending => {
+                self.deferred.insert(path.to_owned());
+                None
+            }
+        }
+    }
+
     /// Get the cached texture by its key, or create it using the provided function and cache it.
     pub fn get_or_create<F>(&mut self, key: String, create_fn: F) -> &Texture2D 
     where F: FnOnce() -> Texture2D 
@@ -74,10 +211,18 @@ impl TextureManager {
 
     /// Cache a texture with the given key.
     pub fn cache(&mut self, key: String, texture: Texture2D) -> &Texture2D {
-        self.textures.insert(key.clone(), TextureData { frames_not_used: 0, texture: texture });
+        self.textures.insert(key.clone(), TextureData { frames_not_used: 0, texture });
         &self.textures.get(&key).unwrap().texture
     }
 
+    /// Keeps `error` and caches the placeholder under `key`, so the load isn't retried while the image is drawn.
+    fn report(&mut self, key: String, error: AssetError) {
+        warn!("{}", error);
+        self.errors.push(error);
+        let placeholder = self.placeholder.get_or_insert_with(|| Texture2D::from_rgba8(1, 1, &[0, 0, 0, 0])).clone();
+        self.cache(key, placeholder);
+    }
+
     pub fn clean(&mut self) {
         self.textures.retain(|_, data| data.frames_not_used <= self.max_frames_not_used);
 
@@ -91,6 +236,135 @@ impl TextureManager {
     }
 }
 
+/// Decodes the image `bytes` loaded from `path`, in any format macroquad supports.
+#[cfg(feature = "macroquad-texture-manager")]
+fn decode_texture(path: &str, bytes: &[u8]) -> Result<Texture2D, AssetError> {
+    Image::from_file_with_format(bytes, None)
+        .map(|image| Texture2D::from_image(&image))
+        .map_err(|error| AssetError::Decode { path: path.to_owned(), message: error.to_string() })
+}
+
+/// Global TextCache, shared by the renderer and the functions of [`create_measure_text_function`].
+pub static TEXT_CACHE: std::sync::LazyLock<std::sync::Mutex<TextCache>> = std::sync::LazyLock::new(|| std::sync::Mutex::new(TextCache::new()));
+
+/// Identifies the fonts a text was measured with: the font table, font id, font size, font scale and whether style
+/// markup was stripped.
+type MeasurementKey = (usize, u16, u16, u32, bool);
+
+/// Identifies a table of fonts indexed by font id, by its address.
+fn font_table(fonts: &[Font]) -> usize {
+    fonts.as_ptr() as usize
+}
+
+/// Caches text measurements across frames and fills the glyph atlases of fonts ahead of drawing.
+///
+/// Measurements and atlases are kept per table of fonts, so renderers and measure functions with different fonts
+/// don't share them. You can adjust `max_frames_not_used` to control how many frames a measurement can go unused
+/// before being dropped, and `max_texts` to control how many texts are kept when measuring without drawing.
+pub struct TextCache {
+    texts: std::collections::HashMap<String, CachedText>,
+    /// Font tables, ids and sizes whose glyphs were rasterized
+    rasterized: std::collections::HashSet<(usize, u16, u16)>,
+    /// Counts the measurements, to tell which texts were used last
+    uses: u64,
+    pub max_frames_not_used: usize,
+    pub max_texts: usize,
+}
+struct CachedText {
+    frames_not_used: usize,
+    last_used: u64,
+    measurements: Vec<(MeasurementKey, TextDimensions)>,
+}
+impl Default for TextCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl TextCache {
+    pub fn new() -> Self {
+        Self {
+            texts: std::collections::HashMap::new(),
+            rasterized: std::collections::HashSet::new(),
+            uses: 0,
+            max_frames_not_used: 60,
+            max_texts: 4096,
+        }
+    }
+
+    /// Measures `text` with the font `font_id` of `fonts` like [`measure_text`], reusing the measurement of earlier
+    /// frames.
+    pub fn measure(&mut self, text: &str, fonts: &[Font], font_id: u16, font_size: u16, font_scale: f32) -> TextDimensions {
+        let key = (font_table(fonts), font_id, font_size, font_scale.to_bits(), false);
+        self.get_or_measure(text, key, || {
+            measure_text(text, Some(&fonts[font_id as usize]), font_size, font_scale)
+        })
+    }
+
+    fn get_or_measure<F>(&mut self, text: &str, key: MeasurementKey, measure: F) -> TextDimensions
+    where F: FnOnce() -> TextDimensions
+    {
+        if !self.texts.contains_key(text) {
+            if self.texts.len() >= self.max_texts {
+                self.trim();
+            }
+            self.texts.insert(text.to_owned(), CachedText { frames_not_used: 0, last_used: 0, measurements: Vec::new() });
+        }
+        self.uses += 1;
+        let cached = self.texts.get_mut(text).unwrap();
+        cached.frames_not_used = 0;
+        cached.last_used = self.uses;
+        if let Some((_, dimensions)) = cached.measurements.iter().find(|(k, _)| *k == key) {
+            return *dimensions;
+        }
+        let dimensions = measure();
+        cached.measurements.push((key, dimensions));
+        dimensions
+    }
+
+    /// Drops the least recently used half of the texts. Measure functions that are used without drawing, where
+    /// [`TextCache::clean`] isn't called, would grow the cache without bound otherwise.
+    fn trim(&mut self) {
+        let mut last_used: Vec<u64> = self.texts.values().map(|cached| cached.last_used).collect();
+        if last_used.is_empty() {
+            return;
+        }
+        let middle = last_used.len() / 2;
+        let newest = *last_used.select_nth_unstable(middle).1;
+        self.texts.retain(|_, cached| cached.last_used >= newest);
+    }
+
+    /// Drops the measurements and atlas bookkeeping of `fonts`, once they are freed.
+    fn forget(&mut self, fonts: &[Font]) {
+        let table = font_table(fonts);
+        for cached in self.texts.values_mut() {
+            cached.measurements.retain(|(key, _)| key.0 != table);
+        }
+        self.texts.retain(|_, cached| !cached.measurements.is_empty());
+        self.rasterized.retain(|&(rasterized_table, _, _)| rasterized_table != table);
+    }
+
+    /// Rasterizes the printable ASCII characters of the font `font_id` of `fonts` at `size` into its atlas, once
+    /// per font and size, instead of one glyph at a time while drawing.
+    pub fn rasterize(&mut self, fonts: &[Font], font_id: u16, size: u16) {
+        if self.rasterized.insert((font_table(fonts), font_id, size)) {
+            let characters: Vec<char> = (' '..='~').collect();
+            fonts[font_id as usize].populate_font_cache(&characters, size);
+        }
+    }
+
+    pub fn clean(&mut self) {
+        self.texts.retain(|_, cached| cached.frames_not_used <= self.max_frames_not_used);
+
+        for cached in self.texts.values_mut() {
+            cached.frames_not_used += 1;
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.texts.len()
+    }
+}
+
 fn clay_to_macroquad_color(clay_color: &crate::color::Color) -> Color {
     Color {
         r: clay_color.r / 255.0,
@@ -100,13 +374,23 @@ fn clay_to_macroquad_color(clay_color: &crate::color::Color) -> Color {
     }
 }
 
-fn draw_good_circle(x: f32, y: f32, r: f32, color: Color) {
-    let sides = ((2.0 * std::f32::consts::PI * r) / PIXELS_PER_POINT).max(20.0);
+fn draw_good_circle(x: f32, y: f32, r: f32, scale: f32, color: Color) {
+    let sides = ((2.0 * std::f32::consts::PI * r * scale) / PIXELS_PER_SEGMENT).max(20.0);
     draw_poly(x, y, sides.min(255.0) as u8, r, 0.0, color);
 }
 
+/// The font size in pixels glyphs are rasterized at, and the font scale that draws them
+/// `font_size` layout units large
+fn pixel_font_size(font_size: u16, scale: f32) -> (u16, f32) {
+    let pixels = (font_size as f32 * scale).round().max(1.0);
+    (pixels as u16, font_size as f32 / pixels)
+}
+
 struct RenderState {
-    clip: Option<(i32, i32, i32, i32)>,
+    /// Scissor rects in pixels of the framebuffer, each inside the ones before it
+    clips: Vec<(i32, i32, i32, i32)>,
+    /// Pixels per layout unit of the commands drawn
+    scale: f32,
     #[cfg(feature = "macroquad-text-styling")]
     style_stack: Vec<String>,
     #[cfg(feature = "macroquad-text-styling")]
@@ -116,110 +400,203 @@ struct RenderState {
 impl RenderState {
     fn new() -> Self {
         Self {
-            clip: None,
+            clips: Vec::new(),
+            scale: 1.0,
             #[cfg(feature = "macroquad-text-styling")]
             style_stack: Vec::new(),
             #[cfg(feature = "macroquad-text-styling")]
             total_char_index: 0,
         }
     }
-}
-
-fn rounded_rectangle_texture(cr: &CornerRadii, bb: &BoundingBox, clip: &Option<(i32, i32, i32, i32)>) -> Texture2D {
-    let render_target = render_target(bb.width as u32, bb.height as u32);
-    render_target.texture.set_filter(FilterMode::Linear);
-    let mut cam = Camera2D::from_display_rect(Rect::new(0.0, 0.0, bb.width, bb.height));
-    cam.render_target = Some(render_target.clone());
-    set_camera(&cam);
-    unsafe {
-        get_internal_gl().quad_gl.scissor(None);
-    };
 
-    // Edges
-    // Top edge
-    if cr.top_left > 0.0 || cr.top_right > 0.0 {
-        draw_rectangle(
-            cr.top_left,
-            0.0,
-            bb.width - cr.top_left - cr.top_right,
-            bb.height - cr.bottom_left.max(cr.bottom_right),
-            WHITE
-        );
+    fn clip(&self) -> Option<(i32, i32, i32, i32)> {
+        self.clips.last().copied()
     }
-    // Left edge
-    if cr.top_left > 0.0 || cr.bottom_left > 0.0 {
-        draw_rectangle(
-            0.0,
-            cr.top_left,
-            bb.width - cr.top_right.max(cr.bottom_right),
-            bb.height - cr.top_left - cr.bottom_left,
-            WHITE
-        );
-    }
-    // Bottom edge
-    if cr.bottom_left > 0.0 || cr.bottom_right > 0.0 {
-        draw_rectangle(
-            cr.bottom_left,
-            cr.top_left.max(cr.top_right),
-            bb.width - cr.bottom_left - cr.bottom_right,
-            bb.height - cr.top_left.max(cr.top_right),
-            WHITE
-        );
+
+    /// Switches to the scale of the next command, keeping the camera in layout units
+    fn set_scale(&mut self, scale: f32) {
+        if scale != self.scale {
+            self.scale = scale;
+            self.restore_camera();
+        }
     }
-    // Right edge
-    if cr.top_right > 0.0 || cr.bottom_right > 0.0 {
-        draw_rectangle(
-            bb.width - cr.top_right,
-            cr.top_right,
-            bb.width - cr.top_left.max(cr.bottom_left),
-            bb.height - cr.top_right - cr.bottom_right,
-            WHITE
-        );
+
+    /// Sets the camera and clip of the screen again, after drawing to a render target
+    fn restore_camera(&self) {
+        if self.scale == 1.0 {
+            set_default_camera();
+        } else {
+            set_camera(&Camera2D::from_display_rect(Rect::new(
+                0.0,
+                0.0,
+                screen_width() / self.scale,
+                screen_height() / self.scale,
+            )));
+        }
+        unsafe {
+            get_internal_gl().quad_gl.scissor(self.clip());
+        }
     }
 
-    // Corners
-    // Top-left corner
-    if cr.top_left > 0.0 {
-        draw_good_circle(
-            cr.top_left,
-            cr.top_left,
-            cr.top_left,
-            WHITE,
+    fn push_scissor(&mut self, bb: BoundingBox) {
+        // The scissor rect is in pixels of the framebuffer, not of the camera
+        let scale = self.scale * screen_dpi_scale();
+        let mut clip = (
+            (bb.x * scale) as i32,
+            (bb.y * scale) as i32,
+            (bb.width * scale) as i32,
+            (bb.height * scale) as i32,
         );
+        if let Some(parent) = self.clip() {
+            clip = intersect_clips(parent, clip);
+        }
+        self.clips.push(clip);
+        unsafe {
+            get_internal_gl().quad_gl.scissor(self.clip());
+        }
     }
-    // Top-right corner
-    if cr.top_right > 0.0 {
-        draw_good_circle(
-            bb.width - cr.top_right,
-            cr.top_right,
-            cr.top_right,
-            WHITE,
-        );
+
+    fn pop_scissor(&mut self) {
+        self.clips.pop();
+        unsafe {
+            get_internal_gl().quad_gl.scissor(self.clip());
+        }
     }
-    // Bottom-left corner
-    if cr.bottom_left > 0.0 {
-        draw_good_circle(
-            cr.bottom_left,
-            bb.height - cr.bottom_left,
-            cr.bottom_left,
-            WHITE,
-        );
+}
+
+/// The part of the scissor rect `clip` inside `parent`, empty if they don't overlap
+fn intersect_clips(parent: (i32, i32, i32, i32), clip: (i32, i32, i32, i32)) -> (i32, i32, i32, i32) {
+    let x = parent.0.max(clip.0);
+    let y = parent.1.max(clip.1);
+    let right = (parent.0 + parent.2).min(clip.0 + clip.2);
+    let bottom = (parent.1 + parent.3).min(clip.1 + clip.3);
+    (x, y, (right - x).max(0), (bottom - y).max(0))
+}
+
+const ROUNDED_CLIP_VERTEX: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+varying mediump vec2 point;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+    point = position.xy;
+}"#;
+
+const ROUNDED_CLIP_FRAGMENT: &str = r#"#version 100
+precision mediump float;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+varying mediump vec2 point;
+
+uniform sampler2D Texture;
+// x, y, width and height in layout units
+uniform vec4 Rect;
+// Top left, top right, bottom right and bottom left
+uniform vec4 Radii;
+// Framebuffer pixels per layout unit, to antialias the edge over one pixel
+uniform float Pixels;
+
+void main() {
+    vec2 half_size = Rect.zw / 2.0;
+    vec2 p = point - Rect.xy - half_size;
+    float top = p.x > 0.0 ? Radii.y : Radii.x;
+    float bottom = p.x > 0.0 ? Radii.z : Radii.w;
+    float radius = p.y > 0.0 ? bottom : top;
+    vec2 q = abs(p) - half_size + radius;
+    float dist = min(max(q.x, q.y), 0.0) + length(max(q, 0.0)) - radius;
+    float coverage = clamp(0.5 - dist * Pixels, 0.0, 1.0);
+
+    vec4 texel = color * texture2D(Texture, uv);
+    gl_FragColor = vec4(texel.rgb, texel.a * coverage);
+}"#;
+
+/// Material cutting what is drawn with it to a rounded rectangle, see [`with_rounded_clip`]
+static ROUNDED_CLIP: std::sync::LazyLock<Material> = std::sync::LazyLock::new(|| {
+    use macroquad::miniquad::{BlendFactor, BlendState, BlendValue, Equation};
+
+    load_material(
+        ShaderSource::Glsl {
+            vertex: ROUNDED_CLIP_VERTEX,
+            fragment: ROUNDED_CLIP_FRAGMENT,
+        },
+        MaterialParams {
+            pipeline_params: PipelineParams {
+                color_blend: Some(BlendState::new(
+                    Equation::Add,
+                    BlendFactor::Value(BlendValue::SourceAlpha),
+                    BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+                )),
+                ..Default::default()
+            },
+            uniforms: vec![
+                UniformDesc::new("Rect", UniformType::Float4),
+                UniformDesc::new("Radii", UniformType::Float4),
+                UniformDesc::new("Pixels", UniformType::Float1),
+            ],
+            ..Default::default()
+        },
+    )
+    .unwrap()
+});
+
+/// Runs `draw`, cutting what it draws to `bb` with the rounded corners `cr` on the GPU
+fn with_rounded_clip(bb: BoundingBox, cr: &CornerRadii, state: &RenderState, draw: impl FnOnce()) {
+    if cr.top_left == 0.0 && cr.top_right == 0.0 && cr.bottom_left == 0.0 && cr.bottom_right == 0.0 {
+        draw();
+        return;
     }
-    // Bottom-right corner
-    if cr.bottom_right > 0.0 {
-        draw_good_circle(
-            bb.width - cr.bottom_right,
-            bb.height - cr.bottom_right,
-            cr.bottom_right,
-            WHITE,
+
+    let max_radius = bb.width.min(bb.height) / 2.0;
+    ROUNDED_CLIP.set_uniform("Rect", vec4(bb.x, bb.y, bb.width, bb.height));
+    ROUNDED_CLIP.set_uniform(
+        "Radii",
+        vec4(
+            cr.top_left.min(max_radius),
+            cr.top_right.min(max_radius),
+            cr.bottom_right.min(max_radius),
+            cr.bottom_left.min(max_radius),
+        ),
+    );
+    ROUNDED_CLIP.set_uniform("Pixels", state.scale * screen_dpi_scale());
+    gl_use_material(&ROUNDED_CLIP);
+    draw();
+    gl_use_default_material();
+}
+
+/// Draws `texture` stretched over `bb`, with the rounded corners `cr`
+fn draw_texture_rounded(texture: &Texture2D, bb: BoundingBox, cr: &CornerRadii, tint: Color, flip_y: bool, state: &RenderState) {
+    with_rounded_clip(bb, cr, state, || {
+        draw_texture_ex(
+            texture,
+            bb.x,
+            bb.y,
+            tint,
+            DrawTextureParams {
+                dest_size: Some(Vec2::new(bb.width, bb.height)),
+                flip_y,
+                ..Default::default()
+            },
         );
-    }
+    });
+}
 
-    set_default_camera();
-    unsafe {
-        get_internal_gl().quad_gl.scissor(*clip);
-    }
-    render_target.texture
+/// The key a TinyVG image at `path` drawn in `bounds` is cached under, or `None` if `path` isn't a TinyVG image.
+#[cfg(feature = "macroquad-tinyvg")]
+fn tinyvg_key(path: &str, bounds: BoundingBox, state: &RenderState) -> Option<String> {
+    path.to_lowercase()
+        .ends_with(".tvg")
+        .then(|| format!("tvg:{}:{}:{}:{}", path, bounds.width, bounds.height, state.scale))
 }
 
 /// Render a TinyVG image to a Texture2D, scaled to fit the given dimensions.
@@ -228,7 +605,7 @@ fn render_tinyvg_texture(
     tvg_data: &[u8],
     dest_width: f32,
     dest_height: f32,
-    clip: &Option<(i32, i32, i32, i32)>,
+    state: &RenderState,
 ) -> Option<Texture2D> {
     use tinyvg::{Decoder, format::{Command, Style, Segment, SegmentCommandKind, Point as TvgPoint, Color as TvgColor}};
     use kurbo::{BezPath, Point as KurboPoint, Vec2 as KurboVec2, ParamCurve, SvgArc, Arc as KurboArc, PathEl};
@@ -251,7 +628,7 @@ fn render_tinyvg_texture(
     let scale_x = dest_width / tvg_width;
     let scale_y = dest_height / tvg_height;
     
-    let render_target = render_target(dest_width as u32, dest_height as u32);
+    let render_target = render_target((dest_width * state.scale) as u32, (dest_height * state.scale) as u32);
     render_target.texture.set_filter(FilterMode::Linear);
     let mut cam = Camera2D::from_display_rect(Rect::new(0.0, 0.0, dest_width, dest_height));
     cam.render_target = Some(render_target.clone());
@@ -268,11 +645,11 @@ fn render_tinyvg_texture(
     let style_to_color = |style: &Style, color_table: &[TvgColor]| -> Color {
         match style {
             Style::FlatColor { color_index } => {
-                color_table.get(*color_index).map(|c| tvg_to_mq_color(c)).unwrap_or(WHITE)
+                color_table.get(*color_index).map(tvg_to_mq_color).unwrap_or(WHITE)
             }
             Style::LinearGradient { color_index_0, .. } |
             Style::RadialGradient { color_index_0, .. } => {
-                color_table.get(*color_index_0).map(|c| tvg_to_mq_color(c)).unwrap_or(WHITE)
+                color_table.get(*color_index_0).map(tvg_to_mq_color).unwrap_or(WHITE)
             }
         }
     };
@@ -814,957 +1191,831 @@ fn render_tinyvg_texture(
         }
     }
     
-    set_default_camera();
-    unsafe {
-        get_internal_gl().quad_gl.scissor(*clip);
-    }
+    state.restore_camera();
     
     Some(render_target.texture)
 }
 
-fn resize(texture: &Texture2D, height: f32, width: f32, clip: &Option<(i32, i32, i32, i32)>) -> Texture2D {
-    let render_target = render_target(width as u32, height as u32);
-    render_target.texture.set_filter(FilterMode::Linear);
-    let mut cam = Camera2D::from_display_rect(Rect::new(0.0, 0.0, width, height));
-    cam.render_target = Some(render_target.clone());
-    set_camera(&cam);
-    unsafe {
-        get_internal_gl().quad_gl.scissor(None);
+#[cfg(feature = "macroquad-text-styling")]
+fn draw_text_command(bb: BoundingBox, config: &Text, fonts: &[Font], state: &mut RenderState) {
+    use crate::renderers::macroquad_text_styling::StyledSegment;
+
+    let font_size = config.font_size as f32;
+    let font = Some(&fonts[config.font_id as usize]);
+    let default_color = clay_to_macroquad_color(&config.color);
+    let (pixel_size, pixel_scale) = pixel_font_size(config.font_size, state.scale);
+    // Text is stretched to its measured width, which was measured at the unscaled size
+    let stretch = config.letter_spacing > 0 || state.scale != 1.0;
+    let mut cache = TEXT_CACHE.lock().unwrap();
+    cache.rasterize(fonts, config.font_id, pixel_size);
+    let full_width = if stretch {
+        cache.measure(config.text, fonts, config.font_id, pixel_size, pixel_scale).width
+    } else {
+        0.0
     };
-    draw_texture_ex(
-        texture,
-        0.0,
-        0.0,
-        WHITE,
-        DrawTextureParams {
-            dest_size: Some(Vec2::new(width, height)),
-            flip_y: true,
-            ..Default::default()
-        },
-    );
-    set_default_camera();
-    unsafe {
-        get_internal_gl().quad_gl.scissor(*clip);
-    }
-    render_target.texture
-}
 
-fn render_common_command<'a, ImageType, CustomElementData>(
-    command: &RenderCommand<'a, ImageType, CustomElementData>,
-    fonts: &[Font],
-    handle_custom_command: &impl Fn(&RenderCommand<'a, ImageType, CustomElementData>),
-    state: &mut RenderState,
-) {
-    match &command.config {
-        #[cfg(feature = "macroquad-text-styling")]
-        RenderCommandConfig::Text(config) => {
-            use crate::renderers::macroquad_text_styling::StyledSegment;
-
-            let bb = command.bounding_box;
-            let font_size = config.font_size as f32;
-            let font = Some(&fonts[config.font_id as usize]);
-            let default_color = clay_to_macroquad_color(&config.color);
-
-            let normal_render = || {
-                let x_scale = if config.letter_spacing > 0 {
-                    bb.width / measure_text(
-                        config.text,
-                        font,
-                        config.font_size as u16,
-                        1.0
-                    ).width
+    let normal_render = || {
+        let x_scale = if stretch {
+            bb.width / full_width
+        } else {
+            1.0
+        };
+        draw_text_ex(
+            config.text,
+            bb.x,
+            bb.y + bb.height,
+            TextParams {
+                font_size: pixel_size,
+                font,
+                font_scale: pixel_scale,
+                font_scale_aspect: x_scale,
+                rotation: 0.0,
+                color: default_color
+            }
+        );
+    };
+    
+    let mut in_style_def = false;
+    let mut escaped = false;
+    let mut failed = false;
+    
+    let mut text_buffer = String::new();
+    let mut style_buffer = String::new();
+
+    let line = config.text.to_string();
+    let mut segments: Vec<StyledSegment> = Vec::new();
+
+    for c in line.chars() {
+        if escaped {
+            if in_style_def {
+                style_buffer.push(c);
+            } else {
+                text_buffer.push(c);
+            }
+            escaped = false;
+            continue;
+        }
+
+        match c {
+            '\\' => {
+                escaped = true;
+            }
+            '{' => {
+                if in_style_def {
+                    style_buffer.push(c); 
                 } else {
-                    1.0
-                };
-                draw_text_ex(
-                    config.text,
-                    bb.x,
-                    bb.y + bb.height,
-                    TextParams {
-                        font_size: config.font_size as u16,
-                        font,
-                        font_scale: 1.0,
-                        font_scale_aspect: x_scale,
-                        rotation: 0.0,
-                        color: default_color
-                    }
-                );
-            };
-            
-            let mut in_style_def = false;
-            let mut escaped = false;
-            let mut failed = false;
-            
-            let mut text_buffer = String::new();
-            let mut style_buffer = String::new();
-
-            let line = config.text.to_string();
-            let mut segments: Vec<StyledSegment> = Vec::new();
-
-            for c in line.chars() {
-                if escaped {
-                    if in_style_def {
-                        style_buffer.push(c);
-                    } else {
-                        text_buffer.push(c);
+                    if !text_buffer.is_empty() {
+                        segments.push(StyledSegment {
+                            text: text_buffer.clone(),
+                            styles: state.style_stack.clone(),
+                        });
+                        text_buffer.clear();
                     }
-                    escaped = false;
-                    continue;
+                    in_style_def = true;
                 }
-
-                match c {
-                    '\\' => {
-                        escaped = true;
-                    }
-                    '{' => {
-                        if in_style_def {
-                            style_buffer.push(c); 
-                        } else {
-                            if !text_buffer.is_empty() {
-                                segments.push(StyledSegment {
-                                    text: text_buffer.clone(),
-                                    styles: state.style_stack.clone(),
-                                });
-                                text_buffer.clear();
-                            }
-                            in_style_def = true;
-                        }
-                    }
-                    '|' => {
-                        if in_style_def {
-                            state.style_stack.push(style_buffer.clone());
-                            style_buffer.clear();
-                            in_style_def = false;
-                        } else {
-                            text_buffer.push(c);
-                        }
-                    }
-                    '}' => {
-                        if in_style_def {
-                            style_buffer.push(c);
-                        } else {
-                            if !text_buffer.is_empty() {
-                                segments.push(StyledSegment {
-                                    text: text_buffer.clone(),
-                                    styles: state.style_stack.clone(),
-                                });
-                                text_buffer.clear();
-                            }
-                            
-                            if state.style_stack.pop().is_none() {
-                                failed = true;
-                                break;
-                            }
-                        }
+            }
+            '|' => {
+                if in_style_def {
+                    state.style_stack.push(style_buffer.clone());
+                    style_buffer.clear();
+                    in_style_def = false;
+                } else {
+                    text_buffer.push(c);
+                }
+            }
+            '}' => {
+                if in_style_def {
+                    style_buffer.push(c);
+                } else {
+                    if !text_buffer.is_empty() {
+                        segments.push(StyledSegment {
+                            text: text_buffer.clone(),
+                            styles: state.style_stack.clone(),
+                        });
+                        text_buffer.clear();
                     }
-                    _ => {
-                        if in_style_def {
-                            style_buffer.push(c);
-                        } else {
-                            text_buffer.push(c);
-                        }
+                    
+                    if state.style_stack.pop().is_none() {
+                        failed = true;
+                        break;
                     }
                 }
             }
-            if !(failed || in_style_def) {
-                if !text_buffer.is_empty() {
-                    segments.push(StyledSegment {
-                        text: text_buffer.clone(),
-                        styles: state.style_stack.clone(),
-                    });
-                }
-                
-                let time = get_time();
-                
-                let cursor_x = std::cell::Cell::new(bb.x);
-                let cursor_y = bb.y + bb.height;
-                let mut pending_renders = Vec::new();
-                
-                let x_scale = if config.letter_spacing > 0 {
-                    bb.width / measure_text(
-                        config.text,
-                        Some(&fonts[config.font_id as usize]),
-                        config.font_size as u16,
-                        1.0
-                    ).width
+            _ => {
+                if in_style_def {
+                    style_buffer.push(c);
                 } else {
-                    1.0
-                };
-                {
-                    let mut tracker = ANIMATION_TRACKER.lock().unwrap();
-                    render_styled_text(
-                        &segments,
-                        time,
-                        font_size,
-                        &mut *tracker,
-                        &mut state.total_char_index,
-                        |text, tr, style_color| {
-                            let text_string = text.to_string();
-                            let text_width = measure_text(&text_string, font, config.font_size as u16, 1.0).width;
-                            
-                            let color = Color::new(style_color.r, style_color.g, style_color.b, style_color.a);
-                            let x = cursor_x.get();
-                            
-                            pending_renders.push((x, text_string, tr, color));
-                            
-                            cursor_x.set(x + text_width*x_scale);
-                        },
-                        |text, tr, style_color| {
-                            let text_string = text.to_string();
-                            let color = Color::new(style_color.r, style_color.g, style_color.b, style_color.a);
-                            let x = cursor_x.get();
-                            
-                            draw_text_ex(
-                                &text_string,
-                                x + tr.x*x_scale,
-                                cursor_y + tr.y,
-                                TextParams {
-                                    font_size: config.font_size as u16,
-                                    font,
-                                    font_scale: tr.scale_y.max(0.01),
-                                    font_scale_aspect: if tr.scale_y > 0.01 { tr.scale_x / tr.scale_y * x_scale } else { x_scale },
-                                    rotation: tr.rotation.to_radians(),
-                                    color
-                                }
-                            );
-                        }
-                    );
+                    text_buffer.push(c);
                 }
-                for (x, text_string, tr, color) in pending_renders {
+            }
+        }
+    }
+    if !(failed || in_style_def) {
+        if !text_buffer.is_empty() {
+            segments.push(StyledSegment {
+                text: text_buffer.clone(),
+                styles: state.style_stack.clone(),
+            });
+        }
+        
+        let time = get_time();
+        
+        let cursor_x = std::cell::Cell::new(bb.x);
+        let cursor_y = bb.y + bb.height;
+        let mut pending_renders = Vec::new();
+        
+        let x_scale = if stretch {
+            bb.width / full_width
+        } else {
+            1.0
+        };
+        {
+            let mut tracker = ANIMATION_TRACKER.lock().unwrap();
+            render_styled_text(
+                &segments,
+                time,
+                font_size,
+                &mut *tracker,
+                &mut state.total_char_index,
+                |text, tr, style_color| {
+                    let text_string = text.to_string();
+                    let text_width = cache.measure(&text_string, fonts, config.font_id, pixel_size, pixel_scale).width;
+                    
+                    let color = Color::new(style_color.r, style_color.g, style_color.b, style_color.a);
+                    let x = cursor_x.get();
+                    
+                    pending_renders.push((x, text_string, tr, color));
+                    
+                    cursor_x.set(x + text_width*x_scale);
+                },
+                |text, tr, style_color| {
+                    let text_string = text.to_string();
+                    let color = Color::new(style_color.r, style_color.g, style_color.b, style_color.a);
+                    let x = cursor_x.get();
+                    
                     draw_text_ex(
                         &text_string,
                         x + tr.x*x_scale,
                         cursor_y + tr.y,
                         TextParams {
-                            font_size: config.font_size as u16,
+                            font_size: pixel_size,
                             font,
-                            font_scale: tr.scale_y.max(0.01),
+                            font_scale: tr.scale_y.max(0.01) * pixel_scale,
                             font_scale_aspect: if tr.scale_y > 0.01 { tr.scale_x / tr.scale_y * x_scale } else { x_scale },
                             rotation: tr.rotation.to_radians(),
                             color
                         }
                     );
                 }
-            } else {
-                if in_style_def {
-                    warn!("Style definition didn't end! Here is what we tried to render: {}", config.text);
-                } else if failed {
-                    warn!("Encountered }} without opened style! Make sure to escape curly braces with \\. Here is what we tried to render: {}", config.text);
-                }
-                normal_render();
-            }
+            );
         }
-        #[cfg(not(feature = "macroquad-text-styling"))]
-        RenderCommandConfig::Text(config) => {
-            let bb = command.bounding_box;
-            let color = clay_to_macroquad_color(&config.color);
-
-            let x_scale = if config.letter_spacing > 0 {
-                bb.width / measure_text(
-                    config.text,
-                    Some(&fonts[config.font_id as usize]),
-                    config.font_size as u16,
-                    1.0
-                ).width
-            } else {
-                1.0
-            };
+        for (x, text_string, tr, color) in pending_renders {
             draw_text_ex(
-                &config.text,
-                bb.x,
-                bb.y + bb.height,
+                &text_string,
+                x + tr.x*x_scale,
+                cursor_y + tr.y,
                 TextParams {
-                    font_size: config.font_size as u16,
-                    font: Some(&fonts[config.font_id as usize]),
-                    font_scale: 1.0,
-                    font_scale_aspect: x_scale,
-                    rotation: 0.0,
+                    font_size: pixel_size,
+                    font,
+                    font_scale: tr.scale_y.max(0.01) * pixel_scale,
+                    font_scale_aspect: if tr.scale_y > 0.01 { tr.scale_x / tr.scale_y * x_scale } else { x_scale },
+                    rotation: tr.rotation.to_radians(),
                     color
                 }
             );
         }
-        RenderCommandConfig::Border(config) => {
-            let bb = command.bounding_box;
-            let bw = &config.width;
-            let cr = &config.corner_radii;
-            let color = clay_to_macroquad_color(&config.color);
-            if cr.top_left == 0.0 && cr.top_right == 0.0 && cr.bottom_left == 0.0 && cr.bottom_right == 0.0 {
-                if bw.left == bw.right && bw.left == bw.top && bw.left == bw.bottom {
-                    let border_width = bw.left as f32;
-                    draw_rectangle_lines(
-                        bb.x - border_width / 2.0,
-                        bb.y - border_width / 2.0,
-                        bb.width + border_width,
-                        bb.height + border_width,
-                        border_width,
-                        color
-                    );
-                } else {
-                    // Top edge
-                    draw_line(
-                        bb.x,
-                        bb.y - bw.top as f32 / 2.0,
-                        bb.x + bb.width,
-                        bb.y - bw.top as f32 / 2.0,
-                        bw.top as f32,
-                        color
-                    );
-                    // Left edge
-                    draw_line(
-                        bb.x - bw.left as f32 / 2.0,
-                        bb.y,
-                        bb.x - bw.left as f32 / 2.0,
-                        bb.y + bb.height,
-                        bw.left as f32,
-                        color
-                    );
-                    // Bottom edge
-                    draw_line(
-                        bb.x,
-                        bb.y + bb.height + bw.bottom as f32 / 2.0,
-                        bb.x + bb.width,
-                        bb.y + bb.height + bw.bottom as f32 / 2.0,
-                        bw.bottom as f32,
-                        color
-                    );
-                    // Right edge
-                    draw_line(
-                        bb.x + bb.width + bw.right as f32 / 2.0,
-                        bb.y,
-                        bb.x + bb.width + bw.right as f32 / 2.0,
-                        bb.y + bb.height,
-                        bw.right as f32,
-                        color
-                    );
-                }
-            } else {
-                // Edges
-                // Top edge
-                draw_line(
-                    bb.x + cr.top_left,
-                    bb.y - bw.top as f32 / 2.0,
-                    bb.x + bb.width - cr.top_right,
-                    bb.y - bw.top as f32 / 2.0,
-                    bw.top as f32,
-                    color
-                );
-                // Left edge
-                draw_line(
-                    bb.x - bw.left as f32 / 2.0,
-                    bb.y + cr.top_left,
-                    bb.x - bw.left as f32 / 2.0,
-                    bb.y + bb.height - cr.bottom_left,
-                    bw.left as f32,
-                    color
-                );
-                // Bottom edge
-                draw_line(
-                    bb.x + cr.bottom_left,
-                    bb.y + bb.height + bw.bottom as f32 / 2.0,
-                    bb.x + bb.width - cr.bottom_right,
-                    bb.y + bb.height + bw.bottom as f32 / 2.0,
-                    bw.bottom as f32,
-                    color
-                );
-                // Right edge
-                draw_line(
-                    bb.x + bb.width + bw.right as f32 / 2.0,
-                    bb.y + cr.top_right,
-                    bb.x + bb.width + bw.right as f32 / 2.0,
-                    bb.y + bb.height - cr.bottom_right,
-                    bw.right as f32,
-                    color
-                );
-
-                // Corners
-                // Top-left corner
-                if cr.top_left > 0.0 {
-                    let width = bw.left.max(bw.top) as f32;
-                    let points = ((std::f32::consts::PI * (cr.top_left + width)) / 2.0 / PIXELS_PER_POINT).max(5.0);
-                    draw_arc(
-                        bb.x + cr.top_left,
-                        bb.y + cr.top_left,
-                        points as u8,
-                        cr.top_left,
-                        180.0,
-                        bw.left as f32,
-                        90.0,
-                        color
-                    );
-                }
-                // Top-right corner
-                if cr.top_right > 0.0 {
-                    let width = bw.top.max(bw.right) as f32;
-                    let points = ((std::f32::consts::PI * (cr.top_right + width)) / 2.0 / PIXELS_PER_POINT).max(5.0);
-                    draw_arc(
-                        bb.x + bb.width - cr.top_right,
-                        bb.y + cr.top_right,
-                        points as u8,
-                        cr.top_right,
-                        270.0,
-                        bw.top as f32,
-                        90.0,
-                        color
-                    );
-                }
-                // Bottom-left corner
-                if cr.bottom_left > 0.0 {
-                    let width = bw.left.max(bw.bottom) as f32;
-                    let points = ((std::f32::consts::PI * (cr.bottom_left + width)) / 2.0 / PIXELS_PER_POINT).max(5.0);
-                    draw_arc(
-                        bb.x + cr.bottom_left,
-                        bb.y + bb.height - cr.bottom_left,
-                        points as u8,
-                        cr.bottom_left,
-                        90.0,
-                        bw.bottom as f32,
-                        90.0,
-                        color
-                    );
-                }
-                // Bottom-right corner
-                if cr.bottom_right > 0.0 {
-                    let width = bw.bottom.max(bw.right) as f32;
-                    let points = ((std::f32::consts::PI * (cr.bottom_right + width)) / 2.0 / PIXELS_PER_POINT).max(5.0);
-                    draw_arc(
-                        bb.x + bb.width - cr.bottom_right,
-                        bb.y + bb.height - cr.bottom_right,
-                        points as u8,
-                        cr.bottom_right,
-                        0.0,
-                        bw.right as f32,
-                        90.0,
-                        color
-                    );
-                }
-            }
+    } else {
+        if in_style_def {
+            warn!("Style definition didn't end! Here is what we tried to render: {}", config.text);
+        } else if failed {
+            warn!("Encountered }} without opened style! Make sure to escape curly braces with \\. Here is what we tried to render: {}", config.text);
         }
-        RenderCommandConfig::ScissorStart() => {
-            let bb = command.bounding_box;
-            state.clip = Some((
-                bb.x as i32,
-                bb.y as i32,
-                bb.width as i32,
-                bb.height as i32,
-            ));
-            unsafe {
-                get_internal_gl().quad_gl.scissor(state.clip);
-            }
+        normal_render();
+    }
+}
+
+#[cfg(not(feature = "macroquad-text-styling"))]
+fn draw_text_command(bb: BoundingBox, config: &Text, fonts: &[Font], state: &mut RenderState) {
+    let color = clay_to_macroquad_color(&config.color);
+    let (pixel_size, pixel_scale) = pixel_font_size(config.font_size, state.scale);
+    let font = &fonts[config.font_id as usize];
+    let mut cache = TEXT_CACHE.lock().unwrap();
+    cache.rasterize(fonts, config.font_id, pixel_size);
+
+    // Text is stretched to its measured width, which was measured at the unscaled size
+    let x_scale = if config.letter_spacing > 0 || state.scale != 1.0 {
+        bb.width / cache.measure(config.text, fonts, config.font_id, pixel_size, pixel_scale).width
+    } else {
+        1.0
+    };
+    draw_text_ex(
+        config.text,
+        bb.x,
+        bb.y + bb.height,
+        TextParams {
+            font_size: pixel_size,
+            font: Some(font),
+            font_scale: pixel_scale,
+            font_scale_aspect: x_scale,
+            rotation: 0.0,
+            color
         }
-        RenderCommandConfig::ScissorEnd() => {
-            state.clip = None;
-            unsafe {
-                get_internal_gl().quad_gl.scissor(None);
-            }
+    );
+}
+
+fn draw_border_command(bb: BoundingBox, config: &Border, state: &RenderState) {
+    let bw = &config.width;
+    let cr = &config.corner_radii;
+    let color = clay_to_macroquad_color(&config.color);
+    if cr.top_left == 0.0 && cr.top_right == 0.0 && cr.bottom_left == 0.0 && cr.bottom_right == 0.0 {
+        if bw.left == bw.right && bw.left == bw.top && bw.left == bw.bottom {
+            let border_width = bw.left as f32;
+            draw_rectangle_lines(
+                bb.x - border_width / 2.0,
+                bb.y - border_width / 2.0,
+                bb.width + border_width,
+                bb.height + border_width,
+                border_width,
+                color
+            );
+        } else {
+            // Top edge
+            draw_line(
+                bb.x,
+                bb.y - bw.top as f32 / 2.0,
+                bb.x + bb.width,
+                bb.y - bw.top as f32 / 2.0,
+                bw.top as f32,
+                color
+            );
+            // Left edge
+            draw_line(
+                bb.x - bw.left as f32 / 2.0,
+                bb.y,
+                bb.x - bw.left as f32 / 2.0,
+                bb.y + bb.height,
+                bw.left as f32,
+                color
+            );
+            // Bottom edge
+            draw_line(
+                bb.x,
+                bb.y + bb.height + bw.bottom as f32 / 2.0,
+                bb.x + bb.width,
+                bb.y + bb.height + bw.bottom as f32 / 2.0,
+                bw.bottom as f32,
+                color
+            );
+            // Right edge
+            draw_line(
+                bb.x + bb.width + bw.right as f32 / 2.0,
+                bb.y,
+                bb.x + bb.width + bw.right as f32 / 2.0,
+                bb.y + bb.height,
+                bw.right as f32,
+                color
+            );
+        }
+    } else {
+        // Edges
+        // Top edge
+        draw_line(
+            bb.x + cr.top_left,
+            bb.y - bw.top as f32 / 2.0,
+            bb.x + bb.width - cr.top_right,
+            bb.y - bw.top as f32 / 2.0,
+            bw.top as f32,
+            color
+        );
+        // Left edge
+        draw_line(
+            bb.x - bw.left as f32 / 2.0,
+            bb.y + cr.top_left,
+            bb.x - bw.left as f32 / 2.0,
+            bb.y + bb.height - cr.bottom_left,
+            bw.left as f32,
+            color
+        );
+        // Bottom edge
+        draw_line(
+            bb.x + cr.bottom_left,
+            bb.y + bb.height + bw.bottom as f32 / 2.0,
+            bb.x + bb.width - cr.bottom_right,
+            bb.y + bb.height + bw.bottom as f32 / 2.0,
+            bw.bottom as f32,
+            color
+        );
+        // Right edge
+        draw_line(
+            bb.x + bb.width + bw.right as f32 / 2.0,
+            bb.y + cr.top_right,
+            bb.x + bb.width + bw.right as f32 / 2.0,
+            bb.y + bb.height - cr.bottom_right,
+            bw.right as f32,
+            color
+        );
+
+        // Corners
+        // Top-left corner
+        if cr.top_left > 0.0 {
+            let width = bw.left.max(bw.top) as f32;
+            let points = ((std::f32::consts::PI * (cr.top_left + width)) * state.scale / 2.0 / PIXELS_PER_SEGMENT).max(5.0);
+            draw_arc(
+                bb.x + cr.top_left,
+                bb.y + cr.top_left,
+                points as u8,
+                cr.top_left,
+                180.0,
+                bw.left as f32,
+                90.0,
+                color
+            );
+        }
+        // Top-right corner
+        if cr.top_right > 0.0 {
+            let width = bw.top.max(bw.right) as f32;
+            let points = ((std::f32::consts::PI * (cr.top_right + width)) * state.scale / 2.0 / PIXELS_PER_SEGMENT).max(5.0);
+            draw_arc(
+                bb.x + bb.width - cr.top_right,
+                bb.y + cr.top_right,
+                points as u8,
+                cr.top_right,
+                270.0,
+                bw.top as f32,
+                90.0,
+                color
+            );
+        }
+        // Bottom-left corner
+        if cr.bottom_left > 0.0 {
+            let width = bw.left.max(bw.bottom) as f32;
+            let points = ((std::f32::consts::PI * (cr.bottom_left + width)) * state.scale / 2.0 / PIXELS_PER_SEGMENT).max(5.0);
+            draw_arc(
+                bb.x + cr.bottom_left,
+                bb.y + bb.height - cr.bottom_left,
+                points as u8,
+                cr.bottom_left,
+                90.0,
+                bw.bottom as f32,
+                90.0,
+                color
+            );
+        }
+        // Bottom-right corner
+        if cr.bottom_right > 0.0 {
+            let width = bw.bottom.max(bw.right) as f32;
+            let points = ((std::f32::consts::PI * (cr.bottom_right + width)) * state.scale / 2.0 / PIXELS_PER_SEGMENT).max(5.0);
+            draw_arc(
+                bb.x + bb.width - cr.bottom_right,
+                bb.y + bb.height - cr.bottom_right,
+                points as u8,
+                cr.bottom_right,
+                0.0,
+                bw.right as f32,
+                90.0,
+                color
+            );
+        }
+    }
+}
+
+/// Draws a rectangle, with its rounded corners drawn as circles if it is opaque
+fn draw_rectangle_command(bb: BoundingBox, config: &Rectangle, state: &RenderState) {
+    let color = clay_to_macroquad_color(&config.color);
+    let cr = &config.corner_radii;
+
+    if cr.top_left == 0.0 && cr.top_right == 0.0 && cr.bottom_left == 0.0 && cr.bottom_right == 0.0 {
+        draw_rectangle(
+            bb.x,
+            bb.y,
+            bb.width,
+            bb.height,
+            color
+        );
+    } else if color.a == 1.0 {
+        // Edges
+        // Top edge
+        if cr.top_left > 0.0 || cr.top_right > 0.0 {
+            draw_rectangle(
+                bb.x + cr.top_left,
+                bb.y,
+                bb.width - cr.top_left - cr.top_right,
+                bb.height - cr.bottom_left.max(cr.bottom_right),
+                color
+            );
+        }
+        // Left edge
+        if cr.top_left > 0.0 || cr.bottom_left > 0.0 {
+            draw_rectangle(
+                bb.x,
+                bb.y + cr.top_left,
+                bb.width - cr.top_right.max(cr.bottom_right),
+                bb.height - cr.top_left - cr.bottom_left,
+                color
+            );
+        }
+        // Bottom edge
+        if cr.bottom_left > 0.0 || cr.bottom_right > 0.0 {
+            draw_rectangle(
+                bb.x + cr.bottom_left,
+                bb.y + cr.top_left.max(cr.top_right),
+                bb.width - cr.bottom_left - cr.bottom_right,
+                bb.height - cr.top_left.max(cr.top_right),
+                color
+            );
+        }
+        // Right edge
+        if cr.top_right > 0.0 || cr.bottom_right > 0.0 {
+            draw_rectangle(
+                bb.x + cr.top_left.max(cr.bottom_left),
+                bb.y + cr.top_right,
+                bb.width - cr.top_left.max(cr.bottom_left),
+                bb.height - cr.top_right - cr.bottom_right,
+                color
+            );
+        }
+
+        // Corners
+        // Top-left corner
+        if cr.top_left > 0.0 {
+            draw_good_circle(
+                bb.x + cr.top_left,
+                bb.y + cr.top_left,
+                cr.top_left,
+                state.scale,
+                color,
+            );
         }
-        RenderCommandConfig::Custom(_) => {
-            handle_custom_command(command);
+        // Top-right corner
+        if cr.top_right > 0.0 {
+            draw_good_circle(
+                bb.x + bb.width - cr.top_right,
+                bb.y + cr.top_right,
+                cr.top_right,
+                state.scale,
+                color,
+            );
         }
-        RenderCommandConfig::Image(_) => {}
-        RenderCommandConfig::Rectangle(_) => {}
-        RenderCommandConfig::None() => {}
+        // Bottom-left corner
+        if cr.bottom_left > 0.0 {
+            draw_good_circle(
+                bb.x + cr.bottom_left,
+                bb.y + bb.height - cr.bottom_left,
+                cr.bottom_left,
+                state.scale,
+                color,
+            );
+        }
+        // Bottom-right corner
+        if cr.bottom_right > 0.0 {
+            draw_good_circle(
+                bb.x + bb.width - cr.bottom_right,
+                bb.y + bb.height - cr.bottom_right,
+                cr.bottom_right,
+                state.scale,
+                color,
+            );
+        }
+    } else {
+        // Translucent rectangles are drawn in one piece, as overlapping pieces would blend twice
+        with_rounded_clip(bb, cr, state, || {
+            draw_rectangle(bb.x, bb.y, bb.width, bb.height, color);
+        });
+    }
+}
+
+/// Images are tinted with their background color, or drawn as they are without one
+fn image_tint<ImageElementData>(image: &ImageCommand<ImageElementData>) -> Color {
+    let tint = clay_to_macroquad_color(&image.background_color);
+    if tint == Color::new(0.0, 0.0, 0.0, 0.0) {
+        Color::new(1.0, 1.0, 1.0, 1.0)
+    } else {
+        tint
     }
 }
 
 #[cfg(not(feature = "macroquad-texture-manager"))]
-pub fn clay_macroquad_render<'a, CustomElementData: 'a>(
-    commands: impl Iterator<Item = RenderCommand<'a, Texture2D, CustomElementData>>,
-    fonts: &[Font],
-    handle_custom_command: impl Fn(&RenderCommand<'a, Texture2D, CustomElementData>)
-) {
-    let mut state = RenderState::new();
-    for command in commands {
-        match &command.config {
-            RenderCommandConfig::Image(image) => {
-                let bb = command.bounding_box;
-                let cr = &image.corner_radii;
-                let mut tint = clay_to_macroquad_color(&image.background_color);
-                if tint == Color::new(0.0, 0.0, 0.0, 0.0) {
-                    tint = Color::new(1.0, 1.0, 1.0, 1.0);
-                }
-                if cr.top_left == 0.0 && cr.top_right == 0.0 && cr.bottom_left == 0.0 && cr.bottom_right == 0.0 {
-                    draw_texture_ex(
-                        image.data,
-                        bb.x,
-                        bb.y,
-                        tint,
-                        DrawTextureParams {
-                            dest_size: Some(Vec2::new(bb.width, bb.height)),
-                            ..Default::default()
-                        },
-                    );
-                } else {
-                    let mut resized_image: Image = resize(&image.data, bb.height, bb.width, &state.clip).get_texture_data();
-                    let rounded_rect: Image = rounded_rectangle_texture(cr, &bb, &state.clip).get_texture_data();
+fn draw_image_command(bb: BoundingBox, image: &ImageCommand<Texture2D>, state: &RenderState) {
+    draw_texture_rounded(image.data, bb, &image.corner_radii, image_tint(image), false, state);
+}
 
-                    for i in 0..resized_image.bytes.len()/4 {
-                        let this_alpha = resized_image.bytes[i * 4 + 3] as f32 / 255.0;
-                        let mask_alpha = rounded_rect.bytes[i * 4 + 3] as f32 / 255.0;
-                        resized_image.bytes[i * 4 + 3] = (this_alpha * mask_alpha * 255.0) as u8;
-                    }
+/// A [`Renderer`] drawing with macroquad, with the fonts indexed by the font ids.
+///
+/// Custom elements are drawn by `handle_custom_element`, with the camera in layout units.
+///
+/// With the `macroquad-texture-manager` feature, images are [`ImageSource`]s drawn with the
+/// [`TEXTURE_MANAGER`], see [`TextureManager::get_image`] for the paths that are loaded without
+/// awaiting. Others are drawn once loaded, for example by [`clay_macroquad_render`] or
+/// [`TextureManager::get_or_load`].
+pub struct MacroquadRenderer<'a, F> {
+    pub fonts: &'a [Font],
+    pub handle_custom_element: F,
+    state: RenderState,
+}
 
-                    draw_texture_ex(
-                        &Texture2D::from_image(&resized_image),
-                        bb.x,
-                        bb.y,
-                        tint,
-                        DrawTextureParams {
-                            dest_size: Some(Vec2::new(bb.width, bb.height)),
-                            ..Default::default()
-                        },
-                    );
+impl<'a, F> MacroquadRenderer<'a, F> {
+    pub fn new(fonts: &'a [Font], handle_custom_element: F) -> Self {
+        Self {
+            fonts,
+            handle_custom_element,
+            state: RenderState::new(),
+        }
+    }
+}
+
+fn skip_custom_element<CustomElementData>(_: BoundingBox, _: &Custom<CustomElementData>) {}
+
+impl<CustomElementData, F> Renderer<CustomElementData> for MacroquadRenderer<'_, F>
+where
+    F: FnMut(BoundingBox, &Custom<CustomElementData>),
+{
+    #[cfg(not(feature = "macroquad-texture-manager"))]
+    type ImageElementData = Texture2D;
+    #[cfg(feature = "macroquad-texture-manager")]
+    type ImageElementData = ImageSource;
+
+    fn end_frame(&mut self) {
+        self.state.set_scale(1.0);
+        #[cfg(feature = "macroquad-texture-manager")]
+        TEXTURE_MANAGER.lock().unwrap().clean();
+        TEXT_CACHE.lock().unwrap().clean();
+    }
+
+    fn rectangle(&mut self, bounds: BoundingBox, scale_factor: f32, rectangle: &Rectangle) {
+        self.state.set_scale(scale_factor);
+        draw_rectangle_command(bounds, rectangle, &self.state);
+    }
+
+    fn border(&mut self, bounds: BoundingBox, scale_factor: f32, border: &Border) {
+        self.state.set_scale(scale_factor);
+        draw_border_command(bounds, border, &self.state);
+    }
+
+    fn text(&mut self, bounds: BoundingBox, scale_factor: f32, text: &Text) {
+        self.state.set_scale(scale_factor);
+        draw_text_command(bounds, text, self.fonts, &mut self.state);
+    }
+
+    #[cfg(not(feature = "macroquad-texture-manager"))]
+    fn image(&mut self, bounds: BoundingBox, scale_factor: f32, image: &ImageCommand<Texture2D>) {
+        self.state.set_scale(scale_factor);
+        draw_image_command(bounds, image, &self.state);
+    }
+
+    #[cfg(feature = "macroquad-texture-manager")]
+    fn image(&mut self, bounds: BoundingBox, scale_factor: f32, image: &ImageCommand<ImageSource>) {
+        self.state.set_scale(scale_factor);
+        let mut manager = TEXTURE_MANAGER.lock().unwrap();
+        #[cfg(feature = "macroquad-tinyvg")]
+        if let ImageSource::Path(path) = image.data {
+            if let Some(key) = tinyvg_key(path, bounds, &self.state) {
+                if let Some(texture) = manager.get_tinyvg(path, key, bounds, &self.state) {
+                    draw_texture_rounded(&texture, bounds, &image.corner_radii, image_tint(image), true, &self.state);
                 }
+                return;
             }
-            RenderCommandConfig::Rectangle(config) => {
-                let bb = command.bounding_box;
-                let color = clay_to_macroquad_color(&config.color);
-                let cr = &config.corner_radii;
+        }
+        if let Some(texture) = manager.get_image(image.data) {
+            draw_texture_rounded(&texture, bounds, &image.corner_radii, image_tint(image), false, &self.state);
+        }
+    }
 
-                if cr.top_left == 0.0 && cr.top_right == 0.0 && cr.bottom_left == 0.0 && cr.bottom_right == 0.0 {
-                    draw_rectangle(
-                        bb.x,
-                        bb.y,
-                        bb.width,
-                        bb.height,
-                        color
-                    );
-                } else if color.a == 1.0 {
-                    // Edges
-                    // Top edge
-                    if cr.top_left > 0.0 || cr.top_right > 0.0 {
-                        draw_rectangle(
-                            bb.x + cr.top_left,
-                            bb.y,
-                            bb.width - cr.top_left - cr.top_right,
-                            bb.height - cr.bottom_left.max(cr.bottom_right),
-                            color
-                        );
-                    }
-                    // Left edge
-                    if cr.top_left > 0.0 || cr.bottom_left > 0.0 {
-                        draw_rectangle(
-                            bb.x,
-                            bb.y + cr.top_left,
-                            bb.width - cr.top_right.max(cr.bottom_right),
-                            bb.height - cr.top_left - cr.bottom_left,
-                            color
-                        );
-                    }
-                    // Bottom edge
-                    if cr.bottom_left > 0.0 || cr.bottom_right > 0.0 {
-                        draw_rectangle(
-                            bb.x + cr.bottom_left,
-                            bb.y + cr.top_left.max(cr.top_right),
-                            bb.width - cr.bottom_left - cr.bottom_right,
-                            bb.height - cr.top_left.max(cr.top_right),
-                            color
-                        );
-                    }
-                    // Right edge
-                    if cr.top_right > 0.0 || cr.bottom_right > 0.0 {
-                        draw_rectangle(
-                            bb.x + cr.top_left.max(cr.bottom_left),
-                            bb.y + cr.top_right,
-                            bb.width - cr.top_left.max(cr.bottom_left),
-                            bb.height - cr.top_right - cr.bottom_right,
-                            color
-                        );
-                    }
+    fn push_scissor(&mut self, bounds: BoundingBox, scale_factor: f32) {
+        self.state.set_scale(scale_factor);
+        self.state.push_scissor(bounds);
+    }
 
-                    // Corners
-                    // Top-left corner
-                    if cr.top_left > 0.0 {
-                        draw_good_circle(
-                            bb.x + cr.top_left,
-                            bb.y + cr.top_left,
-                            cr.top_left,
-                            color,
-                        );
-                    }
-                    // Top-right corner
-                    if cr.top_right > 0.0 {
-                        draw_good_circle(
-                            bb.x + bb.width - cr.top_right,
-                            bb.y + cr.top_right,
-                            cr.top_right,
-                            color,
-                        );
-                    }
-                    // Bottom-left corner
-                    if cr.bottom_left > 0.0 {
-                        draw_good_circle(
-                            bb.x + cr.bottom_left,
-                            bb.y + bb.height - cr.bottom_left,
-                            cr.bottom_left,
-                            color,
-                        );
-                    }
-                    // Bottom-right corner
-                    if cr.bottom_right > 0.0 {
-                        draw_good_circle(
-                            bb.x + bb.width - cr.bottom_right,
-                            bb.y + bb.height - cr.bottom_right,
-                            cr.bottom_right,
-                            color,
-                        );
-                    }
-                } else {
-                    draw_texture_ex(
-                        &rounded_rectangle_texture(cr, &bb, &state.clip),
-                        bb.x,
-                        bb.y,
-                        color,
-                        DrawTextureParams {
-                            dest_size: Some(Vec2::new(bb.width, bb.height)),
-                            flip_y: true,
-                            ..Default::default()
-                        },
-                    );
-                }
+    fn pop_scissor(&mut self) {
+        self.state.pop_scissor();
+    }
+
+    fn custom(&mut self, bounds: BoundingBox, scale_factor: f32, custom: &Custom<CustomElementData>) {
+        self.state.set_scale(scale_factor);
+        (self.handle_custom_element)(bounds, custom);
+    }
+
+    fn measure_text_function(&self) -> impl Fn(&str, &TextConfig) -> Dimensions + 'static {
+        create_measure_text_function(self.fonts.to_vec())
+    }
+}
+
+#[cfg(not(feature = "macroquad-texture-manager"))]
+pub fn clay_macroquad_render<'a, CustomElementData: 'a, UserData: 'a>(
+    commands: impl Iterator<Item = RenderCommand<'a, Texture2D, CustomElementData, UserData>>,
+    fonts: &[Font],
+    handle_custom_command: impl Fn(&RenderCommand<'a, Texture2D, CustomElementData, UserData>)
+) {
+    let mut renderer = MacroquadRenderer::new(fonts, skip_custom_element::<CustomElementData>);
+    renderer.begin_frame();
+    for command in commands {
+        match &command.config {
+            RenderCommandConfig::Custom(_) => {
+                renderer.state.set_scale(command.scale_factor);
+                handle_custom_command(&command);
             }
-            _ => render_common_command(&command, fonts, &handle_custom_command, &mut state),
+            _ => render_command(&mut renderer, &command),
         }
     }
+    renderer.end_frame();
 }
 
+// Macroquad runs its futures on a single thread, so holding the manager lock while loading is fine.
 #[cfg(feature = "macroquad-texture-manager")]
-pub async fn clay_macroquad_render<'a, CustomElementData: 'a>(
-    commands: impl Iterator<Item = RenderCommand<'a, &'static str, CustomElementData>>,
+#[allow(clippy::await_holding_lock)]
+pub async fn clay_macroquad_render<'a, CustomElementData: 'a, UserData: 'a>(
+    commands: impl Iterator<Item = RenderCommand<'a, ImageSource, CustomElementData, UserData>>,
     fonts: &[Font],
-    handle_custom_command: impl Fn(&RenderCommand<'a, &'static str, CustomElementData>),
+    handle_custom_command: impl Fn(&RenderCommand<'a, ImageSource, CustomElementData, UserData>),
 ) {
-    let mut state = RenderState::new();
+    let mut renderer = MacroquadRenderer::new(fonts, skip_custom_element::<CustomElementData>);
+    renderer.begin_frame();
     for command in commands {
         match &command.config {
             RenderCommandConfig::Image(image) => {
+                renderer.state.set_scale(command.scale_factor);
+                let state = &renderer.state;
                 let bb = command.bounding_box;
                 let cr = &image.corner_radii;
-                let mut tint = clay_to_macroquad_color(&image.background_color);
-                if tint == Color::new(0.0, 0.0, 0.0, 0.0) {
-                    tint = Color::new(1.0, 1.0, 1.0, 1.0);
-                }
+                let tint = image_tint(image);
                 
                 let mut manager = TEXTURE_MANAGER.lock().unwrap();
 
                 #[cfg(feature = "macroquad-tinyvg")]
-                let is_tvg = image.data.to_lowercase().ends_with(".tvg");
-                #[cfg(not(feature = "macroquad-tinyvg"))]
-                let is_tvg = false;
-
-                #[cfg(feature = "macroquad-tinyvg")]
-                if is_tvg {
-                    let tvg_data = load_file(image.data).await;
-                    
-                    if let Ok(tvg_bytes) = tvg_data {
-                        let key = format!(
-                            "tvg:{}:{}:{}:{}:{}:{}:{}:{:?}",
-                            image.data,
-                            bb.width,
-                            bb.height,
-                            cr.top_left,
-                            cr.top_right,
-                            cr.bottom_left,
-                            cr.bottom_right,
-                            state.clip
-                        );
-                        let has_corner_radii = cr.top_left > 0.0 || cr.top_right > 0.0 || cr.bottom_left > 0.0 || cr.bottom_right > 0.0;
-                        let texture = if !has_corner_radii {
-                            manager.get_or_create(key, || {
-                                if let Some(tvg_texture) = render_tinyvg_texture(&tvg_bytes, bb.width, bb.height, &state.clip) {
-                                    tvg_texture
-                                } else {
-                                    warn!("Failed to load TinyVG image: {}", image.data);
-                                    Texture2D::from_rgba8(1, 1, &[0, 0, 0, 0])
-                                }
-                            })
-                        } else {
-                            let zerocr_key = format!(
-                                "tvg:{}:{}:{}:{}:{}:{}:{}:{:?}",
-                                image.data,
-                                bb.width,
-                                bb.height,
-                                0.0,
-                                0.0,
-                                0.0,
-                                0.0,
-                                state.clip
-                            );
-                            
-                            let base_texture = if let Some(cached) = manager.get(&zerocr_key) {
-                                cached
-                            } else {
-                                let texture = if let Some(tvg_texture) = render_tinyvg_texture(&tvg_bytes, bb.width, bb.height, &state.clip) {
-                                    tvg_texture
-                                } else {
-                                    warn!("Failed to load TinyVG image: {}", image.data);
-                                    Texture2D::from_rgba8(1, 1, &[0, 0, 0, 0])
-                                };
-                                manager.cache(zerocr_key.clone(), texture)
-                            }.clone();
-                            
-                            manager.get_or_create(key, || {
-                                let mut tvg_image: Image = base_texture.get_texture_data();
-                                let rounded_rect: Image = rounded_rectangle_texture(cr, &bb, &state.clip).get_texture_data();
-                                
-                                for i in 0..tvg_image.bytes.len()/4 {
-                                    let this_alpha = tvg_image.bytes[i * 4 + 3] as f32 / 255.0;
-                                    let mask_alpha = rounded_rect.bytes[i * 4 + 3] as f32 / 255.0;
-                                    tvg_image.bytes[i * 4 + 3] = (this_alpha * mask_alpha * 255.0) as u8;
-                                }
-                                Texture2D::from_image(&tvg_image)
-                            })
-                        };
-                        
-                        draw_texture_ex(
-                            texture,
-                            bb.x,
-                            bb.y,
-                            tint,
-                            DrawTextureParams {
-                                dest_size: Some(Vec2::new(bb.width, bb.height)),
-                                flip_y: true,
-                                ..Default::default()
-                            },
-                        );
+                if let ImageSource::Path(path) = image.data {
+                    if let Some(key) = tinyvg_key(path, bb, state) {
+                        if manager.get(&key).is_none() {
+                            let bytes = manager.source.load(path).await;
+                            manager.cache_tinyvg(key.clone(), path, bytes, bb, state);
+                        }
+                        let texture = manager.get(&key).unwrap();
+                        draw_texture_rounded(texture, bb, cr, tint, true, state);
+                        continue;
                     }
-                    continue;
                 }
 
-                if !is_tvg && cr.top_left == 0.0 && cr.top_right == 0.0 && cr.bottom_left == 0.0 && cr.bottom_right == 0.0 {
-                    let texture = manager.get_or_load(image.data).await;
-                    draw_texture_ex(
-                        texture,
-                        bb.x,
-                        bb.y,
-                        tint,
-                        DrawTextureParams {
-                            dest_size: Some(Vec2::new(bb.width, bb.height)),
-                            ..Default::default()
-                        },
-                    );
-                } else {
-                    let source_texture = manager.get_or_load(image.data).await.clone();
-                    
-                    let key = format!(
-                        "image:{}:{}:{}:{}:{}:{}:{}:{:?}",
-                        image.data,
-                        bb.width,
-                        bb.height,
-                        cr.top_left,
-                        cr.top_right,
-                        cr.bottom_left,
-                        cr.bottom_right,
-                        state.clip
-                    );
-
-                    let texture = manager.get_or_create(key, || {
-                        let mut resized_image: Image = resize(&source_texture, bb.height, bb.width, &state.clip).get_texture_data();
-                        let rounded_rect: Image = rounded_rectangle_texture(cr, &bb, &state.clip).get_texture_data();
-
-                        for i in 0..resized_image.bytes.len()/4 {
-                            let this_alpha = resized_image.bytes[i * 4 + 3] as f32 / 255.0;
-                            let mask_alpha = rounded_rect.bytes[i * 4 + 3] as f32 / 255.0;
-                            resized_image.bytes[i * 4 + 3] = (this_alpha * mask_alpha * 255.0) as u8;
-                        }
-
-                        Texture2D::from_image(&resized_image)
-                    });
-
-                    draw_texture_ex(
-                        texture,
-                        bb.x,
-                        bb.y,
-                        tint,
-                        DrawTextureParams {
-                            dest_size: Some(Vec2::new(bb.width, bb.height)),
-                            ..Default::default()
-                        },
-                    );
-                }
+                let texture = manager.get_or_load_image(image.data).await;
+                draw_texture_rounded(&texture, bb, cr, tint, false, state);
             }
-            RenderCommandConfig::Rectangle(config) => {
-                let bb = command.bounding_box;
-                let color = clay_to_macroquad_color(&config.color);
-                let cr = &config.corner_radii;
-
-                if cr.top_left == 0.0 && cr.top_right == 0.0 && cr.bottom_left == 0.0 && cr.bottom_right == 0.0 {
-                    draw_rectangle(
-                        bb.x,
-                        bb.y,
-                        bb.width,
-                        bb.height,
-                        color
-                    );
-                } else if color.a == 1.0 {
-                    // Edges
-                    // Top edge
-                    if cr.top_left > 0.0 || cr.top_right > 0.0 {
-                        draw_rectangle(
-                            bb.x + cr.top_left,
-                            bb.y,
-                            bb.width - cr.top_left - cr.top_right,
-                            bb.height - cr.bottom_left.max(cr.bottom_right),
-                            color
-                        );
-                    }
-                    // Left edge
-                    if cr.top_left > 0.0 || cr.bottom_left > 0.0 {
-                        draw_rectangle(
-                            bb.x,
-                            bb.y + cr.top_left,
-                            bb.width - cr.top_right.max(cr.bottom_right),
-                            bb.height - cr.top_left - cr.bottom_left,
-                            color
-                        );
-                    }
-                    // Bottom edge
-                    if cr.bottom_left > 0.0 || cr.bottom_right > 0.0 {
-                        draw_rectangle(
-                            bb.x + cr.bottom_left,
-                            bb.y + cr.top_left.max(cr.top_right),
-                            bb.width - cr.bottom_left - cr.bottom_right,
-                            bb.height - cr.top_left.max(cr.top_right),
-                            color
-                        );
-                    }
-                    // Right edge
-                    if cr.top_right > 0.0 || cr.bottom_right > 0.0 {
-                        draw_rectangle(
-                            bb.x + cr.top_left.max(cr.bottom_left),
-                            bb.y + cr.top_right,
-                            bb.width - cr.top_left.max(cr.bottom_left),
-                            bb.height - cr.top_right - cr.bottom_right,
-                            color
-                        );
-                    }
+            RenderCommandConfig::Custom(_) => {
+                renderer.state.set_scale(command.scale_factor);
+                handle_custom_command(&command);
+            }
+            _ => render_command(&mut renderer, &command),
+        }
+    }
+    renderer.end_frame();
+}
 
-                    // Corners
-                    // Top-left corner
-                    if cr.top_left > 0.0 {
-                        draw_good_circle(
-                            bb.x + cr.top_left,
-                            bb.y + cr.top_left,
-                            cr.top_left,
-                            color,
-                        );
-                    }
-                    // Top-right corner
-                    if cr.top_right > 0.0 {
-                        draw_good_circle(
-                            bb.x + bb.width - cr.top_right,
-                            bb.y + cr.top_right,
-                            cr.top_right,
-                            color,
-                        );
-                    }
-                    // Bottom-left corner
-                    if cr.bottom_left > 0.0 {
-                        draw_good_circle(
-                            bb.x + cr.bottom_left,
-                            bb.y + bb.height - cr.bottom_left,
-                            cr.bottom_left,
-                            color,
-                        );
-                    }
-                    // Bottom-right corner
-                    if cr.bottom_right > 0.0 {
-                        draw_good_circle(
-                            bb.x + bb.width - cr.bottom_right,
-                            bb.y + bb.height - cr.bottom_right,
-                            cr.bottom_right,
-                            color,
-                        );
-                    }
-                } else {
-                    let mut manager = TEXTURE_MANAGER.lock().unwrap();
-                    let key = format!(
-                        "rect:{}:{}:{}:{}:{}:{}:{:?}",
-                        bb.width,
-                        bb.height,
-                        cr.top_left,
-                        cr.top_right,
-                        cr.bottom_left,
-                        cr.bottom_right,
-                        state.clip
-                    );
+/// The fonts of a function of [`create_measure_text_function`], whose measurements are dropped with it.
+struct MeasuredFonts(Vec<Font>);
 
-                    let texture = manager.get_or_create(key, || {
-                        rounded_rectangle_texture(cr, &bb, &state.clip)
-                    });
-
-                    draw_texture_ex(
-                        texture,
-                        bb.x,
-                        bb.y,
-                        color,
-                        DrawTextureParams {
-                            dest_size: Some(Vec2::new(bb.width, bb.height)),
-                            flip_y: true,
-                            ..Default::default()
-                        },
-                    );
-                }
-            }
-            _ => render_common_command(&command, fonts, &handle_custom_command, &mut state),
+impl Drop for MeasuredFonts {
+    fn drop(&mut self) {
+        if let Ok(mut cache) = TEXT_CACHE.lock() {
+            cache.forget(&self.0);
         }
     }
-    TEXTURE_MANAGER.lock().unwrap().clean();
 }
 
+/// Returns a function measuring text with `fonts`, indexed by font id. Measurements are kept in the [`TEXT_CACHE`].
 pub fn create_measure_text_function(
     fonts: Vec<Font>,
 ) -> impl Fn(&str, &crate::TextConfig) -> crate::Dimensions + 'static {
+    let fonts = MeasuredFonts(fonts);
     move |text: &str, config: &crate::TextConfig| {
-        #[cfg(feature = "macroquad-text-styling")]
-        let cleaned_text = {
-            // Remove macroquad_text_styling tags, handling escapes
-            let mut result = String::new();
-            let mut in_style_def = false;
-            let mut escaped = false;
-            for c in text.chars() {
-                if escaped {
-                    result.push(c);
-                    escaped = false;
-                    continue;
-                }
-                match c {
-                    '\\' => {
-                        escaped = true;
-                    }
-                    '{' => {
-                        in_style_def = true;
+        let fonts = &fonts.0;
+        let key = (font_table(fonts), config.font_id, config.font_size, 1.0f32.to_bits(), cfg!(feature = "macroquad-text-styling"));
+        let measured = TEXT_CACHE.lock().unwrap().get_or_measure(text, key, || {
+            #[cfg(feature = "macroquad-text-styling")]
+            let cleaned_text = {
+                // Remove macroquad_text_styling tags, handling escapes
+                let mut result = String::new();
+                let mut in_style_def = false;
+                let mut escaped = false;
+                for c in text.chars() {
+                    if escaped {
+                        result.push(c);
+                        escaped = false;
+                        continue;
                     }
-                    '|' => {
-                        if in_style_def {
-                            in_style_def = false;
-                        } else {
-                            result.push(c);
+                    match c {
+                        '\\' => {
+                            escaped = true;
                         }
-                    }
-                    '}' => {
-                        // Nothing
-                    }
-                    _ => {
-                        if !in_style_def {
-                            result.push(c);
+                        '{' => {
+                            in_style_def = true;
+                        }
+                        '|' => {
+                            if in_style_def {
+                                in_style_def = false;
+                            } else {
+                                result.push(c);
+                            }
+                        }
+                        '}' => {
+                            // Nothing
+                        }
+                        _ => {
+                            if !in_style_def {
+                                result.push(c);
+                            }
                         }
                     }
                 }
-            }
-            if in_style_def {
-                warn!("Ended inside a style definition while cleaning text for measurement! Make sure to escape curly braces with \\. Here is what we tried to measure: {}", text);
-            }
-            result
-        };
-        #[cfg(not(feature = "macroquad-text-styling"))]
-        let cleaned_text = text.to_string();
-        let measured = macroquad::text::measure_text(
-            &cleaned_text,
-            Some(&fonts[config.font_id as usize]),
-            config.font_size,
-            1.0,
-        );
+                if in_style_def {
+                    warn!("Ended inside a style definition while cleaning text for measurement! Make sure to escape curly braces with \\. Here is what we tried to measure: {}", text);
+                }
+                result
+            };
+            #[cfg(not(feature = "macroquad-text-styling"))]
+            let cleaned_text = text.to_string();
+            macroquad::text::measure_text(
+                &cleaned_text,
+                Some(&fonts[config.font_id as usize]),
+                config.font_size,
+                1.0,
+            )
+        });
         let added_space = (text.chars().count().max(1) - 1) as f32 * config.letter_spacing as f32;
         crate::Dimensions::new(measured.width + added_space, measured.height)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_cache_drops_measurements_unused_for_too_long() {
+        let mut cache = TextCache::new();
+        cache.max_frames_not_used = 1;
+        let dimensions = |width| TextDimensions { width, height: 10.0, offset_y: 8.0 };
+        let key = (1, 0, 16, 1.0f32.to_bits(), false);
+
+        assert_eq!(cache.get_or_measure("hello", key, || dimensions(40.0)).width, 40.0);
+        // Cached, so measuring again isn't needed
+        assert_eq!(cache.get_or_measure("hello", key, || unreachable!()).width, 40.0);
+        // Another size of the same text is measured separately
+        let larger = (1, 0, 32, 1.0f32.to_bits(), false);
+        assert_eq!(cache.get_or_measure("hello", larger, || dimensions(80.0)).width, 80.0);
+
+        cache.clean();
+        cache.clean();
+        assert_eq!(cache.size(), 1);
+        cache.clean();
+        assert_eq!(cache.size(), 0);
+        assert_eq!(cache.get_or_measure("hello", key, || dimensions(41.0)).width, 41.0);
+    }
+    #[test]
+    fn text_cache_keeps_font_tables_apart_and_trims_itself() {
+        let mut cache = TextCache::new();
+        cache.max_texts = 4;
+        let dimensions = |width| TextDimensions { width, height: 10.0, offset_y: 8.0 };
+        let (first, second) = ((1, 0, 16, 1.0f32.to_bits(), false), (2, 0, 16, 1.0f32.to_bits(), false));
+
+        assert_eq!(cache.get_or_measure("hello", first, || dimensions(40.0)).width, 40.0);
+        // The same font id of another font table is measured separately
+        assert_eq!(cache.get_or_measure("hello", second, || dimensions(50.0)).width, 50.0);
+
+        for text in ["a", "b", "c"] {
+            cache.get_or_measure(text, first, || dimensions(10.0));
+        }
+        assert_eq!(cache.size(), 4);
+        // Full, so the texts used longest ago make room without a frame ending
+        cache.get_or_measure("d", first, || dimensions(10.0));
+        assert_eq!(cache.size(), 3);
+        assert!(!cache.texts.contains_key("hello"));
+    }
+    #[test]
+    fn nested_scissors_are_clipped_to_their_parents() {
+        let parent = (10, 10, 100, 50);
+        assert_eq!(intersect_clips(parent, (0, 20, 50, 100)), (10, 20, 40, 40));
+        assert_eq!(intersect_clips(parent, (20, 20, 10, 10)), (20, 20, 10, 10));
+        // Scrolled out of the parent
+        assert_eq!(intersect_clips(parent, (200, 0, 10, 10)), (200, 10, 0, 0));
+    }
+    #[cfg(feature = "macroquad-texture-manager")]
+    #[test]
+    fn paths_still_loading_are_deferred() {
+        use crate::renderers::macroquad_assets::{AssetFuture, MemorySource};
+
+        struct Loading;
+        impl AssetSource for Loading {
+            fn load<'a>(&'a self, _: &'a str) -> AssetFuture<'a> {
+                Box::pin(std::future::pending())
+            }
+        }
+
+        let mut manager = TextureManager::new();
+        let mut source = MemorySource::new();
+        source.insert("icon.png", &b"png"[..]);
+        manager.set_source(source);
+        assert_eq!(manager.load_now("icon.png"), Some(Ok(b"png".to_vec())));
+
+        manager.set_source(Loading);
+        assert_eq!(manager.load_now("icon.png"), None);
+        assert!(manager.deferred.contains("icon.png"));
+        // Not requested again
+        manager.set_source(|_: &str| -> Result<Vec<u8>, AssetError> { unreachable!() });
+        assert_eq!(manager.load_now("icon.png"), None);
+    }
+}
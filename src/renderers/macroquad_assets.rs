@@ -0,0 +1,232 @@
+//! Where the [`TextureManager`](super::macroquad::TextureManager) loads images from.
+//!
+//! Image elements drawn by the macroquad renderer with the `macroquad-texture-manager` feature
+//! have an [`ImageSource`] as image data. Paths are loaded from the [`AssetSource`] of the
+//! texture manager, files by default, and loads that fail are drawn as a placeholder and
+//! reported by [`TextureManager::take_errors`](super::macroquad::TextureManager::take_errors).
+
+use std::{borrow::Cow, collections::HashMap, fmt, future::Future, ops::Range, pin::Pin};
+
+use macroquad::texture::Texture2D;
+
+/// The image data of image elements drawn with the texture manager.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageSource {
+    /// A file loaded from the [`AssetSource`] of the texture manager. Files ending in `.tvg` are
+    /// TinyVG images, rasterized at the size they are drawn with the `macroquad-tinyvg` feature.
+    Path(Cow<'static, str>),
+    /// An encoded image, like a PNG, cached under `key`.
+    Bytes {
+        key: Cow<'static, str>,
+        bytes: Cow<'static, [u8]>,
+    },
+    /// A texture that was already loaded.
+    Texture(Texture2D),
+}
+
+impl From<&'static str> for ImageSource {
+    fn from(path: &'static str) -> Self {
+        Self::Path(path.into())
+    }
+}
+
+impl From<String> for ImageSource {
+    fn from(path: String) -> Self {
+        Self::Path(path.into())
+    }
+}
+
+impl From<Texture2D> for ImageSource {
+    fn from(texture: Texture2D) -> Self {
+        Self::Texture(texture)
+    }
+}
+
+/// Why an image couldn't be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetError {
+    /// The asset source has no file at the path.
+    NotFound(String),
+    /// The asset source failed to read the file at `path`.
+    Load { path: String, message: String },
+    /// The file at `path` isn't an image macroquad or TinyVG can decode.
+    Decode { path: String, message: String },
+    /// An archive couldn't be read.
+    InvalidArchive(String),
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(path) => write!(f, "no asset at {path}"),
+            Self::Load { path, message } => write!(f, "failed to load {path}: {message}"),
+            Self::Decode { path, message } => write!(f, "failed to decode {path}: {message}"),
+            Self::InvalidArchive(message) => write!(f, "invalid archive: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
+
+/// The future of [`AssetSource::load`].
+pub type AssetFuture<'a> = Pin<Box<dyn Future<Output = Result<Vec<u8>, AssetError>> + 'a>>;
+
+/// Loads the bytes of files by their path, set with
+/// [`TextureManager::set_source`](super::macroquad::TextureManager::set_source).
+///
+/// Closures from a path to the bytes are asset sources too, for custom synchronous loaders.
+pub trait AssetSource: Send {
+    fn load<'a>(&'a self, path: &'a str) -> AssetFuture<'a>;
+}
+
+impl<F> AssetSource for F
+where
+    F: Fn(&str) -> Result<Vec<u8>, AssetError> + Send,
+{
+    fn load<'a>(&'a self, path: &'a str) -> AssetFuture<'a> {
+        Box::pin(std::future::ready(self(path)))
+    }
+}
+
+/// Loads files with [`macroquad::file::load_file`], from the file system or over HTTP on the web.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileSource;
+
+impl AssetSource for FileSource {
+    fn load<'a>(&'a self, path: &'a str) -> AssetFuture<'a> {
+        Box::pin(async move {
+            macroquad::file::load_file(path)
+                .await
+                .map_err(|error| AssetError::Load {
+                    path: path.to_owned(),
+                    message: error.to_string(),
+                })
+        })
+    }
+}
+
+/// Files kept in memory, like embedded with `include_bytes!` or read from an archive.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySource {
+    files: HashMap<String, Vec<u8>>,
+}
+
+impl MemorySource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the regular files of an uncompressed tar archive, keyed by their path in it.
+    pub fn from_tar(archive: &[u8]) -> Result<Self, AssetError> {
+        let mut source = Self::new();
+        let mut offset = 0;
+        while let Some(header) = archive.get(offset..offset + 512) {
+            // The archive ends with empty blocks
+            if header.iter().all(|&byte| byte == 0) {
+                break;
+            }
+            let field = |range: Range<usize>| {
+                let field = &header[range];
+                let end = field
+                    .iter()
+                    .position(|&byte| byte == 0)
+                    .unwrap_or(field.len());
+                String::from_utf8_lossy(&field[..end]).into_owned()
+            };
+
+            let size = usize::from_str_radix(field(124..136).trim(), 8)
+                .map_err(|_| AssetError::InvalidArchive(format!("bad size at {offset}")))?;
+            let start = offset + 512;
+            let data = start
+                .checked_add(size)
+                .and_then(|end| archive.get(start..end))
+                .ok_or_else(|| {
+                    AssetError::InvalidArchive(format!("{} is truncated", field(0..100)))
+                })?;
+
+            // Directories, links and other entries are skipped
+            if matches!(header[156], b'0' | 0) {
+                let name = field(0..100);
+                let prefix = if &header[257..262] == b"ustar" {
+                    field(345..500)
+                } else {
+                    String::new()
+                };
+                let path = if prefix.is_empty() {
+                    name
+                } else {
+                    format!("{prefix}/{name}")
+                };
+                source.insert(path.trim_start_matches("./"), data);
+            }
+            // `data` is in the archive, so rounding its end up to a block can't overflow
+            offset = start + size.div_ceil(512) * 512;
+        }
+        Ok(source)
+    }
+
+    /// Adds the file `bytes` at `path`, replacing the file that was there.
+    pub fn insert(&mut self, path: impl Into<String>, bytes: impl Into<Vec<u8>>) -> &mut Self {
+        self.files.insert(path.into(), bytes.into());
+        self
+    }
+
+    pub fn get(&self, path: &str) -> Option<&[u8]> {
+        self.files.get(path).map(Vec::as_slice)
+    }
+}
+
+impl AssetSource for MemorySource {
+    fn load<'a>(&'a self, path: &'a str) -> AssetFuture<'a> {
+        let bytes = self
+            .get(path)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| AssetError::NotFound(path.to_owned()));
+        Box::pin(std::future::ready(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tar_entry(path: &str, contents: &[u8]) -> Vec<u8> {
+        let mut header = [0u8; 512];
+        header[..path.len()].copy_from_slice(path.as_bytes());
+        let size = format!("{:011o}", contents.len());
+        header[124..135].copy_from_slice(size.as_bytes());
+        header[156] = b'0';
+        header[257..262].copy_from_slice(b"ustar");
+
+        let mut entry = header.to_vec();
+        entry.extend_from_slice(contents);
+        entry.resize(512 + contents.len().div_ceil(512) * 512, 0);
+        entry
+    }
+
+    #[test]
+    fn reads_files_from_tar_archives() {
+        let mut archive = tar_entry("./icons/close.png", b"png");
+        archive.extend(tar_entry("avatar.tvg", &[7; 600]));
+        archive.extend([0; 1024]);
+
+        let source = MemorySource::from_tar(&archive).unwrap();
+        assert_eq!(source.get("icons/close.png"), Some(&b"png"[..]));
+        assert_eq!(source.get("avatar.tvg").map(<[u8]>::len), Some(600));
+        assert_eq!(source.get("missing.png"), None);
+
+        // The second file ends early
+        assert!(matches!(
+            MemorySource::from_tar(&archive[..1024 + 512 + 100]),
+            Err(AssetError::InvalidArchive(_))
+        ));
+
+        // A size far past the end of the archive
+        let mut huge = tar_entry("huge.png", b"");
+        huge[124..135].copy_from_slice(format!("{:011o}", usize::MAX >> 31).as_bytes());
+        assert!(matches!(
+            MemorySource::from_tar(&huge),
+            Err(AssetError::InvalidArchive(_))
+        ));
+    }
+}
@@ -1,3 +1,12 @@
+use crate::{
+    math::{BoundingBox, Dimensions},
+    render_commands::{Border, Custom, Image, Rectangle, RenderCommand, RenderCommandConfig, Text},
+    text::TextConfig,
+};
+
+#[cfg(feature = "alloc")]
+pub mod geometry;
+
 #[cfg(feature = "raylib-renderer")]
 pub mod raylib;
 #[cfg(feature = "raylib-renderer")]
@@ -8,11 +17,184 @@ pub mod skia;
 #[cfg(feature = "skia-renderer")]
 pub use skia::clay_skia_render;
 
+#[cfg(feature = "wgpu-renderer")]
+pub mod wgpu;
+#[cfg(feature = "wgpu-renderer")]
+pub use self::wgpu::WgpuRenderer;
+
 #[cfg(feature = "macroquad-renderer")]
 pub mod macroquad;
 #[cfg(feature = "macroquad-renderer")]
 pub use macroquad::clay_macroquad_render;
 #[cfg(feature = "macroquad-renderer")]
 pub use macroquad::create_measure_text_function;
+#[cfg(feature = "macroquad-texture-manager")]
+pub mod macroquad_assets;
 #[cfg(feature = "macroquad-text-styling")]
-pub mod macroquad_text_styling;
\ No newline at end of file
+pub mod macroquad_text_styling;
+
+/// A backend that draws render commands, fed by [`render`].
+///
+/// Bounding boxes, corner radii, border widths and font sizes are in layout units, and are drawn
+/// `scale_factor` pixels large, see [`Clay::set_scale_factor`](crate::Clay::set_scale_factor).
+pub trait Renderer<CustomElementData = ()> {
+    /// The image data of the image elements this backend draws.
+    type ImageElementData;
+
+    /// Called before the first command of a frame.
+    fn begin_frame(&mut self) {}
+    /// Called after the last command of a frame.
+    fn end_frame(&mut self) {}
+
+    fn rectangle(&mut self, bounds: BoundingBox, scale_factor: f32, rectangle: &Rectangle);
+    fn border(&mut self, bounds: BoundingBox, scale_factor: f32, border: &Border);
+    fn text(&mut self, bounds: BoundingBox, scale_factor: f32, text: &Text);
+    fn image(
+        &mut self,
+        bounds: BoundingBox,
+        scale_factor: f32,
+        image: &Image<'_, Self::ImageElementData>,
+    );
+    /// Clips the following commands to `bounds`, until the matching [`Renderer::pop_scissor`].
+    fn push_scissor(&mut self, bounds: BoundingBox, scale_factor: f32);
+    fn pop_scissor(&mut self);
+    fn custom(
+        &mut self,
+        bounds: BoundingBox,
+        scale_factor: f32,
+        custom: &Custom<'_, CustomElementData>,
+    );
+
+    /// Returns a function that measures text the way [`Renderer::text`] draws it, to pass to
+    /// [`Clay::set_measure_text_function`](crate::Clay::set_measure_text_function).
+    ///
+    /// Not available on `dyn Renderer`, so that the other hooks are.
+    fn measure_text_function(&self) -> impl Fn(&str, &TextConfig) -> Dimensions + 'static
+    where
+        Self: Sized;
+}
+
+/// Draws `command` with the hook of `renderer` for its type.
+pub fn render_command<R, CustomElementData, UserData>(
+    renderer: &mut R,
+    command: &RenderCommand<'_, R::ImageElementData, CustomElementData, UserData>,
+) where
+    R: Renderer<CustomElementData> + ?Sized,
+{
+    let (bounds, scale_factor) = (command.bounding_box, command.scale_factor);
+    match &command.config {
+        RenderCommandConfig::Rectangle(rectangle) => {
+            renderer.rectangle(bounds, scale_factor, rectangle)
+        }
+        RenderCommandConfig::Border(border) => renderer.border(bounds, scale_factor, border),
+        RenderCommandConfig::Text(text) => renderer.text(bounds, scale_factor, text),
+        RenderCommandConfig::Image(image) => renderer.image(bounds, scale_factor, image),
+        RenderCommandConfig::ScissorStart() => renderer.push_scissor(bounds, scale_factor),
+        RenderCommandConfig::ScissorEnd() => renderer.pop_scissor(),
+        RenderCommandConfig::Custom(custom) => renderer.custom(bounds, scale_factor, custom),
+        RenderCommandConfig::None() => {}
+    }
+}
+
+/// Draws the render commands of a frame with `renderer`.
+pub fn render<'a, R, CustomElementData: 'a, UserData: 'a>(
+    renderer: &mut R,
+    commands: impl Iterator<Item = RenderCommand<'a, R::ImageElementData, CustomElementData, UserData>>,
+) where
+    R: Renderer<CustomElementData> + ?Sized,
+    R::ImageElementData: 'a,
+{
+    renderer.begin_frame();
+    for command in commands {
+        render_command(renderer, &command);
+    }
+    renderer.end_frame();
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    #[derive(Default)]
+    struct Recorder(Vec<(&'static str, f32)>);
+
+    impl Renderer for Recorder {
+        type ImageElementData = ();
+
+        fn begin_frame(&mut self) {
+            self.0.push(("begin", 0.0));
+        }
+        fn end_frame(&mut self) {
+            self.0.push(("end", 0.0));
+        }
+        fn rectangle(&mut self, bounds: BoundingBox, scale_factor: f32, _: &Rectangle) {
+            self.0.push(("rectangle", bounds.width * scale_factor));
+        }
+        fn border(&mut self, _: BoundingBox, _: f32, _: &Border) {
+            self.0.push(("border", 0.0));
+        }
+        fn text(&mut self, _: BoundingBox, scale_factor: f32, text: &Text) {
+            self.0.push(("text", text.font_size as f32 * scale_factor));
+        }
+        fn image(&mut self, _: BoundingBox, _: f32, _: &Image<'_, ()>) {
+            self.0.push(("image", 0.0));
+        }
+        fn push_scissor(&mut self, _: BoundingBox, _: f32) {
+            self.0.push(("push_scissor", 0.0));
+        }
+        fn pop_scissor(&mut self) {
+            self.0.push(("pop_scissor", 0.0));
+        }
+        fn custom(&mut self, _: BoundingBox, _: f32, _: &Custom<'_, ()>) {
+            self.0.push(("custom", 0.0));
+        }
+        fn measure_text_function(&self) -> impl Fn(&str, &TextConfig) -> Dimensions + 'static {
+            |text, _| Dimensions::new(text.len() as f32, 1.0)
+        }
+    }
+
+    #[test]
+    fn dispatches_commands_between_frame_hooks() {
+        let command = |config| RenderCommand::<(), (), ()> {
+            bounding_box: BoundingBox::new(0.0, 0.0, 10.0, 10.0),
+            config,
+            id: 0,
+            z_index: 0,
+            user_data: None,
+            scale_factor: 2.0,
+        };
+        let commands = [
+            command(RenderCommandConfig::ScissorStart()),
+            command(RenderCommandConfig::Rectangle(Rectangle {
+                color: Color::rgb(0., 0., 0.),
+                corner_radii: Default::default(),
+            })),
+            command(RenderCommandConfig::Text(Text {
+                text: "hi",
+                color: Color::rgb(0., 0., 0.),
+                font_id: 0,
+                font_size: 16,
+                letter_spacing: 0,
+                line_height: 20,
+            })),
+            command(RenderCommandConfig::ScissorEnd()),
+            command(RenderCommandConfig::None()),
+        ];
+
+        let mut recorder = Recorder::default();
+        let renderer: &mut dyn Renderer<ImageElementData = ()> = &mut recorder;
+        render(renderer, commands.into_iter());
+        assert_eq!(
+            recorder.0,
+            [
+                ("begin", 0.0),
+                ("push_scissor", 0.0),
+                ("rectangle", 20.0),
+                ("text", 32.0),
+                ("pop_scissor", 0.0),
+                ("end", 0.0),
+            ]
+        );
+    }
+}
@@ -1,8 +1,16 @@
-use crate::render_commands::{RenderCommand, RenderCommandConfig};
+use super::{render, Renderer};
+use crate::math::{BoundingBox, Dimensions};
+use crate::render_commands::{Border, CornerRadii, Custom, Image, Rectangle, RenderCommand, Text};
+use crate::text::TextConfig;
 use raylib::{
-    ffi::{BeginScissorMode, EndScissorMode},
+    ffi::{self, BeginScissorMode, EndScissorMode},
     prelude::*,
 };
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+/// Length in pixels of the segments curves are approximated with
+const PIXELS_PER_SEGMENT: f32 = 2.0;
 
 macro_rules! clay_to_raylib_color {
     ($color:expr) => {
@@ -26,205 +34,289 @@ macro_rules! clay_to_raylib_rect {
     };
 }
 
+fn scale_corner_radii(radii: &CornerRadii, scale: f32) -> CornerRadii {
+    CornerRadii {
+        top_left: radii.top_left * scale,
+        top_right: radii.top_right * scale,
+        bottom_left: radii.bottom_left * scale,
+        bottom_right: radii.bottom_right * scale,
+    }
+}
+
 #[doc = "This is a direct* port of Clay's raylib renderer. See [the C implementation](https://github.com/nicbarker/clay/blob/main/renderers/raylib/clay_renderer_raylib.c) for more info."]
-pub fn clay_raylib_render<'rl, 'a, CustomElementData: 'a>(
+///
+/// Text is drawn with `fonts[font_id]`, or raylib's default font if there is no such font.
+pub fn clay_raylib_render<'rl, 'a, CustomElementData: 'a, UserData: 'a>(
     d: &mut RaylibDrawHandle<'rl>,
-    render_commands: impl Iterator<Item = RenderCommand<'a, Texture2D, CustomElementData>>,
-    mut handle_custom_element: impl FnMut(&CustomElementData, &mut RaylibDrawHandle<'rl>),
+    render_commands: impl Iterator<Item = RenderCommand<'a, Texture2D, CustomElementData, UserData>>,
+    handle_custom_element: impl FnMut(&CustomElementData, &mut RaylibDrawHandle<'rl>),
+    fonts: &[Font],
 ) {
-    for command in render_commands {
-        match command.config {
-            RenderCommandConfig::Text(text) => {
-                let text_data = text.text;
-                d.draw_text(
-                    text_data,
-                    command.bounding_box.x as i32,
-                    command.bounding_box.y as i32,
-                    text.font_size.into(),
-                    clay_to_raylib_color!(text.color),
-                );
-            }
+    render(
+        &mut RaylibRenderer::new(d, fonts, handle_custom_element),
+        render_commands,
+    );
+}
 
-            RenderCommandConfig::Image(image) => {
-                let texture = image.data;
-
-                d.draw_texture_ex(
-                    texture,
-                    Vector2::new(command.bounding_box.x, command.bounding_box.y),
-                    0.,
-                    command.bounding_box.width / texture.width as f32,
-                    // TODO: backgrond color isnt in raylib bindings?
-                    clay_to_raylib_color!(Color::WHITE),
-                );
-            }
+/// A [`Renderer`] drawing with a raylib draw handle, with the fonts indexed by the font ids.
+///
+/// Custom elements are drawn by `handle_custom_element`.
+pub struct RaylibRenderer<'d, 'rl, F> {
+    pub d: &'d mut RaylibDrawHandle<'rl>,
+    pub fonts: &'d [Font],
+    pub handle_custom_element: F,
+}
+
+impl<'d, 'rl, F> RaylibRenderer<'d, 'rl, F> {
+    pub fn new(
+        d: &'d mut RaylibDrawHandle<'rl>,
+        fonts: &'d [Font],
+        handle_custom_element: F,
+    ) -> Self {
+        Self {
+            d,
+            fonts,
+            handle_custom_element,
+        }
+    }
+}
+
+/// Returns the area of a border as a closed triangle strip, alternating between its outer and
+/// inner edge clockwise from the top left corner. The inner corners are shrunk by the widths of
+/// the sides meeting there, see [`Border::inner_corner_radii`].
+fn border_strip(bb: BoundingBox, scale: f32, border: &Border) -> Vec<Vector2> {
+    let radii = scale_corner_radii(&border.corner_radii, scale);
+    let inner = border
+        .inner_corner_radii()
+        .map(|(x, y)| (x * scale, y * scale));
+    let width = |width: u16| width as f32 * scale;
+    let (left, right) = (width(border.width.left), width(border.width.right));
+    let (top, bottom) = (width(border.width.top), width(border.width.bottom));
+    let (x1, y1, x2, y2) = (bb.x, bb.y, bb.x + bb.width, bb.y + bb.height);
 
-            // safety: raylib's BeginScissorMode is safe to call with any values.
-            // we need to use this here because the regular begin_scissor_mode
-            // ends the scissor mode on drop.
-            RenderCommandConfig::ScissorStart() => unsafe {
-                BeginScissorMode(
-                    command.bounding_box.x as i32,
-                    command.bounding_box.y as i32,
-                    command.bounding_box.width as i32,
-                    command.bounding_box.height as i32,
-                );
-            },
-
-            RenderCommandConfig::ScissorEnd() => unsafe {
-                EndScissorMode();
-            },
-
-            RenderCommandConfig::Rectangle(rect) => {
-                if rect.corner_radii.top_left > 0. {
-                    let radius = (rect.corner_radii.top_left * 2.)
-                        / if command.bounding_box.width > command.bounding_box.height {
-                            command.bounding_box.height
-                        } else {
-                            command.bounding_box.width
-                        };
-
-                    d.draw_rectangle_rounded(
-                        clay_to_raylib_rect!(command.bounding_box),
-                        radius,
-                        8,
-                        clay_to_raylib_color!(rect.color),
-                    );
+    // The corner, its outer radius, inner radii, the side widths meeting there, the direction
+    // it points in and the angle its arc starts at
+    #[rustfmt::skip]
+    let corners = [
+        ((x1, y1), radii.top_left, inner[0], (left, top), (-1., -1.), PI),
+        ((x2, y1), radii.top_right, inner[1], (right, top), (1., -1.), PI * 1.5),
+        ((x2, y2), radii.bottom_right, inner[3], (right, bottom), (1., 1.), 0.),
+        ((x1, y2), radii.bottom_left, inner[2], (left, bottom), (-1., 1.), PI * 0.5),
+    ];
+
+    let mut points = Vec::new();
+    for ((x, y), radius, (inner_x, inner_y), (width_x, width_y), (dx, dy), start) in corners {
+        let outer_center = Vector2::new(x - dx * radius, y - dy * radius);
+        let inner_center = Vector2::new(x - dx * (width_x + inner_x), y - dy * (width_y + inner_y));
+        let segments = if radius > 0. {
+            (radius * PI / 2. / PIXELS_PER_SEGMENT)
+                .ceil()
+                .clamp(2., 64.) as usize
+        } else {
+            0
+        };
+        for segment in 0..=segments {
+            let angle = start + PI / 2. * segment as f32 / segments.max(1) as f32;
+            let (sin, cos) = angle.sin_cos();
+            points.push(Vector2::new(
+                outer_center.x + radius * cos,
+                outer_center.y + radius * sin,
+            ));
+            points.push(Vector2::new(
+                inner_center.x + inner_x * cos,
+                inner_center.y + inner_y * sin,
+            ));
+        }
+    }
+    points.extend_from_within(..2);
+    points
+}
+
+/// Converts layout units to pixels.
+fn scale_bounding_box(bb: BoundingBox, scale: f32) -> BoundingBox {
+    BoundingBox::new(
+        bb.x * scale,
+        bb.y * scale,
+        bb.width * scale,
+        bb.height * scale,
+    )
+}
+
+impl<'rl, CustomElementData, F> Renderer<CustomElementData> for RaylibRenderer<'_, 'rl, F>
+where
+    F: FnMut(&CustomElementData, &mut RaylibDrawHandle<'rl>),
+{
+    type ImageElementData = Texture2D;
+
+    fn rectangle(&mut self, bb: BoundingBox, scale: f32, rect: &Rectangle) {
+        let (d, bb) = (&mut *self.d, scale_bounding_box(bb, scale));
+        if rect.corner_radii.top_left > 0. {
+            let radius = (rect.corner_radii.top_left * scale * 2.)
+                / if bb.width > bb.height {
+                    bb.height
                 } else {
-                    d.draw_rectangle(
-                        command.bounding_box.x as i32,
-                        command.bounding_box.y as i32,
-                        command.bounding_box.width as i32,
-                        command.bounding_box.height as i32,
-                        clay_to_raylib_color!(rect.color),
-                    );
-                }
+                    bb.width
+                };
+
+            d.draw_rectangle_rounded(
+                clay_to_raylib_rect!(bb),
+                radius,
+                8,
+                clay_to_raylib_color!(rect.color),
+            );
+        } else {
+            d.draw_rectangle(
+                bb.x as i32,
+                bb.y as i32,
+                bb.width as i32,
+                bb.height as i32,
+                clay_to_raylib_color!(rect.color),
+            );
+        }
+    }
+
+    /// Raylib doesn't anti-alias the border itself, enable MSAA with `FLAG_MSAA_4X_HINT` for
+    /// smooth corners.
+    fn border(&mut self, bb: BoundingBox, scale: f32, border: &Border) {
+        let points = border_strip(scale_bounding_box(bb, scale), scale, border);
+        self.d
+            .draw_triangle_strip(&points, clay_to_raylib_color!(border.color));
+    }
+
+    fn text(&mut self, bb: BoundingBox, scale: f32, text: &Text) {
+        let bb = scale_bounding_box(bb, scale);
+        let font_size = text.font_size as f32 * scale;
+        // Glyphs are centered vertically in the line height
+        let position = Vector2::new(bb.x, bb.y + (bb.height - font_size) / 2.);
+        let spacing = text.letter_spacing as f32 * scale;
+        let color = clay_to_raylib_color!(text.color);
+        match self.fonts.get(text.font_id as usize) {
+            Some(font) => self
+                .d
+                .draw_text_ex(font, text.text, position, font_size, spacing, color),
+            None => {
+                let font = self.d.get_font_default();
+                self.d
+                    .draw_text_ex(font, text.text, position, font_size, spacing, color)
             }
+        }
+    }
+
+    fn image(&mut self, bb: BoundingBox, scale: f32, image: &Image<Texture2D>) {
+        let bb = scale_bounding_box(bb, scale);
+        let texture = image.data;
 
-            RenderCommandConfig::Border(border) => {
-                if border.width.left > 0 {
-                    d.draw_rectangle(
-                        command.bounding_box.x as i32,
-                        (command.bounding_box.y + border.corner_radii.top_left) as i32,
-                        border.width.left as i32,
-                        (command.bounding_box.height
-                            - border.corner_radii.top_left
-                            - border.corner_radii.bottom_left) as i32,
-                        clay_to_raylib_color!(border.color),
-                    );
-                }
-
-                if border.width.right > 0 {
-                    d.draw_rectangle(
-                        (command.bounding_box.x + command.bounding_box.width
-                            - border.width.right as f32) as i32,
-                        (command.bounding_box.y + border.corner_radii.top_right) as i32,
-                        border.width.right as i32,
-                        (command.bounding_box.height
-                            - border.corner_radii.top_right
-                            - border.corner_radii.bottom_right) as i32,
-                        clay_to_raylib_color!(border.color),
-                    );
-                }
-
-                if border.width.top > 0 {
-                    d.draw_rectangle(
-                        (command.bounding_box.x + border.corner_radii.top_left) as i32,
-                        command.bounding_box.y as i32,
-                        (command.bounding_box.width
-                            - border.corner_radii.top_left
-                            - border.corner_radii.top_right) as i32,
-                        border.width.top as i32,
-                        clay_to_raylib_color!(border.color),
-                    );
-                }
-
-                if border.width.bottom > 0 {
-                    d.draw_rectangle(
-                        (command.bounding_box.x + border.corner_radii.bottom_left) as i32,
-                        (command.bounding_box.y + command.bounding_box.height
-                            - border.width.bottom as f32) as i32,
-                        (command.bounding_box.width
-                            - border.corner_radii.bottom_left
-                            - border.corner_radii.bottom_right) as i32,
-                        border.width.bottom as i32,
-                        clay_to_raylib_color!(border.color),
-                    )
-                }
-
-                if border.corner_radii.top_left > 0. {
-                    let vec = Vector2::new(
-                        (command.bounding_box.x + border.corner_radii.top_left) as f32,
-                        (command.bounding_box.y + border.corner_radii.top_left) as f32,
-                    );
-
-                    d.draw_ring(
-                        vec,
-                        border.corner_radii.top_left - border.width.top as f32,
-                        border.corner_radii.top_left,
-                        180.,
-                        270.,
-                        10,
-                        clay_to_raylib_color!(border.color),
-                    );
-                }
-
-                if border.corner_radii.top_right > 0. {
-                    let vec = Vector2::new(
-                        (command.bounding_box.x + command.bounding_box.width
-                            - border.corner_radii.top_right) as f32,
-                        (command.bounding_box.y + border.corner_radii.top_right) as f32,
-                    );
-
-                    d.draw_ring(
-                        vec,
-                        border.corner_radii.top_right - border.width.top as f32,
-                        border.corner_radii.top_right,
-                        270.,
-                        360.,
-                        10,
-                        clay_to_raylib_color!(border.color),
-                    );
-                }
-
-                if border.corner_radii.bottom_left > 0. {
-                    let vec = Vector2::new(
-                        (command.bounding_box.x + border.corner_radii.bottom_left) as f32,
-                        (command.bounding_box.y + command.bounding_box.height
-                            - border.corner_radii.bottom_left) as f32,
-                    );
-
-                    d.draw_ring(
-                        vec,
-                        border.corner_radii.bottom_left - border.width.bottom as f32,
-                        border.corner_radii.bottom_left,
-                        90.,
-                        180.,
-                        10,
-                        clay_to_raylib_color!(border.color),
-                    );
-                }
-
-                if border.corner_radii.bottom_right > 0. {
-                    let vec = Vector2::new(
-                        (command.bounding_box.x + command.bounding_box.width
-                            - border.corner_radii.bottom_right) as f32,
-                        (command.bounding_box.y + command.bounding_box.height
-                            - border.corner_radii.bottom_right) as f32,
-                    );
-
-                    d.draw_ring(
-                        vec,
-                        border.corner_radii.bottom_right - border.width.bottom as f32,
-                        border.corner_radii.bottom_right,
-                        0.,
-                        90.,
-                        10,
-                        clay_to_raylib_color!(border.color),
-                    );
-                }
+        self.d.draw_texture_ex(
+            texture,
+            Vector2::new(bb.x, bb.y),
+            0.,
+            bb.width / texture.width as f32,
+            // TODO: backgrond color isnt in raylib bindings?
+            clay_to_raylib_color!(Color::WHITE),
+        );
+    }
+
+    // safety: raylib's BeginScissorMode is safe to call with any values.
+    // we need to use this here because the regular begin_scissor_mode
+    // ends the scissor mode on drop.
+    fn push_scissor(&mut self, bb: BoundingBox, scale: f32) {
+        let bb = scale_bounding_box(bb, scale);
+        unsafe {
+            BeginScissorMode(bb.x as i32, bb.y as i32, bb.width as i32, bb.height as i32);
+        }
+    }
+
+    fn pop_scissor(&mut self) {
+        unsafe {
+            EndScissorMode();
+        }
+    }
+
+    fn custom(&mut self, _: BoundingBox, _: f32, custom: &Custom<CustomElementData>) {
+        (self.handle_custom_element)(custom.data, &mut *self.d)
+    }
+
+    fn measure_text_function(&self) -> impl Fn(&str, &TextConfig) -> Dimensions + 'static {
+        create_measure_text_function(self.fonts)
+    }
+}
+
+/// Returns a function measuring text the way [`clay_raylib_render`] draws it with `fonts`, and
+/// with raylib's default font for other font ids. Create it once the window is open.
+///
+/// The glyph metrics are copied from the fonts, so they don't have to outlive the function.
+pub fn create_measure_text_function(
+    fonts: &[Font],
+) -> impl Fn(&str, &TextConfig) -> Dimensions + 'static {
+    let fonts: Vec<FontMetrics> = fonts
+        .iter()
+        .map(|font| FontMetrics::new(font.as_ref()))
+        .collect();
+    // safety: the default font is loaded with the window
+    let default_font = FontMetrics::new(&unsafe { ffi::GetFontDefault() });
+    move |text, text_config| {
+        fonts
+            .get(text_config.font_id as usize)
+            .unwrap_or(&default_font)
+            .measure(text, text_config)
+    }
+}
+
+/// The advances of the glyphs of a font, to measure text like raylib's `MeasureTextEx`
+struct FontMetrics {
+    base_size: f32,
+    advances: HashMap<char, f32>,
+    /// Advance of the glyph drawn for characters the font doesn't have
+    fallback: f32,
+}
+
+impl FontMetrics {
+    fn new(font: &ffi::Font) -> Self {
+        let count = font.glyphCount.max(0) as usize;
+        let (glyphs, recs) = if count == 0 || font.glyphs.is_null() || font.recs.is_null() {
+            (&[][..], &[][..])
+        } else {
+            // safety: a loaded font has `glyphCount` glyphs and glyph rectangles
+            unsafe {
+                (
+                    std::slice::from_raw_parts(font.glyphs, count),
+                    std::slice::from_raw_parts(font.recs, count),
+                )
             }
-            RenderCommandConfig::Custom(custom) => handle_custom_element(custom.data, &mut *d),
-            RenderCommandConfig::None() => {}
+        };
+        let advance = |(glyph, rec): (&ffi::GlyphInfo, &ffi::Rectangle)| {
+            if glyph.advanceX != 0 {
+                glyph.advanceX as f32
+            } else {
+                rec.width + glyph.offsetX as f32
+            }
+        };
+        let advances: HashMap<char, f32> = glyphs
+            .iter()
+            .zip(recs)
+            .filter_map(|glyph| Some((char::from_u32(glyph.0.value as u32)?, advance(glyph))))
+            .collect();
+        let fallback = advances
+            .get(&'?')
+            .copied()
+            .or_else(|| glyphs.iter().zip(recs).next().map(advance))
+            .unwrap_or(0.);
+        Self {
+            base_size: font.baseSize.max(1) as f32,
+            advances,
+            fallback,
         }
     }
+
+    fn measure(&self, text: &str, text_config: &TextConfig) -> Dimensions {
+        let font_size = text_config.font_size as f32;
+        let (width, count) = text.chars().fold((0., 0), |(width, count), c| {
+            (
+                width + self.advances.get(&c).copied().unwrap_or(self.fallback),
+                count + 1,
+            )
+        });
+        let spacing = text_config.letter_spacing as f32 * (count as f32 - 1.).max(0.);
+        Dimensions::new(width * font_size / self.base_size + spacing, font_size)
+    }
 }
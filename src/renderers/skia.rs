@@ -1,9 +1,17 @@
+use super::{render, render_command, Renderer};
 use crate::math::{BoundingBox, Dimensions};
-use crate::render_commands::{Custom, RenderCommand, RenderCommandConfig};
+use crate::render_commands::{
+    Border, CornerRadii, Custom, Image as ImageCommand, Rectangle, RenderCommand,
+    RenderCommandConfig, Text,
+};
 use crate::text::TextConfig;
 use crate::{ClayLayoutScope, Color as ClayColor};
+use skia_safe::textlayout::{
+    FontCollection, Paragraph, ParagraphBuilder, ParagraphStyle, TextStyle, TypefaceFontProvider,
+};
 use skia_safe::{
-    Canvas, ClipOp, Color, Font, Image, Paint, Point, RRect, Rect, SamplingOptions, Typeface,
+    pdf, surfaces, Canvas, ClipOp, Color, Document, EncodedImageFormat, FontMgr, Image, Paint,
+    Point, RRect, Rect, SamplingOptions, Typeface,
 };
 
 pub fn clay_to_skia_color(color: ClayColor) -> Color {
@@ -19,268 +27,226 @@ fn clay_to_skia_rect(rect: BoundingBox) -> Rect {
     Rect::from_xywh(rect.x, rect.y, rect.width, rect.height)
 }
 /// This is a direct* port of Clay's raylib renderer using skia_safe as the drawing API.
-pub fn clay_skia_render<'a, CustomElementData: 'a>(
+pub fn clay_skia_render<'a, CustomElementData: 'a, UserData: 'a>(
     canvas: &Canvas,
-    render_commands: impl Iterator<Item = RenderCommand<'a, Image, CustomElementData>>,
+    render_commands: impl Iterator<Item = RenderCommand<'a, Image, CustomElementData, UserData>>,
     mut render_custom_element: impl FnMut(
-        &RenderCommand<'a, Image, CustomElementData>,
+        &RenderCommand<'a, Image, CustomElementData, UserData>,
         &Custom<'a, CustomElementData>,
         &Canvas,
     ),
-    fonts: &[&Typeface],
+    fonts: &SkiaFonts,
 ) {
+    let mut renderer = SkiaRenderer::new(canvas, fonts, skip_custom_element::<CustomElementData>);
     for command in render_commands {
         match command.config {
-            RenderCommandConfig::Text(text) => {
-                let text_data = text.text;
-                let mut paint = Paint::default();
-                paint.set_color(clay_to_skia_color(text.color));
-                let font = Font::new(fonts[text.font_id as usize].clone(), text.font_size as f32);
-                let pos = Point::new(
-                    command.bounding_box.x,
-                    command.bounding_box.y + text.font_size as f32,
-                );
-                canvas.draw_str(&text_data, pos, &font, &paint);
+            RenderCommandConfig::Custom(ref custom) => {
+                let scale = command.scale_factor;
+                canvas.save();
+                canvas.scale((scale, scale));
+                render_custom_element(&command, custom, canvas);
+                canvas.restore();
             }
+            _ => render_command(&mut renderer, &command),
+        }
+    }
+}
 
-            RenderCommandConfig::Image(image) => {
-                let skia_image = image.data;
-                let mut paint = Paint::default();
-                paint.set_color(Color::WHITE);
-                paint.set_anti_alias(true);
-
-                let bounds = clay_to_skia_rect(command.bounding_box);
-                let has_border_radius = image.corner_radii.top_left > 0.
-                    || image.corner_radii.top_right > 0.
-                    || image.corner_radii.bottom_left > 0.
-                    || image.corner_radii.bottom_right > 0.;
-                if has_border_radius {
-                    canvas.save();
-                    let rrect = RRect::new_rect_radii(
-                        bounds,
-                        &[
-                            Point::new(image.corner_radii.top_left, image.corner_radii.top_left),
-                            Point::new(image.corner_radii.top_right, image.corner_radii.top_right),
-                            Point::new(
-                                image.corner_radii.bottom_left,
-                                image.corner_radii.bottom_left,
-                            ),
-                            Point::new(
-                                image.corner_radii.bottom_right,
-                                image.corner_radii.bottom_right,
-                            ),
-                        ],
-                    );
-                    canvas.clip_rrect(rrect, ClipOp::Intersect, true);
-                }
-
-                canvas.draw_image_rect_with_sampling_options(
-                    skia_image,
-                    None,
-                    bounds,
-                    SamplingOptions::new(
-                        skia_safe::FilterMode::Linear,
-                        skia_safe::MipmapMode::Linear,
-                    ),
-                    &paint,
-                );
+fn skip_custom_element<CustomElementData>(
+    _: BoundingBox,
+    _: &Custom<CustomElementData>,
+    _: &Canvas,
+) {
+}
+
+/// A [`Renderer`] drawing onto a skia canvas, with the typefaces indexed by the font ids.
+///
+/// Custom elements are drawn by `render_custom_element`, on the canvas scaled to layout units.
+pub struct SkiaRenderer<'a, F> {
+    pub canvas: &'a Canvas,
+    pub fonts: &'a SkiaFonts,
+    pub render_custom_element: F,
+}
+
+impl<'a, F> SkiaRenderer<'a, F> {
+    pub fn new(canvas: &'a Canvas, fonts: &'a SkiaFonts, render_custom_element: F) -> Self {
+        Self {
+            canvas,
+            fonts,
+            render_custom_element,
+        }
+    }
+
+    /// Draws in layout units, scaled to pixels by the canvas.
+    fn scaled(&self, scale_factor: f32, draw: impl FnOnce(&Canvas)) {
+        self.canvas.save();
+        self.canvas.scale((scale_factor, scale_factor));
+        draw(self.canvas);
+        self.canvas.restore();
+    }
+}
+
+/// Skia orders the radii clockwise from the top left corner.
+fn skia_rrect(bounds: Rect, corner_radii: &CornerRadii) -> RRect {
+    RRect::new_rect_radii(
+        bounds,
+        &[
+            Point::new(corner_radii.top_left, corner_radii.top_left),
+            Point::new(corner_radii.top_right, corner_radii.top_right),
+            Point::new(corner_radii.bottom_right, corner_radii.bottom_right),
+            Point::new(corner_radii.bottom_left, corner_radii.bottom_left),
+        ],
+    )
+}
+
+fn has_corner_radii(corner_radii: &CornerRadii) -> bool {
+    corner_radii.top_left > 0.
+        || corner_radii.top_right > 0.
+        || corner_radii.bottom_left > 0.
+        || corner_radii.bottom_right > 0.
+}
 
-                // Restore canvas state if we applied a clip
-                if has_border_radius {
-                    canvas.restore();
-                }
+impl<CustomElementData, F> Renderer<CustomElementData> for SkiaRenderer<'_, F>
+where
+    F: FnMut(BoundingBox, &Custom<CustomElementData>, &Canvas),
+{
+    type ImageElementData = Image;
+
+    fn rectangle(&mut self, bounds: BoundingBox, scale_factor: f32, rect: &Rectangle) {
+        self.scaled(scale_factor, |canvas| {
+            let paint = {
+                let mut p = Paint::default();
+                p.set_color(clay_to_skia_color(rect.color));
+                p.set_anti_alias(true);
+                p.set_style(skia_safe::PaintStyle::Fill);
+                p
+            };
+            let bounds = clay_to_skia_rect(bounds);
+            if has_corner_radii(&rect.corner_radii) {
+                canvas.draw_rrect(skia_rrect(bounds, &rect.corner_radii), &paint);
+            } else {
+                canvas.draw_rect(bounds, &paint);
             }
+        });
+    }
+
+    fn border(&mut self, bb: BoundingBox, scale_factor: f32, border: &Border) {
+        self.scaled(scale_factor, |canvas| {
+            // The border is the area between the outer and the inner rounded rectangle, so each
+            // side keeps its own width around the corners.
+            let mut paint = Paint::default();
+            paint.set_color(clay_to_skia_color(border.color));
+            paint.set_anti_alias(true);
+
+            let outer_bounds = clay_to_skia_rect(bb);
+            let outer = skia_rrect(outer_bounds, &border.corner_radii);
+            let width = &border.width;
+            let inner_bounds = Rect::new(
+                outer_bounds.left + width.left as f32,
+                outer_bounds.top + width.top as f32,
+                outer_bounds.right - width.right as f32,
+                outer_bounds.bottom - width.bottom as f32,
+            );
+            if inner_bounds.is_empty() {
+                canvas.draw_rrect(outer, &paint);
+                return;
+            }
+
+            let [top_left, top_right, bottom_left, bottom_right] = border.inner_corner_radii();
+            let inner = RRect::new_rect_radii(
+                inner_bounds,
+                &[
+                    Point::new(top_left.0, top_left.1),
+                    Point::new(top_right.0, top_right.1),
+                    Point::new(bottom_right.0, bottom_right.1),
+                    Point::new(bottom_left.0, bottom_left.1),
+                ],
+            );
+            canvas.draw_drrect(outer, inner, &paint);
+        });
+    }
+
+    fn text(&mut self, bounds: BoundingBox, scale_factor: f32, text: &Text) {
+        let paragraph = self.fonts.paragraph(
+            text.text,
+            text.font_id,
+            text.font_size,
+            text.letter_spacing,
+            text.line_height,
+            text.color,
+        );
+        self.scaled(scale_factor, |canvas| {
+            // The line is centered in the bounds, which are as high as the measured line or the
+            // line height
+            let y = bounds.y + (bounds.height - paragraph.height()) / 2.0;
+            paragraph.paint(canvas, Point::new(bounds.x, y));
+        });
+    }
+
+    fn image(&mut self, bounds: BoundingBox, scale_factor: f32, image: &ImageCommand<Image>) {
+        self.scaled(scale_factor, |canvas| {
+            let mut paint = Paint::default();
+            paint.set_color(Color::WHITE);
+            paint.set_anti_alias(true);
 
-            RenderCommandConfig::ScissorStart() => {
-                // Save the current state then clip to the bounding box.
+            let bounds = clay_to_skia_rect(bounds);
+            let has_border_radius = has_corner_radii(&image.corner_radii);
+            if has_border_radius {
                 canvas.save();
-                let clip_rect = clay_to_skia_rect(command.bounding_box);
-                canvas.clip_rect(clip_rect, ClipOp::Intersect, false);
+                canvas.clip_rrect(
+                    skia_rrect(bounds, &image.corner_radii),
+                    ClipOp::Intersect,
+                    true,
+                );
             }
 
-            RenderCommandConfig::ScissorEnd() => {
-                // Restore the previous state
+            canvas.draw_image_rect_with_sampling_options(
+                image.data,
+                None,
+                bounds,
+                SamplingOptions::new(skia_safe::FilterMode::Linear, skia_safe::MipmapMode::Linear),
+                &paint,
+            );
+
+            // Restore canvas state if we applied a clip
+            if has_border_radius {
                 canvas.restore();
             }
+        });
+    }
 
-            RenderCommandConfig::Rectangle(rect) => {
-                let paint = {
-                    let mut p = Paint::default();
-                    p.set_color(clay_to_skia_color(rect.color));
-                    p.set_anti_alias(true);
-                    p.set_style(skia_safe::PaintStyle::Fill);
-                    p
-                };
-                let bounds = clay_to_skia_rect(command.bounding_box);
-                if rect.corner_radii.top_left > 0.
-                    || rect.corner_radii.top_right > 0.
-                    || rect.corner_radii.bottom_left > 0.
-                    || rect.corner_radii.bottom_right > 0.
-                {
-                    let rrect = RRect::new_rect_radii(
-                        bounds,
-                        &[
-                            Point::new(rect.corner_radii.top_left, rect.corner_radii.top_left),
-                            Point::new(rect.corner_radii.top_right, rect.corner_radii.top_right),
-                            Point::new(
-                                rect.corner_radii.bottom_left,
-                                rect.corner_radii.bottom_left,
-                            ),
-                            Point::new(
-                                rect.corner_radii.bottom_right,
-                                rect.corner_radii.bottom_right,
-                            ),
-                        ],
-                    );
-                    canvas.draw_rrect(rrect, &paint);
-                } else {
-                    canvas.draw_rect(bounds, &paint);
-                }
-            }
+    fn push_scissor(&mut self, bb: BoundingBox, scale_factor: f32) {
+        // Save the current state then clip to the bounding box, in pixels.
+        self.canvas.save();
+        let clip_rect = Rect::from_xywh(
+            bb.x * scale_factor,
+            bb.y * scale_factor,
+            bb.width * scale_factor,
+            bb.height * scale_factor,
+        );
+        self.canvas.clip_rect(clip_rect, ClipOp::Intersect, false);
+    }
 
-            RenderCommandConfig::Border(border) => {
-                // Draw each border side using fill rectangles.
-                let paint = {
-                    let mut p = Paint::default();
-                    p.set_color(clay_to_skia_color(border.color));
-                    p.set_anti_alias(true);
-                    p
-                };
-
-                let bb = &command.bounding_box;
-
-                // Left border.
-                if border.width.left > 0 {
-                    let rect = Rect::from_xywh(
-                        bb.x,
-                        bb.y + border.corner_radii.top_left,
-                        border.width.left as f32,
-                        bb.height - border.corner_radii.top_left - border.corner_radii.bottom_left,
-                    );
-                    canvas.draw_rect(rect, &paint);
-                }
-
-                // Right border.
-                if border.width.right > 0 {
-                    let rect = Rect::from_xywh(
-                        bb.x + bb.width - border.width.right as f32,
-                        bb.y + border.corner_radii.top_right,
-                        border.width.right as f32,
-                        bb.height
-                            - border.corner_radii.top_right
-                            - border.corner_radii.bottom_right,
-                    );
-                    canvas.draw_rect(rect, &paint);
-                }
-
-                // Top border.
-                if border.width.top > 0 {
-                    let rect = Rect::from_xywh(
-                        bb.x + border.corner_radii.top_left,
-                        bb.y,
-                        bb.width - border.corner_radii.top_left - border.corner_radii.top_right,
-                        border.width.top as f32,
-                    );
-                    canvas.draw_rect(rect, &paint);
-                }
-
-                // Bottom border.
-                if border.width.bottom > 0 {
-                    let rect = Rect::from_xywh(
-                        bb.x + border.corner_radii.bottom_left,
-                        bb.y + bb.height - border.width.bottom as f32,
-                        bb.width
-                            - border.corner_radii.bottom_left
-                            - border.corner_radii.bottom_right,
-                        border.width.bottom as f32,
-                    );
-                    canvas.draw_rect(rect, &paint);
-                }
-
-                // For corner arcs, we draw strokes.
-                let mut stroke = Paint::default();
-                stroke.set_color(clay_to_skia_color(border.color));
-                stroke.set_stroke_width(1.0);
-                stroke.set_style(skia_safe::paint::Style::Stroke);
-                stroke.set_anti_alias(true);
-
-                // Helper to draw an arc.
-                let draw_corner_arc = |canvas: &Canvas,
-                                       center_x: f32,
-                                       center_y: f32,
-                                       radius: f32,
-                                       start_angle: f32,
-                                       sweep_angle: f32| {
-                    let arc_rect = Rect::from_xywh(
-                        center_x - radius,
-                        center_y - radius,
-                        radius * 2.0,
-                        radius * 2.0,
-                    );
-                    canvas.draw_arc(arc_rect, start_angle, sweep_angle, false, &stroke);
-                };
-
-                if border.corner_radii.top_left > 0. {
-                    // top-left: arc from 180 to 270 degrees.
-                    let center_x = bb.x + border.corner_radii.top_left;
-                    let center_y = bb.y + border.corner_radii.top_left;
-                    draw_corner_arc(
-                        canvas,
-                        center_x,
-                        center_y,
-                        border.corner_radii.top_left,
-                        180.0,
-                        90.0,
-                    );
-                }
-
-                if border.corner_radii.top_right > 0. {
-                    // top-right: arc from 270 to 360 degrees.
-                    let center_x = bb.x + bb.width - border.corner_radii.top_right;
-                    let center_y = bb.y + border.corner_radii.top_right;
-                    draw_corner_arc(
-                        canvas,
-                        center_x,
-                        center_y,
-                        border.corner_radii.top_right,
-                        270.0,
-                        90.0,
-                    );
-                }
-
-                if border.corner_radii.bottom_left > 0. {
-                    // bottom-left: arc from 90 to 180 degrees.
-                    let center_x = bb.x + border.corner_radii.bottom_left;
-                    let center_y = bb.y + bb.height - border.corner_radii.bottom_left;
-                    draw_corner_arc(
-                        canvas,
-                        center_x,
-                        center_y,
-                        border.corner_radii.bottom_left,
-                        90.0,
-                        90.0,
-                    );
-                }
-
-                if border.corner_radii.bottom_right > 0. {
-                    // bottom-right: arc from 0 to 90 degrees.
-                    let center_x = bb.x + bb.width - border.corner_radii.bottom_right;
-                    let center_y = bb.y + bb.height - border.corner_radii.bottom_right;
-                    draw_corner_arc(
-                        canvas,
-                        center_x,
-                        center_y,
-                        border.corner_radii.bottom_right,
-                        0.0,
-                        90.0,
-                    );
-                }
-            }
-            RenderCommandConfig::Custom(ref custom) => {
-                render_custom_element(&command, custom, canvas)
-            }
-            RenderCommandConfig::None() => {}
-        }
+    fn pop_scissor(&mut self) {
+        // Restore the previous state
+        self.canvas.restore();
+    }
+
+    fn custom(
+        &mut self,
+        bounds: BoundingBox,
+        scale_factor: f32,
+        custom: &Custom<CustomElementData>,
+    ) {
+        let canvas = self.canvas;
+        canvas.save();
+        canvas.scale((scale_factor, scale_factor));
+        (self.render_custom_element)(bounds, custom, canvas);
+        canvas.restore();
+    }
+
+    fn measure_text_function(&self) -> impl Fn(&str, &TextConfig) -> Dimensions + 'static {
+        create_measure_text_function(self.fonts)
     }
 }
 
@@ -291,15 +257,226 @@ pub fn get_source_dimensions_from_skia_image(image: &Image) -> Dimensions {
     (image.width() as f32, image.height() as f32).into()
 }
 
+/// Returns a function measuring text with the same paragraph layout [`clay_skia_render`] draws
+/// it with.
 pub fn create_measure_text_function(
-    fonts: &'static [&Typeface],
+    fonts: &SkiaFonts,
 ) -> impl Fn(&str, &TextConfig) -> Dimensions + 'static {
-    |text, text_config| {
-        let font = Font::new(
-            fonts[text_config.font_id as usize],
-            text_config.font_size as f32,
+    let fonts = fonts.clone();
+    move |text, text_config| {
+        let paragraph = fonts.paragraph(
+            text,
+            text_config.font_id,
+            text_config.font_size,
+            text_config.letter_spacing,
+            text_config.line_height,
+            text_config.color,
+        );
+        (paragraph.max_intrinsic_width(), paragraph.height()).into()
+    }
+}
+
+/// The fonts text is laid out with, indexed by font id. Characters missing from a font fall back
+/// to the fonts of the system.
+#[derive(Clone)]
+pub struct SkiaFonts {
+    collection: FontCollection,
+}
+
+impl SkiaFonts {
+    pub fn new(fonts: &[&Typeface]) -> Self {
+        let mut provider = TypefaceFontProvider::new();
+        for (font_id, typeface) in fonts.iter().enumerate() {
+            provider.register_typeface((*typeface).clone(), Some(family(font_id)));
+        }
+        let provider: FontMgr = provider.into();
+        let mut collection = FontCollection::new();
+        collection.set_asset_font_manager(Some(provider));
+        collection.set_default_font_manager(FontMgr::new(), None);
+        Self { collection }
+    }
+
+    /// Returns the font collection, for example to change the fallback fonts.
+    pub fn font_collection_mut(&mut self) -> &mut FontCollection {
+        &mut self.collection
+    }
+
+    /// Lays out `text` on a single line, as Clay already wrapped it.
+    fn paragraph(
+        &self,
+        text: &str,
+        font_id: u16,
+        font_size: u16,
+        letter_spacing: u16,
+        line_height: u16,
+        color: ClayColor,
+    ) -> Paragraph {
+        let mut style = TextStyle::new();
+        style.set_font_families(&[family(font_id as usize)]);
+        style.set_font_size(font_size as f32);
+        style.set_letter_spacing(letter_spacing as f32);
+        style.set_color(clay_to_skia_color(color));
+        if line_height > 0 && font_size > 0 {
+            // Spread the extra height evenly above and below the glyphs
+            style.set_height_override(true);
+            style.set_height(line_height as f32 / font_size as f32);
+            style.set_half_leading(true);
+        }
+
+        let mut builder = ParagraphBuilder::new(&ParagraphStyle::new(), self.collection.clone());
+        builder.push_style(&style);
+        builder.add_text(text);
+        let mut paragraph = builder.build();
+        paragraph.layout(f32::INFINITY);
+        paragraph
+    }
+}
+
+fn family(font_id: usize) -> String {
+    format!("clay-font-{font_id}")
+}
+
+/// Layout units per inch in exported images and documents, like CSS pixels.
+pub const UNITS_PER_INCH: f32 = 96.0;
+
+/// Draws exported frames at one pixel per layout unit before the export scales the canvas, as
+/// `dpi` already sets the size of a layout unit in exports.
+fn unscaled<'a, ImageElementData, CustomElementData, UserData>(
+    render_commands: impl Iterator<
+        Item = RenderCommand<'a, ImageElementData, CustomElementData, UserData>,
+    >,
+) -> impl Iterator<Item = RenderCommand<'a, ImageElementData, CustomElementData, UserData>> {
+    render_commands.map(|command| RenderCommand {
+        scale_factor: 1.0,
+        ..command
+    })
+}
+
+/// Renders a frame of `size` layout units into a PNG with `dpi` pixels per inch, without a
+/// window or GPU. Returns `None` if the image couldn't be created or encoded.
+///
+/// The scale factor of the frame set with [`Clay::set_scale_factor`](crate::Clay::set_scale_factor)
+/// is ignored, only `dpi` sets how many pixels a layout unit covers.
+pub fn render_png<'a, CustomElementData: 'a, UserData: 'a>(
+    size: Dimensions,
+    dpi: f32,
+    render_commands: impl Iterator<Item = RenderCommand<'a, Image, CustomElementData, UserData>>,
+    fonts: &SkiaFonts,
+    render_custom_element: impl FnMut(BoundingBox, &Custom<CustomElementData>, &Canvas),
+) -> Option<Vec<u8>> {
+    let scale = dpi / UNITS_PER_INCH;
+    let mut surface = surfaces::raster_n32_premul((
+        (size.width * scale).ceil() as i32,
+        (size.height * scale).ceil() as i32,
+    ))?;
+    let canvas = surface.canvas();
+    canvas.clear(Color::TRANSPARENT);
+    canvas.scale((scale, scale));
+    render(
+        &mut SkiaRenderer::new(canvas, fonts, render_custom_element),
+        unscaled(render_commands),
+    );
+
+    let png = surface
+        .image_snapshot()
+        .encode(None, EncodedImageFormat::PNG, None)?;
+    Some(png.as_bytes().to_vec())
+}
+
+/// Renders frames into a PDF, with `add_pages` adding a page for each frame. Text and shapes
+/// stay vectors, images are embedded with at most `dpi` pixels per inch.
+pub fn render_pdf(dpi: f32, fonts: &SkiaFonts, add_pages: impl FnOnce(&mut PdfPages)) -> Vec<u8> {
+    let mut output = Vec::new();
+    {
+        let metadata = pdf::Metadata {
+            raster_dpi: Some(dpi),
+            ..Default::default()
+        };
+        let mut pages = PdfPages {
+            document: Some(pdf::new_document(&mut output, Some(&metadata))),
+            fonts,
+        };
+        add_pages(&mut pages);
+        if let Some(document) = pages.document.take() {
+            document.close();
+        }
+    }
+    output
+}
+
+/// The pages of a PDF being rendered by [`render_pdf`].
+pub struct PdfPages<'a, 'w> {
+    document: Option<Document<'w>>,
+    fonts: &'a SkiaFonts,
+}
+
+impl PdfPages<'_, '_> {
+    /// Adds a page of `size` layout units with a frame. Like [`render_png`], the scale factor of
+    /// the frame is ignored.
+    pub fn page<'a, CustomElementData: 'a, UserData: 'a>(
+        &mut self,
+        size: Dimensions,
+        render_commands: impl Iterator<Item = RenderCommand<'a, Image, CustomElementData, UserData>>,
+        render_custom_element: impl FnMut(BoundingBox, &Custom<CustomElementData>, &Canvas),
+    ) {
+        let Some(document) = self.document.take() else {
+            return;
+        };
+        // PDFs are measured in points, 72 per inch
+        let scale = 72.0 / UNITS_PER_INCH;
+        let mut page = document.begin_page((size.width * scale, size.height * scale), None);
+        let canvas = page.canvas();
+        canvas.scale((scale, scale));
+        render(
+            &mut SkiaRenderer::new(canvas, self.fonts, render_custom_element),
+            unscaled(render_commands),
         );
-        let width = font.measure_str(text, None).0;
-        (width, font.metrics().1.bottom - font.metrics().1.top).into()
+        self.document = Some(page.end_page());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A red rectangle covering a 100 by 50 frame drawn at twice the size
+    fn frame() -> [RenderCommand<'static, Image, (), ()>; 1] {
+        [RenderCommand {
+            bounding_box: BoundingBox::new(0.0, 0.0, 100.0, 50.0),
+            config: RenderCommandConfig::Rectangle(Rectangle {
+                color: ClayColor::rgb(255.0, 0.0, 0.0),
+                corner_radii: CornerRadii::default(),
+            }),
+            id: 1,
+            z_index: 0,
+            user_data: None,
+            scale_factor: 2.0,
+        }]
+    }
+
+    #[test]
+    fn exports_ignore_the_scale_factor() {
+        let fonts = SkiaFonts::new(&[]);
+        let size = Dimensions::new(100.0, 50.0);
+
+        let png = render_png(size, 192.0, frame().into_iter(), &fonts, |_, _, _| {}).unwrap();
+        let image = Image::from_encoded(skia_safe::Data::new_copy(&png))
+            .and_then(|image| image.make_raster_image(None, None))
+            .unwrap();
+        assert_eq!((image.width(), image.height()), (200, 100));
+        // The rectangle fills the image without being cut off
+        let pixels = image.peek_pixels().unwrap();
+        assert_eq!(pixels.get_color((199, 99)), Color::RED);
+        assert_eq!(pixels.get_color((0, 0)), Color::RED);
+
+        let pdf = render_pdf(72.0, &fonts, |pages| {
+            pages.page(size, frame().into_iter(), |_, _, _| {})
+        });
+        assert!(pdf.starts_with(b"%PDF"));
+        // 100 by 50 layout units are 75 by 37.5 points
+        let media_box = b"/MediaBox [0 0 75 37.5]";
+        assert!(pdf
+            .windows(media_box.len())
+            .any(|window| window == media_box));
     }
 }
@@ -0,0 +1,592 @@
+use std::{
+    cell::{RefCell, RefMut},
+    rc::Rc,
+    sync::Arc,
+};
+
+use glyphon::{
+    Attrs, Buffer as TextBuffer, Cache, Family, FontSystem, Metrics, PrepareError, RenderError,
+    Resolution, Shaping, SwashCache, TextArea, TextAtlas, TextBounds, TextRenderer, Viewport,
+};
+use wgpu::util::DeviceExt;
+
+use super::{
+    geometry::{depth, Geometry, Vertex},
+    render, Renderer,
+};
+use crate::{
+    color::Color,
+    math::{BoundingBox, Dimensions, Vector2},
+    render_commands::{Border, CornerRadii, Custom, Image, Rectangle, RenderCommand, Text},
+    text::TextConfig,
+};
+
+/// The depth format of the attachment the render pass passed to [`WgpuRenderer::render`] needs.
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+// SAFETY: the vertex is `repr(C)` and only made of `f32`s, so it has no padding
+unsafe impl bytemuck::Zeroable for Vertex {}
+unsafe impl bytemuck::Pod for Vertex {}
+
+const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 7] = wgpu::vertex_attr_array![
+    0 => Float32x2,
+    1 => Float32,
+    2 => Float32x2,
+    3 => Float32x4,
+    4 => Float32x4,
+    5 => Float32x4,
+    6 => Float32x4,
+];
+
+/// A texture image elements can be drawn with, created by [`WgpuRenderer::create_image`].
+#[derive(Debug, Clone)]
+pub struct WgpuImage {
+    bind_group: Arc<wgpu::BindGroup>,
+}
+
+impl PartialEq for WgpuImage {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.bind_group, &other.bind_group)
+    }
+}
+
+struct TextLine {
+    buffer: TextBuffer,
+    left: f32,
+    top: f32,
+    scale: f32,
+    bounds: TextBounds,
+    color: glyphon::Color,
+}
+
+/// Draws render commands with wgpu, and text with glyphon.
+///
+/// Each frame, [`WgpuRenderer::prepare`] uploads the render commands and
+/// [`WgpuRenderer::render`] records them into a render pass. Later commands are drawn over
+/// earlier ones with the depth buffer, so the render pass needs a [`DEPTH_FORMAT`] depth
+/// attachment cleared to `1.0`.
+pub struct WgpuRenderer {
+    pipeline: wgpu::RenderPipeline,
+    globals: wgpu::Buffer,
+    globals_bind_group: wgpu::BindGroup,
+    image_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    /// Drawn with by everything that isn't an image
+    white: WgpuImage,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    srgb: bool,
+
+    geometry: Geometry<WgpuImage>,
+    resolution: (u32, u32),
+    /// Scale factor of the command being prepared
+    scale: f32,
+
+    font_system: Rc<RefCell<FontSystem>>,
+    swash_cache: SwashCache,
+    viewport: Viewport,
+    atlas: TextAtlas,
+    text_renderer: TextRenderer,
+    lines: Vec<TextLine>,
+}
+
+impl WgpuRenderer {
+    /// Creates a renderer drawing into render targets of `format`.
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat) -> Self {
+        let globals = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("clay globals"),
+            size: 16,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let globals_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("clay globals"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let globals_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("clay globals"),
+            layout: &globals_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: globals.as_entire_binding(),
+            }],
+        });
+
+        let image_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("clay image"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("clay image"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("wgpu.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("clay"),
+            bind_group_layouts: &[&globals_layout, &image_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("clay"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: size_of::<Vertex>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &VERTEX_ATTRIBUTES,
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            // Shapes are drawn in command order, and only write their depth for the text
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let white = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("clay white"),
+                size: wgpu::Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &[255; 4],
+        );
+        let white = Self::image_bind_group(
+            device,
+            &image_layout,
+            &sampler,
+            &white.create_view(&Default::default()),
+        );
+
+        let cache = Cache::new(device);
+        let mut atlas = TextAtlas::new(device, queue, &cache, format);
+        let text_renderer = TextRenderer::new(
+            &mut atlas,
+            device,
+            wgpu::MultisampleState::default(),
+            Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+        );
+
+        Self {
+            pipeline,
+            globals,
+            globals_bind_group,
+            image_layout,
+            sampler,
+            white,
+            vertex_buffer: Self::buffer(device, wgpu::BufferUsages::VERTEX, 0),
+            index_buffer: Self::buffer(device, wgpu::BufferUsages::INDEX, 0),
+            srgb: format.is_srgb(),
+            geometry: Geometry::new(),
+            resolution: (0, 0),
+            scale: 1.0,
+            font_system: Rc::new(RefCell::new(FontSystem::new())),
+            swash_cache: SwashCache::new(),
+            viewport: Viewport::new(device, &cache),
+            atlas,
+            text_renderer,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Makes `view` drawable by image elements. The texture needs
+    /// [`wgpu::TextureUsages::TEXTURE_BINDING`].
+    pub fn create_image(&self, device: &wgpu::Device, view: &wgpu::TextureView) -> WgpuImage {
+        Self::image_bind_group(device, &self.image_layout, &self.sampler, view)
+    }
+
+    /// The font system text is laid out with, to load fonts into. Every font id uses the sans
+    /// serif family.
+    pub fn font_system(&self) -> RefMut<'_, FontSystem> {
+        self.font_system.borrow_mut()
+    }
+
+    /// Measures text the way it is drawn. Letter spacing isn't supported.
+    pub fn measure_text(&self, text: &str, config: &TextConfig) -> Dimensions {
+        measure_text(&mut self.font_system.borrow_mut(), text, config)
+    }
+
+    /// Returns a function measuring text like [`WgpuRenderer::measure_text`], to pass to
+    /// [`Clay::set_measure_text_function`](crate::Clay::set_measure_text_function).
+    pub fn measure_text_function(&self) -> impl Fn(&str, &TextConfig) -> Dimensions + 'static {
+        let font_system = self.font_system.clone();
+        move |text, config| measure_text(&mut font_system.borrow_mut(), text, config)
+    }
+
+    /// Tessellates the render commands of a frame and uploads them for a render target of
+    /// `resolution` pixels. Custom elements are drawn by `render_custom_element`, which can add
+    /// shapes with [`WgpuRenderer::fill_rectangle`] and [`WgpuRenderer::fill_triangle`].
+    pub fn prepare<'a, CustomElementData: 'a, UserData: 'a>(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        resolution: (u32, u32),
+        render_commands: impl Iterator<Item = RenderCommand<'a, WgpuImage, CustomElementData, UserData>>,
+        render_custom_element: impl FnMut(&mut WgpuRenderer, BoundingBox, &Custom<CustomElementData>),
+    ) -> Result<(), PrepareError> {
+        self.geometry.clear();
+        self.lines.clear();
+        self.resolution = resolution;
+        render(
+            &mut Frame {
+                renderer: self,
+                render_custom_element,
+            },
+            render_commands,
+        );
+
+        let globals = [resolution.0 as f32, resolution.1 as f32, 0.0, 0.0];
+        queue.write_buffer(&self.globals, 0, bytemuck::cast_slice(&globals));
+        if self.srgb {
+            for vertex in &mut self.geometry.vertices {
+                for channel in &mut vertex.color[..3] {
+                    *channel = srgb_to_linear(*channel);
+                }
+            }
+        }
+        let vertices = bytemuck::cast_slice(&self.geometry.vertices);
+        if vertices.len() as wgpu::BufferAddress > self.vertex_buffer.size() {
+            self.vertex_buffer =
+                Self::buffer(device, wgpu::BufferUsages::VERTEX, vertices.len() as u64);
+        }
+        queue.write_buffer(&self.vertex_buffer, 0, vertices);
+        let indices = bytemuck::cast_slice(&self.geometry.indices);
+        if indices.len() as wgpu::BufferAddress > self.index_buffer.size() {
+            self.index_buffer =
+                Self::buffer(device, wgpu::BufferUsages::INDEX, indices.len() as u64);
+        }
+        queue.write_buffer(&self.index_buffer, 0, indices);
+
+        self.atlas.trim();
+        self.viewport.update(
+            queue,
+            Resolution {
+                width: resolution.0,
+                height: resolution.1,
+            },
+        );
+        let text_areas = self.lines.iter().map(|line| TextArea {
+            buffer: &line.buffer,
+            left: line.left,
+            top: line.top,
+            scale: line.scale,
+            bounds: line.bounds,
+            default_color: line.color,
+            custom_glyphs: &[],
+        });
+        self.text_renderer.prepare_with_depth(
+            device,
+            queue,
+            &mut self.font_system.borrow_mut(),
+            &mut self.atlas,
+            &self.viewport,
+            text_areas,
+            &mut self.swash_cache,
+            depth,
+        )
+    }
+
+    /// Records the prepared frame into `pass`.
+    pub fn render(&self, pass: &mut wgpu::RenderPass<'_>) -> Result<(), RenderError> {
+        let (width, height) = self.resolution;
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.globals_bind_group, &[]);
+        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        for draw_call in &self.geometry.draw_calls {
+            let [x, y, w, h] = self.clamp_scissor(draw_call.scissor);
+            if w == 0 || h == 0 {
+                continue;
+            }
+            pass.set_scissor_rect(x, y, w, h);
+            let image = draw_call.texture.as_ref().unwrap_or(&self.white);
+            pass.set_bind_group(1, &*image.bind_group, &[]);
+            pass.draw_indexed(draw_call.indices.clone(), 0, 0..1);
+        }
+        pass.set_scissor_rect(0, 0, width, height);
+
+        self.text_renderer.render(&self.atlas, &self.viewport, pass)
+    }
+
+    /// Fills `bounds`, in layout units, with `color` and rounded corners. Meant to be called
+    /// while drawing custom elements.
+    pub fn fill_rectangle(
+        &mut self,
+        bounds: BoundingBox,
+        color: Color,
+        corner_radii: &CornerRadii,
+    ) {
+        let rectangle = Rectangle {
+            color,
+            corner_radii: corner_radii.clone(),
+        };
+        self.geometry.rectangle(bounds, self.scale, &rectangle);
+    }
+
+    /// Fills the triangle between `points`, in layout units, with `color`. Meant to be called
+    /// while drawing custom elements.
+    pub fn fill_triangle(&mut self, points: [Vector2; 3], color: Color) {
+        let scale = self.scale;
+        let points = points.map(|point| Vector2::new(point.x * scale, point.y * scale));
+        self.geometry.triangle(points, color);
+    }
+
+    /// Returns `scissor` clamped to the render target in whole pixels, or the whole render
+    /// target without one.
+    fn clamp_scissor(&self, scissor: Option<BoundingBox>) -> [u32; 4] {
+        let (width, height) = self.resolution;
+        let Some(scissor) = scissor else {
+            return [0, 0, width, height];
+        };
+        let (width, height) = (width as f32, height as f32);
+        let (x1, y1) = (scissor.x.clamp(0.0, width), scissor.y.clamp(0.0, height));
+        let x2 = (scissor.x + scissor.width).clamp(x1, width);
+        let y2 = (scissor.y + scissor.height).clamp(y1, height);
+        let (x1, y1) = (x1.floor(), y1.floor());
+        [
+            x1 as u32,
+            y1 as u32,
+            (x2.ceil() - x1) as u32,
+            (y2.ceil() - y1) as u32,
+        ]
+    }
+
+    fn buffer(
+        device: &wgpu::Device,
+        usage: wgpu::BufferUsages,
+        size: wgpu::BufferAddress,
+    ) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("clay geometry"),
+            size: size.next_power_of_two().max(4096),
+            usage: usage | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn image_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        view: &wgpu::TextureView,
+    ) -> WgpuImage {
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("clay image"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        });
+        WgpuImage {
+            bind_group: Arc::new(bind_group),
+        }
+    }
+}
+
+/// The renderer while it prepares a frame, with the hook for custom elements.
+struct Frame<'r, F> {
+    renderer: &'r mut WgpuRenderer,
+    render_custom_element: F,
+}
+
+impl<F> Frame<'_, F> {
+    /// Moves on to the next command, drawn over the previous ones.
+    fn next_command(&mut self, scale_factor: f32) -> &mut WgpuRenderer {
+        self.renderer.geometry.begin_command();
+        self.renderer.scale = scale_factor;
+        self.renderer
+    }
+}
+
+impl<CustomElementData, F> Renderer<CustomElementData> for Frame<'_, F>
+where
+    F: FnMut(&mut WgpuRenderer, BoundingBox, &Custom<CustomElementData>),
+{
+    type ImageElementData = WgpuImage;
+
+    fn rectangle(&mut self, bounds: BoundingBox, scale_factor: f32, rectangle: &Rectangle) {
+        let renderer = self.next_command(scale_factor);
+        renderer.geometry.rectangle(bounds, scale_factor, rectangle);
+    }
+
+    fn border(&mut self, bounds: BoundingBox, scale_factor: f32, border: &Border) {
+        let renderer = self.next_command(scale_factor);
+        renderer.geometry.border(bounds, scale_factor, border);
+    }
+
+    fn text(&mut self, bounds: BoundingBox, scale_factor: f32, text: &Text) {
+        let renderer = self.next_command(scale_factor);
+        let mut font_system = renderer.font_system.borrow_mut();
+        let metrics = text_metrics(text.font_size, text.line_height);
+        let mut buffer = TextBuffer::new(&mut font_system, metrics);
+        buffer.set_text(
+            &mut font_system,
+            text.text,
+            Attrs::new()
+                .family(Family::SansSerif)
+                .metadata(renderer.geometry.command()),
+            Shaping::Advanced,
+        );
+        buffer.shape_until_scroll(&mut font_system, false);
+        drop(font_system);
+
+        let [x, y, width, height] = renderer.clamp_scissor(renderer.geometry.scissor());
+        let color = text.color;
+        renderer.lines.push(TextLine {
+            buffer,
+            left: bounds.x * scale_factor,
+            top: bounds.y * scale_factor,
+            scale: scale_factor,
+            bounds: TextBounds {
+                left: x as i32,
+                top: y as i32,
+                right: (x + width) as i32,
+                bottom: (y + height) as i32,
+            },
+            color: glyphon::Color::rgba(color.r as u8, color.g as u8, color.b as u8, color.a as u8),
+        });
+    }
+
+    fn image(&mut self, bounds: BoundingBox, scale_factor: f32, image: &Image<'_, WgpuImage>) {
+        let renderer = self.next_command(scale_factor);
+        renderer.geometry.image(bounds, scale_factor, image);
+    }
+
+    fn push_scissor(&mut self, bounds: BoundingBox, scale_factor: f32) {
+        self.renderer.geometry.push_scissor(bounds, scale_factor);
+    }
+
+    fn pop_scissor(&mut self) {
+        self.renderer.geometry.pop_scissor();
+    }
+
+    fn custom(
+        &mut self,
+        bounds: BoundingBox,
+        scale_factor: f32,
+        custom: &Custom<'_, CustomElementData>,
+    ) {
+        self.next_command(scale_factor);
+        (self.render_custom_element)(self.renderer, bounds, custom);
+    }
+
+    fn measure_text_function(&self) -> impl Fn(&str, &TextConfig) -> Dimensions + 'static {
+        self.renderer.measure_text_function()
+    }
+}
+
+/// The metrics of text in layout units. Without a line height, lines are 1.5 times the font
+/// size.
+fn text_metrics(font_size: u16, line_height: u16) -> Metrics {
+    let font_size = font_size as f32;
+    let line_height = match line_height {
+        0 => font_size * 1.5,
+        line_height => line_height as f32,
+    };
+    Metrics::new(font_size, line_height)
+}
+
+fn measure_text(font_system: &mut FontSystem, text: &str, config: &TextConfig) -> Dimensions {
+    let metrics = text_metrics(config.font_size, config.line_height);
+    let mut buffer = TextBuffer::new(font_system, metrics);
+    let mut buffer = buffer.borrow_with(font_system);
+    buffer.set_text(
+        text,
+        Attrs::new().family(Family::SansSerif),
+        Shaping::Advanced,
+    );
+    buffer.shape_until_scroll(false);
+
+    let (width, lines) = buffer
+        .layout_runs()
+        .fold((0.0f32, 0), |(width, lines), run| {
+            (width.max(run.line_w), lines + 1)
+        });
+    (width, metrics.line_height * lines.max(1) as f32).into()
+}
+
+/// Converts a color channel from sRGB to linear.
+fn srgb_to_linear(channel: f32) -> f32 {
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
@@ -0,0 +1,115 @@
+struct Globals {
+    // Size of the render target in pixels
+    size: vec2<f32>,
+}
+
+@group(0) @binding(0) var<uniform> globals: Globals;
+@group(1) @binding(0) var image: texture_2d<f32>;
+@group(1) @binding(1) var image_sampler: sampler;
+
+// See `geometry::Vertex`
+struct Vertex {
+    @location(0) position: vec2<f32>,
+    @location(1) depth: f32,
+    @location(2) uv: vec2<f32>,
+    @location(3) color: vec4<f32>,
+    @location(4) rect: vec4<f32>,
+    @location(5) corner_radii: vec4<f32>,
+    @location(6) border_widths: vec4<f32>,
+};
+
+struct VertexPayload {
+    @builtin(position) position: vec4<f32>,
+    @location(0) pixel: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) rect: vec4<f32>,
+    @location(4) corner_radii: vec4<f32>,
+    @location(5) border_widths: vec4<f32>,
+};
+
+@vertex
+fn vs_main(vertex: Vertex) -> VertexPayload {
+    var out: VertexPayload;
+    out.position = vec4<f32>(
+        vertex.position.x / globals.size.x * 2.0 - 1.0,
+        1.0 - vertex.position.y / globals.size.y * 2.0,
+        vertex.depth,
+        1.0,
+    );
+    out.pixel = vertex.position;
+    out.uv = vertex.uv;
+    out.color = vertex.color;
+    out.rect = vertex.rect;
+    out.corner_radii = vertex.corner_radii;
+    out.border_widths = vertex.border_widths;
+    return out;
+}
+
+// Signed distance from `pixel` to the rectangle `rect` with the top left, top right, bottom right
+// and bottom left `radii`
+fn rounded_rect_distance(pixel: vec2<f32>, rect: vec4<f32>, radii: vec4<f32>) -> f32 {
+    let half_size = rect.zw / 2.0;
+    let p = pixel - rect.xy - half_size;
+    let top = select(radii.x, radii.y, p.x > 0.0);
+    let bottom = select(radii.w, radii.z, p.x > 0.0);
+    let radius = select(top, bottom, p.y > 0.0);
+    let q = abs(p) - half_size + radius;
+    return min(max(q.x, q.y), 0.0) + length(max(q, vec2<f32>(0.0))) - radius;
+}
+
+// Signed distance from `pixel` to the rectangle `rect` with elliptical corners, whose horizontal
+// and vertical radii are `radii_x` and `radii_y`, approximated inside the corners
+fn elliptical_rect_distance(
+    pixel: vec2<f32>,
+    rect: vec4<f32>,
+    radii_x: vec4<f32>,
+    radii_y: vec4<f32>,
+) -> f32 {
+    let half_size = rect.zw / 2.0;
+    let p = pixel - rect.xy - half_size;
+    let radius = vec2<f32>(
+        select(select(radii_x.x, radii_x.y, p.x > 0.0), select(radii_x.w, radii_x.z, p.x > 0.0), p.y > 0.0),
+        select(select(radii_y.x, radii_y.y, p.x > 0.0), select(radii_y.w, radii_y.z, p.x > 0.0), p.y > 0.0),
+    );
+    let q = abs(p) - half_size + radius;
+    if q.x > 0.0 && q.y > 0.0 && min(radius.x, radius.y) > 0.0 {
+        let k0 = length(q / radius);
+        let k1 = length(q / (radius * radius));
+        return k0 * (k0 - 1.0) / k1;
+    }
+    let edge = abs(p) - half_size;
+    return min(max(edge.x, edge.y), 0.0) + length(max(edge, vec2<f32>(0.0)));
+}
+
+@fragment
+fn fs_main(in: VertexPayload) -> @location(0) vec4<f32> {
+    var coverage = clamp(0.5 - rounded_rect_distance(in.pixel, in.rect, in.corner_radii), 0.0, 1.0);
+
+    let widths = in.border_widths;
+    if any(widths != vec4<f32>(0.0)) {
+        let inner = vec4<f32>(
+            in.rect.xy + widths.xy,
+            in.rect.zw - widths.xy - widths.zw,
+        );
+        if inner.z > 0.0 && inner.w > 0.0 {
+            // Each corner shrinks by the width of the side meeting there on each axis, see
+            // `Border::inner_corner_radii`
+            let radii_x = max(in.corner_radii - widths.xzzx, vec4<f32>(0.0));
+            let radii_y = max(in.corner_radii - widths.yyww, vec4<f32>(0.0));
+            let inside = clamp(
+                0.5 - elliptical_rect_distance(in.pixel, inner, radii_x, radii_y),
+                0.0,
+                1.0,
+            );
+            coverage *= 1.0 - inside;
+        }
+    }
+
+    let color = in.color * textureSample(image, image_sampler, in.uv);
+    // Keep the depth of uncovered fragments, like the inside of a border, for the text below
+    if coverage <= 0.0 {
+        discard;
+    }
+    return vec4<f32>(color.rgb, color.a * coverage);
+}
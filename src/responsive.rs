@@ -0,0 +1,66 @@
+//! Responsive layouts that pick between declaration variants by the available size, like media
+//! and container queries.
+//!
+//! Variants are given as `(min_width, variant)` pairs in ascending order of `min_width`. The last
+//! variant whose `min_width` fits is picked, or the first one if none fits.
+
+use crate::{id::Id, math::Dimensions, Clay};
+
+/// Picks the variant for `size` from `(min_size, variant)` pairs sorted by `min_size`.
+/// ```
+/// use clay_layout::responsive::select;
+/// assert_eq!(*select(700.0, &[(0.0, 1), (600.0, 2), (1200.0, 4)]), 2);
+/// ```
+///
+/// # Panics
+/// Panics if `variants` is empty.
+pub fn select<T>(size: f32, variants: &[(f32, T)]) -> &T {
+    let (_, first) = variants.first().expect("no breakpoint variants");
+    variants
+        .iter()
+        .rev()
+        .find(|(min, _)| size >= *min)
+        .map_or(first, |(_, variant)| variant)
+}
+
+impl Clay {
+    /// Returns the dimensions of the global layout
+    pub fn layout_dimensions(&self) -> Dimensions {
+        self.layout_dimensions.get()
+    }
+
+    /// Picks the variant for the width of the global layout, see [`select`].
+    pub fn breakpoint<'a, T>(&self, variants: &'a [(f32, T)]) -> &'a T {
+        select(self.layout_dimensions.get().width, variants)
+    }
+
+    /// Picks the variant for the width the element with `id` had in the last layout, see
+    /// [`select`]. Before the element was laid out once, the width of the global layout is used.
+    ///
+    /// `id` should be an ancestor whose size doesn't depend on the picked variant, otherwise the
+    /// layout can flip between variants every frame.
+    pub fn container_query<'a, T>(&self, id: Id, variants: &'a [(f32, T)]) -> &'a T {
+        let width = self
+            .bounding_box(id)
+            .map_or(self.layout_dimensions.get().width, |bounds| bounds.width);
+        select(width, variants)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_largest_fitting_variant() {
+        let variants = [(0.0, "phone"), (600.0, "tablet"), (1200.0, "desktop")];
+        assert_eq!(*select(320.0, &variants), "phone");
+        assert_eq!(*select(600.0, &variants), "tablet");
+        assert_eq!(*select(1199.0, &variants), "tablet");
+        assert_eq!(*select(4000.0, &variants), "desktop");
+
+        // Below every breakpoint the smallest variant still applies
+        let variants = [(200.0, "icons"), (480.0, "full")];
+        assert_eq!(*select(100.0, &variants), "icons");
+    }
+}
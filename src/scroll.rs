@@ -0,0 +1,898 @@
+use crate::{
+    bindings::*,
+    color::Color,
+    elements::{FloatingAttachToElement, FloatingClipToElement, PointerCaptureMode},
+    id::Id,
+    layout::Sizing,
+    math::{BoundingBox, Dimensions, Vector2},
+    Clay, ClayLayoutScope, Declaration, PointerState,
+};
+
+/// Number of smooth scrolls that can run at the same time.
+pub const MAX_SCROLL_ANIMATIONS: usize = 8;
+
+/// Number of scrollbars per frame that react to the pointer. Further scrollbars are drawn but
+/// can't be dragged, [`Clay::dropped_scrollbars`] counts them.
+pub const MAX_SCROLLBARS: usize = 16;
+
+/// Where an element ends up inside the viewport after [`ScrollContainer::scroll_to_element`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScrollAlignment {
+    /// Aligns the start of the element with the start of the viewport.
+    Start,
+    /// Centers the element in the viewport.
+    Center,
+    /// Aligns the end of the element with the end of the viewport.
+    End,
+    /// Scrolls as little as possible to make the element visible.
+    #[default]
+    Nearest,
+}
+
+/// Styling of the scrollbars shown by a clip container, set with
+/// [`Declaration::scrollbar`].
+///
+/// Scrollbars are declared as floating elements inside the container, so they end up as
+/// rectangle render commands, and react to the pointer state passed to [`Clay::pointer_state`]:
+/// the thumb can be dragged and pressing the track pages by one viewport.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollbarConfig {
+    /// Thickness of the scrollbars.
+    pub width: f32,
+    /// Space between the scrollbars and the edges of the container.
+    pub padding: f32,
+    /// Length the thumb doesn't shrink below.
+    pub min_thumb_length: f32,
+    /// Corner radius of the track and the thumb.
+    pub corner_radius: f32,
+    pub track_color: Color,
+    pub thumb_color: Color,
+    /// Color of the thumb while it is hovered or dragged.
+    pub active_thumb_color: Color,
+    pub z_index: i16,
+}
+
+impl Default for ScrollbarConfig {
+    fn default() -> Self {
+        Self {
+            width: 8.0,
+            padding: 2.0,
+            min_thumb_length: 16.0,
+            corner_radius: 4.0,
+            track_color: Color::u_rgba(0, 0, 0, 32),
+            thumb_color: Color::u_rgba(128, 128, 128, 160),
+            active_thumb_color: Color::u_rgba(128, 128, 128, 224),
+            z_index: 0,
+        }
+    }
+}
+
+/// A scrollbar declared in the last layout.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Scrollbar {
+    container: u32,
+    vertical: bool,
+    track: Id,
+    thumb: Id,
+}
+
+/// The thumb being dragged, `grab` is the distance of the pointer from the start of the thumb.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ScrollbarDrag {
+    scrollbar: Scrollbar,
+    grab: f32,
+}
+
+/// Returns the start of the thumb relative to the start of the track and its length.
+fn thumb_geometry(
+    track_length: f32,
+    viewport: f32,
+    content: f32,
+    offset: f32,
+    min_thumb_length: f32,
+) -> (f32, f32) {
+    let length = (track_length * viewport / content)
+        .max(min_thumb_length)
+        .min(track_length);
+    let range = content - viewport;
+    let progress = if range > 0.0 {
+        (-offset / range).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (progress * (track_length - length), length)
+}
+
+/// Returns the scroll offset that moves the thumb to `thumb_start`, the inverse of
+/// [`thumb_geometry`].
+fn thumb_offset(
+    thumb_start: f32,
+    track_length: f32,
+    thumb_length: f32,
+    viewport: f32,
+    content: f32,
+) -> f32 {
+    let travel = track_length - thumb_length;
+    if travel <= 0.0 {
+        return 0.0;
+    }
+    -(thumb_start / travel).clamp(0.0, 1.0) * (content - viewport).max(0.0)
+}
+
+/// Returns the offset along one axis that moves an element starting at `start` with `size` to
+/// `alignment` inside the viewport starting at `view_start` with `view_size`.
+fn aligned_offset(
+    alignment: ScrollAlignment,
+    offset: f32,
+    (start, size): (f32, f32),
+    (view_start, view_size): (f32, f32),
+) -> f32 {
+    let to_start = offset - (start - view_start);
+    let to_end = offset - (start + size - (view_start + view_size));
+    match alignment {
+        ScrollAlignment::Start => to_start,
+        ScrollAlignment::Center => offset - (start + size / 2.0 - (view_start + view_size / 2.0)),
+        ScrollAlignment::End => to_end,
+        ScrollAlignment::Nearest if start < view_start => to_start,
+        ScrollAlignment::Nearest if start + size > view_start + view_size => to_end,
+        ScrollAlignment::Nearest => offset,
+    }
+}
+
+/// Clamps `offset` to the range that keeps the viewport inside the content.
+fn clamp_offset(offset: Vector2, content: Dimensions, viewport: Dimensions) -> Vector2 {
+    let min_x = -(content.width - viewport.width).max(0.0);
+    let min_y = -(content.height - viewport.height).max(0.0);
+    Vector2::new(offset.x.clamp(min_x, 0.0), offset.y.clamp(min_y, 0.0))
+}
+
+/// A running smooth scroll started with [`ScrollContainer::smooth_scroll_to`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ScrollAnimation {
+    id: u32,
+    from: Vector2,
+    to: Vector2,
+    elapsed: f32,
+    duration: f32,
+}
+
+/// Handle to a scroll container of the last layout, returned by [`Clay::scroll_container`].
+///
+/// Offsets are negative, an offset of `(0, -100)` means the content was scrolled down by 100.
+pub struct ScrollContainer<'clay> {
+    clay: &'clay Clay,
+    id: Id,
+    data: Clay_ScrollContainerData,
+}
+
+impl<'clay> ScrollContainer<'clay> {
+    pub(crate) fn new(clay: &'clay Clay, id: Id) -> Option<Self> {
+        let data = scroll_container_data(id.id.id)?;
+        Some(Self { clay, id, data })
+    }
+
+    /// Returns the id of the scroll container
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    /// Returns the current scroll offset
+    pub fn offset(&self) -> Vector2 {
+        unsafe { *self.data.scrollPosition }.into()
+    }
+
+    /// Sets the scroll offset, clamped to the scrollable range, and stops smooth scrolling
+    pub fn set_offset(&self, offset: Vector2) {
+        self.clay.cancel_scroll_animation(self.id.id.id);
+        unsafe { *self.data.scrollPosition = self.clamp(offset).into() };
+    }
+
+    /// Returns the dimensions of the content inside the scroll container
+    pub fn content_dimensions(&self) -> Dimensions {
+        self.data.contentDimensions.into()
+    }
+
+    /// Returns the visible area of the scroll container
+    pub fn viewport(&self) -> BoundingBox {
+        let dimensions = self.data.scrollContainerDimensions;
+        self.clay.bounding_box(self.id).unwrap_or(BoundingBox::new(
+            0.0,
+            0.0,
+            dimensions.width,
+            dimensions.height,
+        ))
+    }
+
+    /// Returns if the container scrolls horizontally and vertically
+    pub fn axes(&self) -> (bool, bool) {
+        (self.data.config.horizontal, self.data.config.vertical)
+    }
+
+    /// Returns the offset that moves the element with `id` to `alignment` inside the viewport,
+    /// or `None` if the element wasn't part of the last layout
+    pub fn element_offset(&self, id: Id, alignment: ScrollAlignment) -> Option<Vector2> {
+        let element = self.clay.bounding_box(id)?;
+        let viewport = self.viewport();
+        let offset = self.offset();
+
+        let (horizontal, vertical) = self.axes();
+        let mut target = offset;
+        if horizontal {
+            target.x = aligned_offset(
+                alignment,
+                offset.x,
+                (element.x, element.width),
+                (viewport.x, viewport.width),
+            );
+        }
+        if vertical {
+            target.y = aligned_offset(
+                alignment,
+                offset.y,
+                (element.y, element.height),
+                (viewport.y, viewport.height),
+            );
+        }
+        Some(self.clamp(target))
+    }
+
+    /// Scrolls the element with `id` into view, returns `false` if the element wasn't part of
+    /// the last layout
+    pub fn scroll_to_element(&self, id: Id, alignment: ScrollAlignment) -> bool {
+        match self.element_offset(id, alignment) {
+            Some(offset) => {
+                self.set_offset(offset);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Animates the scroll offset to `offset` over `duration` seconds. The animation advances in
+    /// [`Clay::update_scroll_containers`] and stops when the user scrolls this container. While
+    /// [`MAX_SCROLL_ANIMATIONS`] other containers are animating, the offset is set right away.
+    pub fn smooth_scroll_to(&self, offset: Vector2, duration: f32) {
+        let to = self.clamp(offset);
+        if duration <= 0.0 {
+            self.set_offset(to);
+            return;
+        }
+
+        let animation = ScrollAnimation {
+            id: self.id.id.id,
+            from: self.offset(),
+            to,
+            elapsed: 0.0,
+            duration,
+        };
+        if !self.clay.start_scroll_animation(animation) {
+            self.set_offset(to);
+        }
+    }
+
+    /// Returns if the container is currently scrolling smoothly
+    pub fn is_animating(&self) -> bool {
+        self.clay
+            .scroll_animations
+            .get()
+            .iter()
+            .flatten()
+            .any(|animation| animation.id == self.id.id.id)
+    }
+
+    fn clamp(&self, offset: Vector2) -> Vector2 {
+        clamp_offset(
+            offset,
+            self.data.contentDimensions.into(),
+            self.data.scrollContainerDimensions.into(),
+        )
+    }
+}
+
+pub(crate) fn scroll_container_data(id: u32) -> Option<Clay_ScrollContainerData> {
+    let data = unsafe { Clay_GetScrollContainerData(Id::from_raw(id).id) };
+    (data.found && !data.scrollPosition.is_null()).then_some(data)
+}
+
+impl Clay {
+    /// Starts `animation`, replacing a running one of the same container. Returns `false` if
+    /// too many animations are running.
+    fn start_scroll_animation(&self, animation: ScrollAnimation) -> bool {
+        let mut animations = self.scroll_animations.get();
+        let slot = animations
+            .iter()
+            .position(|slot| slot.is_some_and(|running| running.id == animation.id))
+            .or_else(|| animations.iter().position(Option::is_none));
+
+        match slot {
+            Some(slot) => {
+                animations[slot] = Some(animation);
+                self.scroll_animations.set(animations);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn cancel_scroll_animation(&self, id: u32) {
+        let mut animations = self.scroll_animations.get();
+        for slot in animations.iter_mut() {
+            if slot.is_some_and(|animation| animation.id == id) {
+                *slot = None;
+            }
+        }
+        self.scroll_animations.set(animations);
+    }
+
+    /// Advances smooth scrolls by `delta_time`. Containers the user scrolls with `scroll_delta`
+    /// stop animating.
+    pub(crate) fn advance_scroll_animations(&self, scroll_delta: Vector2, delta_time: f32) {
+        let user_scrolled = scroll_delta != Vector2::default();
+        let mut animations = self.scroll_animations.get();
+
+        for slot in animations.iter_mut() {
+            let Some(animation) = slot else {
+                continue;
+            };
+            let cancelled = user_scrolled && self.pointer_over(Id::from_raw(animation.id));
+            let data = match scroll_container_data(animation.id) {
+                Some(data) if !cancelled => data,
+                _ => {
+                    *slot = None;
+                    continue;
+                }
+            };
+
+            animation.elapsed += delta_time;
+            let t = (animation.elapsed / animation.duration).min(1.0);
+            // Ease out cubic
+            let inverse = 1.0 - t;
+            let eased = 1.0 - inverse * inverse * inverse;
+            let position = Vector2::new(
+                animation.from.x + (animation.to.x - animation.from.x) * eased,
+                animation.from.y + (animation.to.y - animation.from.y) * eased,
+            );
+            unsafe { *data.scrollPosition = position.into() };
+
+            if t >= 1.0 {
+                *slot = None;
+            }
+        }
+
+        self.scroll_animations.set(animations);
+    }
+}
+
+impl<
+        'render,
+        'clay: 'render,
+        ImageElementData: 'render,
+        CustomElementData: 'render,
+        UserData: 'render,
+    > ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>
+{
+    /// Declares the scrollbars of the open clip container from its scroll container data of the
+    /// last layout.
+    pub(crate) fn declare_scrollbars(&self, clip: Clay_ClipElementConfig, config: ScrollbarConfig) {
+        let container = unsafe { Clay__GetOpenElementId() };
+        let Some(data) = scroll_container_data(container) else {
+            return;
+        };
+        let offset: Vector2 = unsafe { *data.scrollPosition }.into();
+        let viewport = data.scrollContainerDimensions;
+        let content = data.contentDimensions;
+
+        let horizontal = clip.horizontal && content.width > viewport.width;
+        let vertical = clip.vertical && content.height > viewport.height;
+        // Leaves the corner free when both scrollbars are shown
+        let reserved = if horizontal && vertical {
+            config.width + config.padding
+        } else {
+            0.0
+        };
+        // With external scroll handling clay moves floating elements by the child offset
+        let shift = if self.clay.external_scroll_handling.get() {
+            clip.childOffset.into()
+        } else {
+            Vector2::default()
+        };
+        let dragged = self.clay.scrollbar_drag.get().map(|drag| drag.scrollbar);
+
+        for (is_vertical, shown) in [(false, horizontal), (true, vertical)] {
+            if !shown {
+                continue;
+            }
+            let (view, content, offset, cross) = if is_vertical {
+                (viewport.height, content.height, offset.y, viewport.width)
+            } else {
+                (viewport.width, content.width, offset.x, viewport.height)
+            };
+            let track_length = (view - 2.0 * config.padding - reserved).max(0.0);
+            let (thumb_start, thumb_length) =
+                thumb_geometry(track_length, view, content, offset, config.min_thumb_length);
+
+            let scrollbar = Scrollbar {
+                container,
+                vertical: is_vertical,
+                track: scrollbar_id("clay_rs__scrollbar_track", is_vertical, container),
+                thumb: scrollbar_id("clay_rs__scrollbar_thumb", is_vertical, container),
+            };
+            let active = self.clay.pointer_over(scrollbar.thumb)
+                || dragged.is_some_and(|dragged| {
+                    dragged.container == container && dragged.vertical == is_vertical
+                });
+
+            let across = cross - config.width - config.padding;
+            let (track_position, track_size, thumb_position, thumb_size) = if is_vertical {
+                (
+                    Vector2::new(across, config.padding),
+                    (config.width, track_length),
+                    Vector2::new(0.0, thumb_start),
+                    (config.width, thumb_length),
+                )
+            } else {
+                (
+                    Vector2::new(config.padding, across),
+                    (track_length, config.width),
+                    Vector2::new(thumb_start, 0.0),
+                    (thumb_length, config.width),
+                )
+            };
+
+            let bar = |position: Vector2, (width, height): (f32, f32), color: Color| {
+                let mut declaration = Declaration::<(), ()>::new();
+                declaration
+                    .floating()
+                    .attach_to(FloatingAttachToElement::Parent)
+                    .clip_to(FloatingClipToElement::AttachedParent)
+                    .pointer_capture_mode(PointerCaptureMode::Capture)
+                    .offset(Vector2::new(position.x - shift.x, position.y - shift.y))
+                    .z_index(config.z_index)
+                    .end()
+                    .layout()
+                    .width(Sizing::Fixed(width))
+                    .height(Sizing::Fixed(height))
+                    .end()
+                    .background_color(color)
+                    .corner_radius()
+                    .all(config.corner_radius)
+                    .end();
+                declaration.inner
+            };
+
+            unsafe {
+                Clay__OpenElementWithId(scrollbar.track.id);
+                Clay__ConfigureOpenElement(bar(track_position, track_size, config.track_color));
+                Clay__OpenElementWithId(scrollbar.thumb.id);
+                Clay__ConfigureOpenElement(bar(
+                    thumb_position,
+                    thumb_size,
+                    if active {
+                        config.active_thumb_color
+                    } else {
+                        config.thumb_color
+                    },
+                ));
+                Clay__CloseElement();
+                Clay__CloseElement();
+            }
+
+            let mut scrollbars = self.clay.scrollbars.get();
+            match scrollbars.iter_mut().find(|slot| slot.is_none()) {
+                Some(slot) => {
+                    *slot = Some(scrollbar);
+                    self.clay.scrollbars.set(scrollbars);
+                }
+                None => self
+                    .clay
+                    .dropped_scrollbars
+                    .set(self.clay.dropped_scrollbars.get() + 1),
+            }
+        }
+    }
+}
+
+fn scrollbar_id(label: &'static str, vertical: bool, container: u32) -> Id {
+    Id {
+        id: unsafe { Clay__HashStringWithOffset(label.into(), vertical as u32, container) },
+    }
+}
+
+impl Clay {
+    /// Returns how many scrollbars of the last layout don't react to the pointer, because more
+    /// than [`MAX_SCROLLBARS`] were declared
+    pub fn dropped_scrollbars(&self) -> usize {
+        self.dropped_scrollbars.get()
+    }
+
+    /// Drags the thumb or pages through the track of the scrollbars of the last layout, called
+    /// after the pointer state changed from `was_down`.
+    pub(crate) fn update_scrollbars(&self, was_down: bool) {
+        let PointerState { position, is_down } = self.pointer.get();
+        if !is_down {
+            self.scrollbar_drag.set(None);
+            return;
+        }
+        let along = |vertical: bool, vector: Vector2| if vertical { vector.y } else { vector.x };
+        let start =
+            |vertical: bool, bounds: BoundingBox| if vertical { bounds.y } else { bounds.x };
+        let length = |vertical: bool, bounds: BoundingBox| {
+            if vertical {
+                bounds.height
+            } else {
+                bounds.width
+            }
+        };
+
+        if let Some(ScrollbarDrag { scrollbar, grab }) = self.scrollbar_drag.get() {
+            let vertical = scrollbar.vertical;
+            let (Some(container), Some(track), Some(thumb)) = (
+                self.scroll_container(Id::from_raw(scrollbar.container)),
+                self.bounding_box(scrollbar.track),
+                self.bounding_box(scrollbar.thumb),
+            ) else {
+                self.scrollbar_drag.set(None);
+                return;
+            };
+
+            let thumb_start = along(vertical, position) - grab - start(vertical, track);
+            let content = container.content_dimensions();
+            let viewport = container.data.scrollContainerDimensions;
+            let (view, content) = if vertical {
+                (viewport.height, content.height)
+            } else {
+                (viewport.width, content.width)
+            };
+            let offset = thumb_offset(
+                thumb_start,
+                length(vertical, track),
+                length(vertical, thumb),
+                view,
+                content,
+            );
+
+            let mut target = container.offset();
+            if vertical {
+                target.y = offset;
+            } else {
+                target.x = offset;
+            }
+            container.set_offset(target);
+            return;
+        }
+
+        if was_down {
+            return;
+        }
+        for scrollbar in self.scrollbars.get().into_iter().flatten() {
+            let vertical = scrollbar.vertical;
+            let Some(thumb) = self.bounding_box(scrollbar.thumb) else {
+                continue;
+            };
+
+            if self.pointer_over(scrollbar.thumb) {
+                let grab = along(vertical, position) - start(vertical, thumb);
+                self.scrollbar_drag
+                    .set(Some(ScrollbarDrag { scrollbar, grab }));
+                return;
+            }
+            if !self.pointer_over(scrollbar.track) {
+                continue;
+            }
+            let Some(container) = self.scroll_container(Id::from_raw(scrollbar.container)) else {
+                continue;
+            };
+
+            // Pages by one viewport towards the pointer
+            let viewport = container.data.scrollContainerDimensions;
+            let page = if vertical {
+                viewport.height
+            } else {
+                viewport.width
+            };
+            let page = if along(vertical, position) < start(vertical, thumb) {
+                page
+            } else {
+                -page
+            };
+            let mut target = container.offset();
+            if vertical {
+                target.y += page;
+            } else {
+                target.x += page;
+            }
+            container.set_offset(target);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thumb_offset_inverts_thumb_geometry() {
+        // 100 of 400 visible on a 90 long track, scrolled a third down
+        let (start, length) = thumb_geometry(90.0, 100.0, 400.0, -100.0, 16.0);
+        assert_eq!(length, 22.5);
+        assert_eq!(start, (90.0 - 22.5) / 3.0);
+        assert!((thumb_offset(start, 90.0, length, 100.0, 400.0) + 100.0).abs() < 1e-3);
+
+        // The thumb doesn't shrink below its minimum length or move past the track
+        let (start, length) = thumb_geometry(90.0, 100.0, 10_000.0, -20_000.0, 16.0);
+        assert_eq!((start, length), (74.0, 16.0));
+        assert_eq!(thumb_offset(200.0, 90.0, length, 100.0, 10_000.0), -9_900.0);
+    }
+
+    #[test]
+    fn aligned_offset_moves_the_element_into_the_viewport() {
+        // A 20 long element at 150, in a viewport from 10 to 110, scrolled down by 30
+        let align = |alignment| aligned_offset(alignment, -30.0, (150.0, 20.0), (10.0, 100.0));
+        assert_eq!(align(ScrollAlignment::Start), -170.0);
+        assert_eq!(align(ScrollAlignment::Center), -130.0);
+        assert_eq!(align(ScrollAlignment::End), -90.0);
+        // Below the viewport, so its end is aligned
+        assert_eq!(align(ScrollAlignment::Nearest), -90.0);
+
+        // Above the viewport, so its start is aligned
+        let above = aligned_offset(ScrollAlignment::Nearest, -30.0, (0.0, 20.0), (10.0, 100.0));
+        assert_eq!(above, -20.0);
+        // Already visible, so the offset stays
+        let visible = aligned_offset(ScrollAlignment::Nearest, -30.0, (50.0, 20.0), (10.0, 100.0));
+        assert_eq!(visible, -30.0);
+    }
+
+    #[test]
+    fn offsets_are_clamped_to_the_content() {
+        let content = Dimensions::new(300.0, 500.0);
+        let viewport = Dimensions::new(100.0, 100.0);
+        let clamp = |x, y| clamp_offset(Vector2::new(x, y), content, viewport);
+        assert_eq!(clamp(-50.0, -50.0), Vector2::new(-50.0, -50.0));
+        assert_eq!(clamp(20.0, -1000.0), Vector2::new(0.0, -400.0));
+        assert_eq!(clamp(-1000.0, 20.0), Vector2::new(-200.0, 0.0));
+        // Content smaller than the viewport doesn't scroll
+        let small = clamp_offset(Vector2::new(-10.0, -10.0), viewport, content);
+        assert_eq!(small, Vector2::new(0.0, 0.0));
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod layout_tests {
+    use super::*;
+    use crate::tests::lock_context;
+
+    /// Declares `lists` lists of 100 by 100 next to each other, each scrolling ten rows of 50
+    /// vertically with scrollbars. Row `index` of list `list` has the id `("row", list * 10 +
+    /// index)`.
+    fn lists_frame(clay: &mut Clay, lists: u32) {
+        let offsets: Vec<_> = (0..lists)
+            .map(|list| list_offset(clay, list).unwrap_or_default())
+            .collect();
+        let mut scope = clay.begin::<(), ()>();
+        for (list, offset) in (0..lists).zip(offsets) {
+            let mut declaration = Declaration::new();
+            declaration
+                .id(scope.id_index("list", list))
+                .layout()
+                .width(Sizing::Fixed(100.0))
+                .height(Sizing::Fixed(100.0))
+                .direction(crate::layout::LayoutDirection::TopToBottom)
+                .end()
+                .clip(false, true, offset)
+                .scrollbar();
+            scope.with(&declaration, |scope| {
+                for index in 0..10 {
+                    let mut row = Declaration::new();
+                    row.id(scope.id_index("row", list * 10 + index))
+                        .layout()
+                        .width(Sizing::Fixed(100.0))
+                        .height(Sizing::Fixed(50.0));
+                    scope.with(&row, |_| {});
+                }
+            });
+        }
+        let _commands = scope.end();
+    }
+
+    fn list_frame(clay: &mut Clay) {
+        lists_frame(clay, 1);
+    }
+
+    fn list(clay: &Clay) -> ScrollContainer<'_> {
+        clay.scroll_container(clay.id_index("list", 0)).unwrap()
+    }
+
+    fn list_offset(clay: &Clay, list: u32) -> Option<Vector2> {
+        let container = clay.scroll_container(clay.id_index("list", list))?;
+        Some(container.offset())
+    }
+
+    #[test]
+    fn scrollbars_follow_thumb_drags_and_track_clicks() {
+        let _context = lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        // Scrollbars are declared from the scroll container of the previous layout
+        list_frame(&mut clay);
+        list_frame(&mut clay);
+        assert_eq!(clay.dropped_scrollbars(), 0);
+
+        // The track runs from 2 to 98 at x 90 to 98, the thumb is 19.2 long at its start. Moving
+        // the thumb by half of its 76.8 travel scrolls by half of the 400 scroll range.
+        clay.pointer_state(Vector2::new(94.0, 5.0), true);
+        clay.pointer_state(Vector2::new(94.0, 43.4), true);
+        assert_eq!(list(&clay).offset(), Vector2::new(0.0, -200.0));
+        clay.pointer_state(Vector2::new(94.0, 43.4), false);
+        list_frame(&mut clay);
+
+        // Pressing the track below the thumb pages down by the viewport
+        clay.pointer_state(Vector2::new(94.0, 90.0), true);
+        assert_eq!(list(&clay).offset(), Vector2::new(0.0, -300.0));
+        // Holding the pointer down doesn't page again
+        clay.pointer_state(Vector2::new(94.0, 90.0), true);
+        assert_eq!(list(&clay).offset(), Vector2::new(0.0, -300.0));
+        clay.pointer_state(Vector2::new(94.0, 90.0), false);
+        list_frame(&mut clay);
+
+        // And above it pages up
+        clay.pointer_state(Vector2::new(94.0, 10.0), true);
+        assert_eq!(list(&clay).offset(), Vector2::new(0.0, -200.0));
+    }
+
+    #[test]
+    fn scrollbars_past_the_limit_are_counted() {
+        let _context = lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        let frame = |clay: &mut Clay| {
+            let mut scope = clay.begin::<(), ()>();
+            for index in 0..MAX_SCROLLBARS + 2 {
+                let mut container = Declaration::new();
+                container
+                    .id(scope.id_index("container", index as u32))
+                    .layout()
+                    .width(Sizing::Fixed(20.0))
+                    .height(Sizing::Fixed(20.0))
+                    .end()
+                    .clip(false, true, Vector2::default())
+                    .scrollbar();
+                scope.with(&container, |scope| {
+                    let mut content = Declaration::new();
+                    content
+                        .layout()
+                        .width(Sizing::Fixed(20.0))
+                        .height(Sizing::Fixed(40.0));
+                    scope.with(&content, |_| {});
+                });
+            }
+            let _commands = scope.end();
+        };
+        frame(&mut clay);
+        frame(&mut clay);
+        assert_eq!(clay.dropped_scrollbars(), 2);
+    }
+
+    #[test]
+    fn offsets_are_clamped_and_elements_scrolled_into_view() {
+        let _context = lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        list_frame(&mut clay);
+        list_frame(&mut clay);
+
+        list(&clay).set_offset(Vector2::new(-50.0, -1000.0));
+        assert_eq!(list(&clay).offset(), Vector2::new(0.0, -400.0));
+        list(&clay).set_offset(Vector2::new(0.0, 50.0));
+        assert_eq!(list(&clay).offset(), Vector2::new(0.0, 0.0));
+
+        let row = |index| Id::new_index("row", index);
+        assert!(list(&clay).scroll_to_element(row(5), ScrollAlignment::Start));
+        assert_eq!(list(&clay).offset(), Vector2::new(0.0, -250.0));
+        list_frame(&mut clay);
+
+        // Row 1 is above the viewport
+        assert!(list(&clay).scroll_to_element(row(1), ScrollAlignment::Nearest));
+        assert_eq!(list(&clay).offset(), Vector2::new(0.0, -50.0));
+        list_frame(&mut clay);
+
+        // Row 2 is visible already
+        assert!(list(&clay).scroll_to_element(row(2), ScrollAlignment::Nearest));
+        assert_eq!(list(&clay).offset(), Vector2::new(0.0, -50.0));
+
+        assert!(list(&clay).scroll_to_element(row(9), ScrollAlignment::End));
+        assert_eq!(list(&clay).offset(), Vector2::new(0.0, -400.0));
+        list_frame(&mut clay);
+
+        assert!(list(&clay).scroll_to_element(row(4), ScrollAlignment::Center));
+        assert_eq!(list(&clay).offset(), Vector2::new(0.0, -175.0));
+        list_frame(&mut clay);
+
+        // Centering the first row would scroll past the start
+        assert!(list(&clay).scroll_to_element(row(0), ScrollAlignment::Center));
+        assert_eq!(list(&clay).offset(), Vector2::new(0.0, 0.0));
+        assert!(!list(&clay).scroll_to_element(clay.id("missing"), ScrollAlignment::Start));
+    }
+
+    #[test]
+    fn smooth_scrolls_advance_until_the_user_scrolls() {
+        let _context = lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        list_frame(&mut clay);
+        list_frame(&mut clay);
+
+        // Scroll containers are updated once per frame
+        let update = |clay: &mut Clay, delta: Vector2, delta_time: f32| {
+            clay.update_scroll_containers(false, delta, delta_time);
+            list_frame(clay);
+        };
+
+        // The target is clamped, and eased out over the duration
+        list(&clay).smooth_scroll_to(Vector2::new(0.0, -1000.0), 1.0);
+        assert!(list(&clay).is_animating());
+        update(&mut clay, Vector2::default(), 0.5);
+        assert_eq!(list(&clay).offset(), Vector2::new(0.0, -350.0));
+        update(&mut clay, Vector2::default(), 0.5);
+        assert_eq!(list(&clay).offset(), Vector2::new(0.0, -400.0));
+        assert!(!list(&clay).is_animating());
+
+        // Without a duration the offset is set right away
+        list(&clay).smooth_scroll_to(Vector2::new(0.0, -100.0), 0.0);
+        assert_eq!(list(&clay).offset(), Vector2::new(0.0, -100.0));
+        assert!(!list(&clay).is_animating());
+
+        // Scrolling the container with the pointer over it stops the animation
+        list(&clay).smooth_scroll_to(Vector2::new(0.0, -400.0), 1.0);
+        clay.pointer_state(Vector2::new(50.0, 50.0), false);
+        update(&mut clay, Vector2::new(0.0, -1.0), 0.1);
+        assert!(!list(&clay).is_animating());
+        let offset = list(&clay).offset();
+        update(&mut clay, Vector2::default(), 1.0);
+        assert_eq!(list(&clay).offset(), offset);
+
+        // Setting the offset stops it too
+        list(&clay).smooth_scroll_to(Vector2::new(0.0, -400.0), 1.0);
+        list(&clay).set_offset(Vector2::new(0.0, -50.0));
+        update(&mut clay, Vector2::default(), 1.0);
+        assert_eq!(list(&clay).offset(), Vector2::new(0.0, -50.0));
+    }
+
+    #[test]
+    fn smooth_scrolls_past_the_limit_jump_to_their_target() {
+        let _context = lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        let lists = MAX_SCROLL_ANIMATIONS as u32 + 1;
+        lists_frame(&mut clay, lists);
+        lists_frame(&mut clay, lists);
+
+        for list in 0..lists {
+            let container = clay.scroll_container(clay.id_index("list", list)).unwrap();
+            container.smooth_scroll_to(Vector2::new(0.0, -100.0), 1.0);
+        }
+        for list in 0..lists {
+            let container = clay.scroll_container(clay.id_index("list", list)).unwrap();
+            let animating = list < MAX_SCROLL_ANIMATIONS as u32;
+            assert_eq!(container.is_animating(), animating);
+            let offset = if animating { 0.0 } else { -100.0 };
+            assert_eq!(container.offset(), Vector2::new(0.0, offset));
+        }
+    }
+
+    #[test]
+    fn external_scroll_handling_queries_offsets() {
+        let _context = lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        clay.set_external_scroll_handling(true);
+        clay.set_query_scroll_offset_function(|_| Vector2::new(0.0, -120.0));
+        list_frame(&mut clay);
+        list_frame(&mut clay);
+
+        assert_eq!(list(&clay).offset(), Vector2::new(0.0, -120.0));
+        // The renderer moves the content, so clay lays it out unscrolled
+        let row = clay.bounding_box(clay.id_index("row", 5)).unwrap();
+        assert_eq!(row.y, 250.0);
+        // The thumb stays on the track, 0.3 along its 76.8 travel
+        let list_id = clay.id_index("list", 0).id.id;
+        let thumb = clay
+            .bounding_box(scrollbar_id("clay_rs__scrollbar_thumb", true, list_id))
+            .unwrap();
+        assert!((thumb.y - (2.0 + 0.3 * 76.8)).abs() < 0.001);
+    }
+}
@@ -0,0 +1,500 @@
+//! Mouse driven text selection spanning multiple text elements.
+//!
+//! [`TextSelection`] maps the pointer state passed to [`Clay::pointer_state`] onto the final
+//! [`Text`](crate::render_commands::Text) render commands of a frame. Highlights are emitted as
+//! rectangle render commands, and the selected string can be copied with
+//! [`TextSelection::selected_text`].
+
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    color::Color,
+    math::{BoundingBox, Dimensions, Vector2},
+    render_commands::{Rectangle, RenderCommand, RenderCommandConfig},
+    text::TextConfig,
+    Clay,
+};
+
+/// A caret position inside the text of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectionPoint {
+    /// Id of the text render command (a single line of a text element) the caret is in.
+    pub command_id: u32,
+    /// Byte offset into the text of that render command.
+    pub offset: usize,
+}
+
+/// A line of text taken from a `Text` render command.
+struct TextRun {
+    id: u32,
+    z_index: i16,
+    bounding_box: BoundingBox,
+    text: String,
+    config: TextConfig,
+}
+
+/// Tracks a text selection across frames.
+///
+/// Call [`TextSelection::update`] once per frame with the render commands returned by
+/// [`ClayLayoutScope::end`](crate::ClayLayoutScope::end), then render the commands returned by
+/// [`TextSelection::with_highlights`].
+///
+/// The text runs of a selection follow the order of the render commands, not of the elements in
+/// the layout. Clay emits floating elements apart from the rest of the layout, ordered by their
+/// z-index, so a selection spanning into or out of a floating element covers the text between
+/// them in render command order.
+pub struct TextSelection {
+    /// The color of the highlight rectangles.
+    pub color: Color,
+    anchor: Option<SelectionPoint>,
+    focus: Option<SelectionPoint>,
+    dragging: bool,
+    was_down: bool,
+    runs: Vec<TextRun>,
+    highlights: Vec<(u32, i16, BoundingBox)>,
+    scale_factor: f32,
+}
+
+impl TextSelection {
+    /// Creates an empty selection highlighted with `color`.
+    pub fn new(color: Color) -> Self {
+        Self {
+            color,
+            anchor: None,
+            focus: None,
+            dragging: false,
+            was_down: false,
+            runs: Vec::new(),
+            highlights: Vec::new(),
+            scale_factor: 1.0,
+        }
+    }
+
+    /// Updates the selection from the pointer state of `clay` and the render commands of the
+    /// current frame. Text is measured with the measure function set on `clay`.
+    ///
+    /// Returns `false` without updating the selection if the measure function wasn't set from
+    /// Rust, like with
+    /// [`Clay::set_measure_text_function_unsafe`](crate::Clay::set_measure_text_function_unsafe).
+    /// Use [`TextSelection::update_with`] with a measure function then.
+    pub fn update<ImageElementData, CustomElementData, UserData>(
+        &mut self,
+        clay: &Clay,
+        commands: &[RenderCommand<'_, ImageElementData, CustomElementData, UserData>],
+    ) -> bool {
+        if clay.measure_text_function.is_none() {
+            return false;
+        }
+        self.update_with(
+            clay.pointer_position(),
+            clay.is_pointer_down(),
+            commands,
+            |text, config| clay.measure_text(text, config).unwrap_or_default(),
+        );
+        true
+    }
+
+    /// Same as [`TextSelection::update`], but with an explicit pointer state and measure
+    /// function.
+    pub fn update_with<ImageElementData, CustomElementData, UserData>(
+        &mut self,
+        pointer_position: Vector2,
+        is_pointer_down: bool,
+        commands: &[RenderCommand<'_, ImageElementData, CustomElementData, UserData>],
+        measure: impl Fn(&str, &TextConfig) -> Dimensions,
+    ) {
+        self.runs.clear();
+        for command in commands {
+            self.scale_factor = command.scale_factor;
+            if let RenderCommandConfig::Text(text) = &command.config {
+                self.runs.push(TextRun {
+                    id: command.id,
+                    z_index: command.z_index,
+                    bounding_box: command.bounding_box,
+                    text: String::from(text.text),
+                    config: TextConfig {
+                        color: text.color,
+                        font_id: text.font_id,
+                        font_size: text.font_size,
+                        letter_spacing: text.letter_spacing,
+                        line_height: text.line_height,
+                        ..Default::default()
+                    },
+                });
+            }
+        }
+
+        // Drop the selection if the text it points into disappeared.
+        if self.anchor.is_some_and(|p| self.run_index(p).is_none())
+            || self.focus.is_some_and(|p| self.run_index(p).is_none())
+        {
+            self.clear();
+        }
+
+        let pressed = is_pointer_down && !self.was_down;
+        self.was_down = is_pointer_down;
+
+        if pressed {
+            self.anchor = self.hit_test(pointer_position, true, &measure);
+            self.focus = self.anchor;
+            self.dragging = self.anchor.is_some();
+        } else if is_pointer_down && self.dragging {
+            if let Some(point) = self.hit_test(pointer_position, false, &measure) {
+                self.focus = Some(point);
+            }
+        } else if !is_pointer_down {
+            self.dragging = false;
+        }
+
+        self.highlights.clear();
+        if let Some((start, end)) = self.ordered_range() {
+            for index in start.0..=end.0 {
+                let run = &self.runs[index];
+                let from = if index == start.0 { start.1 } else { 0 };
+                let to = if index == end.0 { end.1 } else { run.text.len() };
+                if from == to {
+                    continue;
+                }
+                let x = run.bounding_box.x + measure(&run.text[..from], &run.config).width;
+                let width = measure(&run.text[from..to], &run.config).width;
+                self.highlights.push((
+                    run.id,
+                    run.z_index,
+                    BoundingBox::new(x, run.bounding_box.y, width, run.bounding_box.height),
+                ));
+            }
+        }
+    }
+
+    /// Removes the selection.
+    pub fn clear(&mut self) {
+        self.anchor = None;
+        self.focus = None;
+        self.dragging = false;
+    }
+
+    /// Returns if a pointer drag is currently extending the selection.
+    pub fn is_dragging(&self) -> bool {
+        self.dragging
+    }
+
+    /// Returns the start and end of the selection in document order, or `None` if nothing is
+    /// selected.
+    pub fn range(&self) -> Option<(SelectionPoint, SelectionPoint)> {
+        let (start, end) = self.ordered_range()?;
+        Some((
+            SelectionPoint {
+                command_id: self.runs[start.0].id,
+                offset: start.1,
+            },
+            SelectionPoint {
+                command_id: self.runs[end.0].id,
+                offset: end.1,
+            },
+        ))
+    }
+
+    /// Returns the selected text in document order, or `None` if nothing is selected.
+    ///
+    /// Lines coming from different rows of the layout are separated by `\n`.
+    pub fn selected_text(&self) -> Option<String> {
+        let (start, end) = self.ordered_range()?;
+        let mut result = String::new();
+        for index in start.0..=end.0 {
+            let run = &self.runs[index];
+            if index > start.0 {
+                let previous = &self.runs[index - 1].bounding_box;
+                if run.bounding_box.y >= previous.y + previous.height / 2.0 {
+                    result.push('\n');
+                }
+            }
+            let from = if index == start.0 { start.1 } else { 0 };
+            let to = if index == end.0 { end.1 } else { run.text.len() };
+            result.push_str(&run.text[from..to]);
+        }
+        Some(result)
+    }
+
+    /// Returns the highlight rectangles of the current selection as render commands.
+    pub fn highlight_commands<'a, ImageElementData: 'a, CustomElementData: 'a, UserData: 'a>(
+        &self,
+    ) -> impl Iterator<Item = RenderCommand<'a, ImageElementData, CustomElementData, UserData>> + '_
+    {
+        self.highlights
+            .iter()
+            .map(|(id, z_index, bounding_box)| self.highlight_command(*id, *z_index, *bounding_box))
+    }
+
+    /// Inserts the highlight rectangles right before the text they cover, so they are drawn
+    /// behind the text.
+    pub fn with_highlights<'a, ImageElementData, CustomElementData, UserData>(
+        &self,
+        commands: impl IntoIterator<
+            Item = RenderCommand<'a, ImageElementData, CustomElementData, UserData>,
+        >,
+    ) -> Vec<RenderCommand<'a, ImageElementData, CustomElementData, UserData>> {
+        let mut result = Vec::new();
+        for command in commands {
+            if let RenderCommandConfig::Text(_) = command.config {
+                for (id, z_index, bounding_box) in &self.highlights {
+                    if *id == command.id {
+                        result.push(self.highlight_command(*id, *z_index, *bounding_box));
+                    }
+                }
+            }
+            result.push(command);
+        }
+        result
+    }
+
+    fn highlight_command<'a, ImageElementData, CustomElementData, UserData>(
+        &self,
+        id: u32,
+        z_index: i16,
+        bounding_box: BoundingBox,
+    ) -> RenderCommand<'a, ImageElementData, CustomElementData, UserData> {
+        RenderCommand {
+            bounding_box,
+            config: RenderCommandConfig::Rectangle(Rectangle {
+                color: self.color,
+                corner_radii: Default::default(),
+            }),
+            id,
+            z_index,
+            user_data: None,
+            scale_factor: self.scale_factor,
+        }
+    }
+
+    /// Returns the run `point` is in, or `None` if its offset is past the end of the text or
+    /// inside a character, which happens when the text of a command changed.
+    fn run_index(&self, point: SelectionPoint) -> Option<usize> {
+        self.runs
+            .iter()
+            .position(|run| run.id == point.command_id && run.text.is_char_boundary(point.offset))
+    }
+
+    /// Returns `((run, offset), (run, offset))` in document order, or `None` if the selection is
+    /// empty.
+    fn ordered_range(&self) -> Option<((usize, usize), (usize, usize))> {
+        let anchor = (self.run_index(self.anchor?)?, self.anchor?.offset);
+        let focus = (self.run_index(self.focus?)?, self.focus?.offset);
+        match anchor.cmp(&focus) {
+            core::cmp::Ordering::Less => Some((anchor, focus)),
+            core::cmp::Ordering::Greater => Some((focus, anchor)),
+            core::cmp::Ordering::Equal => None,
+        }
+    }
+
+    /// Finds the caret position under `position`. If `strict` is false the closest run is used
+    /// when the position is not inside any text.
+    fn hit_test(
+        &self,
+        position: Vector2,
+        strict: bool,
+        measure: &impl Fn(&str, &TextConfig) -> Dimensions,
+    ) -> Option<SelectionPoint> {
+        let distance = |bb: &BoundingBox| {
+            let dx = (bb.x - position.x).max(position.x - bb.x - bb.width).max(0.0);
+            let dy = (bb.y - position.y).max(position.y - bb.y - bb.height).max(0.0);
+            (dy, dx)
+        };
+
+        let (index, (dy, dx)) = self
+            .runs
+            .iter()
+            .map(|run| distance(&run.bounding_box))
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal))?;
+
+        if strict && (dy > 0.0 || dx > 0.0) {
+            return None;
+        }
+
+        let run = &self.runs[index];
+        Some(SelectionPoint {
+            command_id: run.id,
+            offset: caret_offset(&run.text, position.x - run.bounding_box.x, |text| {
+                measure(text, &run.config).width
+            }),
+        })
+    }
+}
+
+/// Returns the char boundary of `text` closest to `x`, measuring prefixes with `width`.
+fn caret_offset(text: &str, x: f32, width: impl Fn(&str) -> f32) -> usize {
+    let boundaries: Vec<usize> = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(core::iter::once(text.len()))
+        .collect();
+
+    // Prefix widths grow monotonically, so binary search for the last boundary left of `x`.
+    let mut low = 0;
+    let mut high = boundaries.len() - 1;
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        if width(&text[..boundaries[mid]]) <= x {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    match boundaries.get(low + 1) {
+        Some(&next) => {
+            let left = width(&text[..boundaries[low]]);
+            let right = width(&text[..next]);
+            if x - left > right - x {
+                next
+            } else {
+                boundaries[low]
+            }
+        }
+        None => boundaries[low],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_commands::Text;
+
+    const CHAR_WIDTH: f32 = 10.0;
+
+    fn measure(text: &str, _config: &TextConfig) -> Dimensions {
+        Dimensions::new(text.chars().count() as f32 * CHAR_WIDTH, 20.0)
+    }
+
+    fn text_command(id: u32, text: &str, x: f32, y: f32) -> RenderCommand<'_, (), ()> {
+        RenderCommand {
+            bounding_box: BoundingBox::new(x, y, text.len() as f32 * CHAR_WIDTH, 20.0),
+            config: RenderCommandConfig::Text(Text {
+                text,
+                color: Color::rgb(0., 0., 0.),
+                font_id: 0,
+                font_size: 16,
+                letter_spacing: 0,
+                line_height: 20,
+            }),
+            id,
+            z_index: 0,
+            user_data: None,
+            scale_factor: 1.0,
+        }
+    }
+
+    #[test]
+    fn caret_offset_picks_closest_boundary() {
+        let width = |text: &str| text.chars().count() as f32 * CHAR_WIDTH;
+        assert_eq!(caret_offset("hello", -5.0, width), 0);
+        assert_eq!(caret_offset("hello", 14.0, width), 1);
+        assert_eq!(caret_offset("hello", 16.0, width), 2);
+        assert_eq!(caret_offset("hello", 500.0, width), 5);
+        assert_eq!(caret_offset("héllo", 16.0, width), 3);
+    }
+
+    #[test]
+    fn drag_selects_across_text_elements() {
+        let commands = [
+            text_command(1, "first line", 0.0, 0.0),
+            text_command(2, "second line", 0.0, 20.0),
+        ];
+        let mut selection = TextSelection::new(Color::rgba(0., 0., 255., 100.));
+
+        selection.update_with(Vector2::new(60.0, 10.0), true, &commands, measure);
+        selection.update_with(Vector2::new(60.0, 30.0), true, &commands, measure);
+        selection.update_with(Vector2::new(60.0, 30.0), false, &commands, measure);
+
+        assert!(!selection.is_dragging());
+        assert_eq!(selection.selected_text().as_deref(), Some("line\nsecond"));
+
+        let highlighted = selection.with_highlights(commands.iter().cloned());
+        assert_eq!(highlighted.len(), 4);
+        assert!(matches!(highlighted[0].config, RenderCommandConfig::Rectangle(_)));
+        assert_eq!(highlighted[0].bounding_box, BoundingBox::new(60.0, 0.0, 40.0, 20.0));
+        assert_eq!(highlighted[2].bounding_box, BoundingBox::new(0.0, 20.0, 60.0, 20.0));
+    }
+
+    #[test]
+    fn click_outside_text_clears_selection() {
+        let commands = [text_command(1, "some text", 0.0, 0.0)];
+        let mut selection = TextSelection::new(Color::rgba(0., 0., 255., 100.));
+
+        selection.update_with(Vector2::new(0.0, 10.0), true, &commands, measure);
+        selection.update_with(Vector2::new(40.0, 10.0), false, &commands, measure);
+        selection.update_with(Vector2::new(40.0, 10.0), true, &commands, measure);
+        assert!(selection.selected_text().is_none());
+
+        selection.update_with(Vector2::new(0.0, 10.0), false, &commands, measure);
+        selection.update_with(Vector2::new(0.0, 10.0), true, &commands, measure);
+        selection.update_with(Vector2::new(40.0, 10.0), true, &commands, measure);
+        assert_eq!(selection.selected_text().as_deref(), Some("some"));
+
+        selection.update_with(Vector2::new(0.0, 100.0), false, &commands, measure);
+        selection.update_with(Vector2::new(0.0, 100.0), true, &commands, measure);
+        assert!(selection.range().is_none());
+    }
+    #[test]
+    fn selection_inside_changed_text_is_cleared() {
+        let mut selection = TextSelection::new(Color::rgba(0., 0., 255., 100.));
+        let commands = [text_command(1, "abcd", 0.0, 0.0)];
+        selection.update_with(Vector2::new(10.0, 10.0), true, &commands, measure);
+        selection.update_with(Vector2::new(30.0, 10.0), true, &commands, measure);
+        assert_eq!(selection.selected_text().as_deref(), Some("bc"));
+
+        // Byte 1 and 3 are inside characters of the new text
+        let commands = [text_command(1, "éééé", 0.0, 0.0)];
+        selection.update_with(Vector2::new(30.0, 10.0), false, &commands, measure);
+        assert!(selection.range().is_none());
+        assert!(selection.selected_text().is_none());
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod layout_tests {
+    use super::*;
+    use crate::{bindings::*, tests::lock_context};
+
+    unsafe extern "C" fn measure_ten_per_byte(
+        text: Clay_StringSlice,
+        _config: *mut Clay_TextElementConfig,
+        _user_data: *mut core::ffi::c_void,
+    ) -> Clay_Dimensions {
+        Clay_Dimensions {
+            width: text.length as f32 * 10.0,
+            height: 20.0,
+        }
+    }
+
+    /// Drags over "hello world" from `from` to `to` along its line
+    fn drag(clay: &mut Clay, selection: &mut TextSelection, from: f32, to: f32) -> bool {
+        let mut updated = true;
+        for (x, is_down) in [(from, true), (to, true), (to, false)] {
+            clay.pointer_state(Vector2::new(x, 10.0), is_down);
+            let mut scope = clay.begin::<(), ()>();
+            scope.text("hello world", TextConfig::new().end());
+            let commands: Vec<_> = scope.end().collect();
+            updated &= selection.update(&scope, &commands);
+        }
+        updated
+    }
+
+    #[test]
+    fn update_needs_a_measure_function_set_from_rust() {
+        let _context = lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        let mut selection = TextSelection::new(Color::rgba(0., 0., 255., 100.));
+
+        // Every caret would be at the start of the text
+        unsafe {
+            Clay::set_measure_text_function_unsafe(measure_ten_per_byte, core::ptr::null_mut())
+        };
+        assert!(!drag(&mut clay, &mut selection, 0.0, 50.0));
+        assert!(selection.range().is_none());
+
+        clay.set_measure_text_function(|text, _| Dimensions::new(text.len() as f32 * 10.0, 20.0));
+        assert!(drag(&mut clay, &mut selection, 0.0, 50.0));
+        assert_eq!(selection.selected_text().as_deref(), Some("hello"));
+    }
+}
@@ -22,8 +22,31 @@ pub enum TextAlignment {
     Right = Clay_TextAlignment_CLAY_TEXT_ALIGN_RIGHT,
 }
 
+/// Defines how text that doesn't fit its element or [`max_lines`](TextConfig::max_lines) is cut.
+///
+/// The ellipsis modes measure text with the function set from Rust. If it was set with
+/// [`Clay::set_measure_text_function_unsafe`](crate::Clay::set_measure_text_function_unsafe), the
+/// lines past `max_lines` are removed but the text isn't ellipsized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextOverflow {
+    /// Cuts the text at the edge of the element.
+    Clip,
+    /// Replaces the start of the text with an ellipsis. The text is shown on a single line.
+    EllipsisStart,
+    /// Replaces the middle of the text with an ellipsis. The text is shown on a single line.
+    EllipsisMiddle,
+    /// Replaces the end of the last visible line with an ellipsis.
+    EllipsisEnd,
+}
+
+/// The character appended or inserted by the ellipsis [`TextOverflow`] modes.
+pub const ELLIPSIS: &str = "…";
+
 pub struct TextElementConfig {
-    inner: *mut Clay_TextElementConfig,
+    pub(crate) inner: *mut Clay_TextElementConfig,
+    /// Only needed to truncate text, which requires `alloc`
+    #[cfg_attr(not(feature = "alloc"), allow(dead_code))]
+    pub(crate) config: TextConfig,
 }
 
 impl From<TextElementConfig> for *mut Clay_TextElementConfig {
@@ -50,6 +73,10 @@ pub struct TextConfig {
     pub wrap_mode: TextElementConfigWrapMode,
     /// The alignment of the text.
     pub alignment: TextAlignment,
+    /// The maximum number of lines shown, `0` means unlimited.
+    pub max_lines: u16,
+    /// Defines how overflowing text is cut.
+    pub overflow: TextOverflow,
 }
 
 impl TextConfig {
@@ -107,11 +134,40 @@ impl TextConfig {
         self
     }
 
+    /// Sets the maximum number of lines shown. Lines after it are removed and the
+    /// [`overflow`](TextConfig::overflow) mode is applied to the last visible line.
+    ///
+    /// Truncation requires the `std` feature.
+    #[inline]
+    pub fn max_lines(&mut self, max_lines: u16) -> &mut Self {
+        self.max_lines = max_lines;
+        self
+    }
+
+    /// Sets how overflowing text is cut.
+    ///
+    /// Truncation requires the `std` feature.
+    #[inline]
+    pub fn overflow(&mut self, overflow: TextOverflow) -> &mut Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Returns if the text needs to be truncated after layout.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub(crate) fn truncates(&self) -> bool {
+        self.max_lines > 0 || self.overflow != TextOverflow::Clip
+    }
+
     /// Finalizes the text configuration and stores it in memory.
     #[inline]
     pub fn end(&self) -> TextElementConfig {
         let memory = unsafe { Clay__StoreTextElementConfig((*self).into()) };
-        TextElementConfig { inner: memory }
+        TextElementConfig {
+            inner: memory,
+            config: *self,
+        }
     }
 }
 
@@ -125,6 +181,8 @@ impl Default for TextConfig {
             line_height: 0,
             wrap_mode: TextElementConfigWrapMode::Words,
             alignment: TextAlignment::Left,
+            max_lines: 0,
+            overflow: TextOverflow::Clip,
         }
     }
 }
@@ -156,6 +214,93 @@ impl From<Clay_TextElementConfig> for TextConfig {
                 core::mem::transmute::<u8, TextElementConfigWrapMode>(value.wrapMode)
             },
             alignment: unsafe { core::mem::transmute::<u8, TextAlignment>(value.textAlignment) },
+            ..Default::default()
         }
     }
 }
+
+/// Shortens `text` with an [`ELLIPSIS`] so it fits in `max_width`, measuring with `measure`.
+///
+/// The text is treated as a single line. [`TextOverflow::Clip`] returns the text unchanged.
+#[cfg(feature = "alloc")]
+pub fn ellipsize(
+    text: &str,
+    max_width: f32,
+    overflow: TextOverflow,
+    measure: impl Fn(&str) -> f32,
+) -> alloc::string::String {
+    let text = text.trim();
+    if overflow == TextOverflow::Clip || measure(text) <= max_width {
+        return text.into();
+    }
+
+    let chars: alloc::vec::Vec<(usize, char)> = text.char_indices().collect();
+    let candidate = |kept: usize| -> alloc::string::String {
+        let split = |count: usize| chars.get(count).map_or(text.len(), |(i, _)| *i);
+        match overflow {
+            TextOverflow::EllipsisStart => {
+                let start = split(chars.len() - kept);
+                [ELLIPSIS, text[start..].trim_start()].concat()
+            }
+            TextOverflow::EllipsisMiddle => {
+                let front = split(kept.div_ceil(2));
+                let back = split(chars.len() - kept / 2);
+                [text[..front].trim_end(), ELLIPSIS, text[back..].trim_start()].concat()
+            }
+            _ => [text[..split(kept)].trim_end(), ELLIPSIS].concat(),
+        }
+    };
+
+    // Find the largest number of kept characters that still fits.
+    let mut low = 0;
+    let mut high = chars.len();
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        if measure(&candidate(mid)) <= max_width {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    candidate(low)
+}
+
+/// Text measurement statistics of a single frame, see [`Clay::measure_text_stats`](crate::Clay::measure_text_stats).
+///
+/// Clay doesn't count its cache, so apart from `measure_calls` these are derived from the calls of
+/// the measure text function and the size of the cache, and are approximate. Only text declared
+/// through the layout scope is counted, text of the debug view is not.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MeasureTextStats {
+    /// How often clay called the measure text function for this frame
+    pub measure_calls: u32,
+    /// Text elements whose measurement was found in the cache, the declared text elements that
+    /// weren't misses
+    pub cache_hits: u32,
+    /// Text elements that had to be measured, counted by the space character clay measures
+    /// first for each of them
+    pub cache_misses: u32,
+    /// Cache entries removed because their text wasn't used for a few frames, the misses that
+    /// didn't grow the cache
+    pub evictions: u32,
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+
+    fn measure(text: &str) -> f32 {
+        text.chars().count() as f32 * 10.0
+    }
+
+    #[test]
+    fn ellipsize_modes() {
+        let text = "Hello wonderful world";
+        assert_eq!(ellipsize(text, 1000.0, TextOverflow::EllipsisEnd, measure), text);
+        assert_eq!(ellipsize(text, 100.0, TextOverflow::Clip, measure), text);
+        assert_eq!(ellipsize(text, 100.0, TextOverflow::EllipsisEnd, measure), "Hello won…");
+        assert_eq!(ellipsize(text, 100.0, TextOverflow::EllipsisStart, measure), "…ful world");
+        assert_eq!(ellipsize(text, 100.0, TextOverflow::EllipsisMiddle, measure), "Hello…orld");
+        assert_eq!(ellipsize(text, 5.0, TextOverflow::EllipsisEnd, measure), "…");
+    }
+}
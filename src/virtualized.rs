@@ -0,0 +1,369 @@
+//! Lists and grids that only declare the items inside the viewport of their scroll container.
+//!
+//! The items before and after the visible ones are replaced by spacer elements of the same size,
+//! so the content dimensions of the scroll container, and with them scrollbars, stay correct.
+
+use crate::{
+    bindings::*,
+    id::Id,
+    layout::{LayoutDirection, Sizing},
+    scroll::scroll_container_data,
+    ClayLayoutScope, Declaration,
+};
+
+/// Size of the items of a virtualized list, or of the rows of a virtualized grid, along the
+/// scroll axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ItemExtent {
+    /// Every item is laid out with exactly this size.
+    Fixed(f32),
+    /// Items fit their content. The spacers use the average size of the items measured in the
+    /// last layout, or this size if none were.
+    Estimated(f32),
+}
+
+/// The rows declared for the viewport, and the size of the spacers before and after them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct VisibleRows {
+    first: usize,
+    end: usize,
+    leading: f32,
+    trailing: f32,
+}
+
+/// Finds the rows of size `extent`, separated by `gap`, that overlap the viewport of size
+/// `viewport` scrolled by `scroll`.
+fn visible_rows(count: usize, extent: f32, gap: f32, scroll: f32, viewport: f32) -> VisibleRows {
+    let step = (extent + gap).max(1.0);
+    let first = ((scroll.max(0.0) / step) as usize).min(count);
+    // Rounds up without `f32::ceil`, which needs `std`
+    let last = (scroll.max(0.0) + viewport) / step;
+    let end = last as usize + usize::from(last > (last as usize) as f32);
+    let end = end.clamp(first, count);
+
+    // The spacers are separated from the rows by `gap` as well
+    let spacer = |rows: usize| {
+        if rows == 0 {
+            0.0
+        } else {
+            rows as f32 * step - gap
+        }
+    };
+    VisibleRows {
+        first,
+        end,
+        leading: spacer(first),
+        trailing: spacer(count - end),
+    }
+}
+
+impl<
+        'render,
+        'clay: 'render,
+        ImageElementData: 'render,
+        CustomElementData: 'render,
+        UserData: 'render,
+    > ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>
+{
+    /// Creates a scroll container from `declaration` with `item_count` items, but only calls
+    /// `f` for the items inside its viewport.
+    ///
+    /// Items are stacked in the layout direction of `declaration`, which should
+    /// [`clip`](Declaration::clip) that axis.
+    pub fn virtual_list<F>(
+        &mut self,
+        declaration: &Declaration<'render, ImageElementData, CustomElementData, UserData>,
+        item_count: usize,
+        extent: ItemExtent,
+        f: F,
+    ) where
+        F: FnMut(
+            &mut ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>,
+            usize,
+        ),
+    {
+        self.virtual_grid(declaration, item_count, 1, extent, f);
+    }
+
+    /// Like [`ClayLayoutScope::virtual_list`], but places `columns` equally sized items in each
+    /// row. Rows are stacked in the layout direction of `declaration`.
+    pub fn virtual_grid<F>(
+        &mut self,
+        declaration: &Declaration<'render, ImageElementData, CustomElementData, UserData>,
+        item_count: usize,
+        columns: usize,
+        row_extent: ItemExtent,
+        mut f: F,
+    ) where
+        F: FnMut(
+            &mut ClayLayoutScope<'clay, 'render, ImageElementData, CustomElementData, UserData>,
+            usize,
+        ),
+    {
+        let columns = columns.max(1);
+        let rows = item_count.div_ceil(columns);
+        let layout = declaration.inner.layout;
+        let vertical = layout.layoutDirection == LayoutDirection::TopToBottom as _;
+        let gap = layout.childGap as f32;
+        let root = self.clay.layout_dimensions.get();
+
+        // Sizes along the scroll axis and across it
+        let sized = |declaration: &mut Declaration<
+            'render,
+            ImageElementData,
+            CustomElementData,
+            UserData,
+        >,
+                     along: Sizing,
+                     across: Sizing| {
+            let (width, height) = if vertical {
+                (across, along)
+            } else {
+                (along, across)
+            };
+            declaration.layout().width(width).height(height).end();
+        };
+        let row_direction = if vertical {
+            LayoutDirection::LeftToRight
+        } else {
+            LayoutDirection::TopToBottom
+        };
+
+        self.with(declaration, |scope| {
+            let container = unsafe { Clay__GetOpenElementId() };
+            let row_id = |row: usize| Id {
+                id: unsafe {
+                    Clay__HashStringWithOffset("clay_rs__virtual_row".into(), row as u32, container)
+                },
+            };
+
+            // Before the container was laid out once, the root is the best guess for its size
+            let (scroll, viewport) = match scroll_container_data(container) {
+                Some(data)
+                    if data.scrollContainerDimensions.width > 0.0
+                        && data.scrollContainerDimensions.height > 0.0 =>
+                {
+                    let offset = unsafe { *data.scrollPosition };
+                    let dimensions = data.scrollContainerDimensions;
+                    if vertical {
+                        (-offset.y - layout.padding.top as f32, dimensions.height)
+                    } else {
+                        (-offset.x - layout.padding.left as f32, dimensions.width)
+                    }
+                }
+                _ => (0.0, if vertical { root.height } else { root.width }),
+            };
+
+            let (mut extent, along) = match row_extent {
+                ItemExtent::Fixed(extent) => (extent, Sizing::Fixed(extent)),
+                ItemExtent::Estimated(extent) => (extent, Sizing::Fit(0.0, f32::MAX)),
+            };
+            let mut visible = visible_rows(rows, extent, gap, scroll, viewport);
+
+            if let ItemExtent::Estimated(_) = row_extent {
+                let (total, measured) = (visible.first..visible.end)
+                    .filter_map(|row| scope.clay.bounding_box(row_id(row)))
+                    .fold((0.0, 0), |(total, measured), bounds| {
+                        let size = if vertical {
+                            bounds.height
+                        } else {
+                            bounds.width
+                        };
+                        (total + size, measured + 1)
+                    });
+                if measured > 0 {
+                    extent = total / measured as f32;
+                    visible = visible_rows(rows, extent, gap, scroll, viewport);
+                }
+            }
+
+            let spacer = |scope: &mut Self, size: f32| {
+                if size > 0.0 {
+                    let mut declaration = Declaration::new();
+                    sized(&mut declaration, Sizing::Fixed(size), Sizing::Fixed(0.0));
+                    scope.with(&declaration, |_| {});
+                }
+            };
+
+            spacer(scope, visible.leading);
+            for row in visible.first..visible.end {
+                let mut row_declaration = Declaration::new();
+                row_declaration
+                    .id(row_id(row))
+                    .layout()
+                    .direction(row_direction)
+                    .child_gap(layout.childGap);
+                sized(&mut row_declaration, along, Sizing::Grow(0.0, f32::MAX));
+
+                scope.with(&row_declaration, |scope| {
+                    if columns == 1 {
+                        f(scope, row);
+                        return;
+                    }
+
+                    let mut cell = Declaration::new();
+                    cell.layout()
+                        .width(Sizing::Grow(0.0, f32::MAX))
+                        .height(Sizing::Grow(0.0, f32::MAX));
+                    for index in row * columns..(row + 1) * columns {
+                        // Empty cells keep the columns of the last row aligned
+                        scope.with(&cell, |scope| {
+                            if index < item_count {
+                                f(scope, index);
+                            }
+                        });
+                    }
+                });
+            }
+            spacer(scope, visible.trailing);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spacers_keep_content_size() {
+        // 100 rows of 20 with a gap of 5 take 100 * 25 - 5
+        let visible = visible_rows(100, 20.0, 5.0, 260.0, 100.0);
+        assert_eq!((visible.first, visible.end), (10, 15));
+        let declared = (visible.end - visible.first) as f32 * 25.0 - 5.0;
+        assert_eq!(
+            visible.leading + 5.0 + declared + 5.0 + visible.trailing,
+            2495.0
+        );
+
+        let top = visible_rows(100, 20.0, 5.0, 0.0, 100.0);
+        assert_eq!((top.first, top.end, top.leading), (0, 4, 0.0));
+
+        let past_end = visible_rows(3, 20.0, 0.0, 500.0, 100.0);
+        assert_eq!(
+            (past_end.first, past_end.end, past_end.trailing),
+            (3, 3, 0.0)
+        );
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod layout_tests {
+    use super::*;
+    use crate::{
+        math::{Dimensions, Vector2},
+        tests::lock_context,
+        Clay,
+    };
+
+    /// Lays out `count` items of 20 high in `columns` columns of a 100 by 100 list with id
+    /// "list", returning the indices of the declared items
+    fn list_frame(clay: &mut Clay, count: usize, columns: usize, extent: ItemExtent) -> Vec<usize> {
+        let list = clay.id("list");
+        let offset = clay
+            .scroll_container(list)
+            .map_or(Vector2::default(), |container| container.offset());
+        let mut declared = Vec::new();
+
+        let mut scope = clay.begin::<(), ()>();
+        let mut declaration = Declaration::new();
+        declaration
+            .id(list)
+            .layout()
+            .width(Sizing::Fixed(100.0))
+            .height(Sizing::Fixed(100.0))
+            .direction(LayoutDirection::TopToBottom)
+            .end()
+            .clip(false, true, offset);
+        scope.virtual_grid(&declaration, count, columns, extent, |scope, index| {
+            declared.push(index);
+            let mut item = Declaration::new();
+            item.id(scope.id_index("item", index as u32))
+                .layout()
+                .width(Sizing::Grow(0.0, f32::MAX))
+                .height(Sizing::Fixed(20.0));
+            scope.with(&item, |_| {});
+        });
+        let _commands = scope.end();
+        declared
+    }
+
+    fn content_height(clay: &Clay) -> f32 {
+        let list = clay.scroll_container(clay.id("list")).unwrap();
+        list.content_dimensions().height
+    }
+
+    #[test]
+    fn virtual_lists_declare_the_visible_rows() {
+        let _context = lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        let extent = ItemExtent::Fixed(20.0);
+
+        // The first layout fills the root, as the list wasn't laid out yet
+        assert_eq!(
+            list_frame(&mut clay, 100, 1, extent),
+            (0..30).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            list_frame(&mut clay, 100, 1, extent),
+            (0..5).collect::<Vec<_>>()
+        );
+        assert_eq!(content_height(&clay), 2000.0);
+
+        let list = clay.scroll_container(clay.id("list")).unwrap();
+        list.set_offset(Vector2::new(0.0, -510.0));
+        assert_eq!(
+            list_frame(&mut clay, 100, 1, extent),
+            (25..31).collect::<Vec<_>>()
+        );
+        // The leading spacer puts the rows where they would be without virtualization
+        let item = clay.bounding_box(clay.id_index("item", 26)).unwrap();
+        assert_eq!(item.y, 10.0);
+        assert_eq!(content_height(&clay), 2000.0);
+    }
+
+    #[test]
+    fn virtual_grids_declare_the_items_of_the_visible_rows() {
+        let _context = lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        let extent = ItemExtent::Fixed(20.0);
+        list_frame(&mut clay, 29, 3, extent);
+        list_frame(&mut clay, 29, 3, extent);
+
+        // Rows 3 to 7 of 10 are visible
+        let list = clay.scroll_container(clay.id("list")).unwrap();
+        list.set_offset(Vector2::new(0.0, -60.0));
+        assert_eq!(
+            list_frame(&mut clay, 29, 3, extent),
+            (9..24).collect::<Vec<_>>()
+        );
+        assert_eq!(content_height(&clay), 200.0);
+        let item = clay.bounding_box(clay.id_index("item", 10)).unwrap();
+        assert_eq!((item.y, item.width), (0.0, 100.0 / 3.0));
+
+        let list = clay.scroll_container(clay.id("list")).unwrap();
+        list.set_offset(Vector2::new(0.0, -100.0));
+        // The last row only has two items
+        assert_eq!(
+            list_frame(&mut clay, 29, 3, extent),
+            (15..29).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn estimated_rows_are_corrected_once_measured() {
+        let _context = lock_context();
+        let mut clay = Clay::new(Dimensions::new(800.0, 600.0));
+        let extent = ItemExtent::Estimated(10.0);
+
+        // 60 rows of 10 fill the root, they turn out to be 20 high
+        assert_eq!(list_frame(&mut clay, 100, 1, extent).len(), 60);
+        assert_eq!(content_height(&clay), 60.0 * 20.0 + 40.0 * 10.0);
+
+        // The spacers use the measured size from then on
+        assert_eq!(
+            list_frame(&mut clay, 100, 1, extent),
+            (0..5).collect::<Vec<_>>()
+        );
+        assert_eq!(content_height(&clay), 2000.0);
+    }
+}